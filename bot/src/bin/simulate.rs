@@ -0,0 +1,178 @@
+//! Plays seeded games between registered strategies and reports how they
+//! went. The same arguments always print the same numbers.
+//!
+//! ```text
+//! cargo run --release -p bot --bin simulate -- --games 1000 --seats normal,easy,easy --rotate
+//! ```
+
+use bot::sim::{games_csv, run, summarize, summary_csv, GameRecord, SimConfig, Summary};
+use bot::strategy_names;
+use game_core::RulePreset;
+use serde::Serialize;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: simulate [--games N] [--seed FIRST] [--preset NAME] \
+[--seats NAME[,NAME...]] [--rotate] [--threads N] [--format json|csv] [--per-game]";
+
+#[derive(Serialize)]
+struct Report<'a> {
+    preset: RulePreset,
+    seats: &'a [String],
+    first_seed: u64,
+    rotate: bool,
+    summary: &'a Summary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    games: Option<&'a [GameRecord]>,
+}
+
+struct Options {
+    preset: RulePreset,
+    config: SimConfig,
+    csv: bool,
+    per_game: bool,
+}
+
+fn parse(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut games = 100u64;
+    let mut first_seed = 0u64;
+    let mut preset = RulePreset::Classic;
+    let mut seats = vec!["easy".to_string()];
+    let mut rotate = false;
+    let mut threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut csv = false;
+    let mut per_game = false;
+
+    let mut args = args.peekable();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--games" => games = value()?.parse().map_err(|_| "bad --games")?,
+            "--seed" => first_seed = value()?.parse().map_err(|_| "bad --seed")?,
+            "--threads" => threads = value()?.parse().map_err(|_| "bad --threads")?,
+            "--preset" => {
+                let name = value()?;
+                preset = RulePreset::ALL
+                    .into_iter()
+                    .find(|preset| format!("{preset:?}").eq_ignore_ascii_case(&name))
+                    .ok_or(format!("unknown preset {name:?}"))?;
+            }
+            "--seats" => seats = value()?.split(',').map(str::to_string).collect(),
+            "--rotate" => rotate = true,
+            "--format" => match value()?.as_str() {
+                "json" => csv = false,
+                "csv" => csv = true,
+                other => return Err(format!("unknown format {other:?}")),
+            },
+            "--per-game" => per_game = true,
+            "--help" | "-h" => return Err(String::new()),
+            other => return Err(format!("unknown argument {other:?}")),
+        }
+    }
+
+    let last_seed = first_seed
+        .checked_add(games)
+        .ok_or("--seed plus --games is past the last seed")?;
+    let rules = preset.rules();
+    // One name fills the table.
+    if seats.len() == 1 {
+        seats = vec![seats[0].clone(); rules.variant.player_count()];
+    }
+    Ok(Options {
+        preset,
+        config: SimConfig {
+            rules,
+            seats,
+            seeds: first_seed..last_seed,
+            rotate,
+            threads,
+        },
+        csv,
+        per_game,
+    })
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("{message}");
+            }
+            eprintln!("{USAGE}");
+            let names: Vec<&str> = strategy_names().collect();
+            eprintln!("strategies: {}", names.join(", "));
+            return ExitCode::from(2);
+        }
+    };
+    let records = match run(&options.config) {
+        Ok(records) => records,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+    let summary = summarize(&records);
+
+    if options.csv {
+        if options.per_game {
+            print!("{}", games_csv(&records));
+        } else {
+            print!("{}", summary_csv(&summary));
+        }
+    } else {
+        let report = Report {
+            preset: options.preset,
+            seats: &options.config.seats,
+            first_seed: options.config.seeds.start,
+            rotate: options.config.rotate,
+            summary: &summary,
+            games: options.per_game.then_some(records.as_slice()),
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("reports serialize")
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &str) -> Result<Options, String> {
+        parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn reads_flags_and_fills_the_table_from_one_name() {
+        let options =
+            parse_args("--games 5 --seed 40 --preset happy --seats normal --format csv").unwrap();
+        assert_eq!(options.preset, RulePreset::Happy);
+        assert_eq!(options.config.seeds, 40..45);
+        assert_eq!(options.config.seats, ["normal", "normal", "normal"]);
+        assert!(options.csv);
+        assert!(!options.per_game && !options.config.rotate);
+
+        let options =
+            parse_args("--preset FourPlayer --seats hard,easy,easy,random --rotate").unwrap();
+        assert_eq!(options.config.seats.len(), 4);
+        assert!(options.config.rotate);
+        assert_eq!(options.config.seeds, 0..100);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for args in [
+            "--games",
+            "--games many",
+            "--preset nowhere",
+            "--format xml",
+            "--shuffle",
+            "--seed 18446744073709551615 --games 2",
+        ] {
+            assert!(parse_args(args).is_err(), "{args}");
+        }
+        assert_eq!(parse_args("--help").err(), Some(String::new()));
+    }
+}
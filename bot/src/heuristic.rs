@@ -0,0 +1,314 @@
+use crate::Strategy;
+use game_core::{
+    classify_play_with, decompose, hints, Action, BiddingMode, Card, Move, PlayKind, PlayerView,
+    Rank, RankCounts,
+};
+
+/// Opponents holding this many cards or fewer are about to go out.
+const DANGER: usize = 2;
+
+/// A rule-based player that always makes the same choice from the same view.
+///
+/// It leads the weakest group of its best split (拆牌), follows with the
+/// cheapest beat, keeps bombs for when an opponent is about to go out and,
+/// as a farmer, lets its teammate's plays stand.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeuristicBot;
+
+impl Strategy for HeuristicBot {
+    /// Bids what the hand is worth, or calls and robs with a strong one.
+    fn choose_bid(&self, view: &PlayerView) -> Action {
+        let strength = hand_strength(&view.hand);
+        match view.bidding_mode {
+            BiddingMode::Points => {
+                let worth = match strength {
+                    10.. => 3,
+                    7.. => 2,
+                    5.. => 1,
+                    _ => 0,
+                };
+                Action::Bid(if worth > view.current_bid { worth } else { 0 })
+            }
+            BiddingMode::Rob => {
+                let needed = if view.current_bid == 0 { 5 } else { 8 };
+                Action::Rob(strength >= needed)
+            }
+        }
+    }
+
+    fn choose_double(&self, view: &PlayerView) -> Action {
+        Action::Double(match hand_strength(&view.hand) {
+            12.. => 4,
+            8.. => 2,
+            _ => 1,
+        })
+    }
+
+    /// The play or pass for the seat to move.
+    fn choose_action(&self, view: &PlayerView) -> Action {
+        match view.to_beat() {
+            None => self.lead(view),
+            Some(prev) => {
+                let moves = hints(&view.hand, Some(prev), &view.rules, view.wild_rank);
+                self.follow(view, moves)
+            }
+        }
+    }
+}
+
+impl HeuristicBot {
+    pub fn new() -> Self {
+        HeuristicBot
+    }
+
+    fn lead(&self, view: &PlayerView) -> Action {
+        if let Some(out) = classify_play_with(&view.hand, &view.rules, view.wild_rank) {
+            return Action::Play {
+                cards: view.hand.clone(),
+                kind: Some(out.kind),
+            };
+        }
+
+        // Hand the lead to a teammate who is down to one card.
+        let next = (view.seat + 1) % view.player_count();
+        if view.is_teammate(next) && view.hand_counts[next] == 1 {
+            if let Some(lowest) = view.hand.iter().min_by_key(|card| card.rank) {
+                return Action::Play {
+                    cards: vec![*lowest],
+                    kind: Some(PlayKind::Single),
+                };
+            }
+        }
+
+        // Listing every lead is slow for big hands, so only fall back to it
+        // when the rules leave the hand without a split.
+        let Some(analysis) = decompose(&view.hand, &view.rules) else {
+            // With nothing legal to lead, the pass is refused by the table
+            // rather than the bot panicking.
+            let moves = hints(&view.hand, None, &view.rules, view.wild_rank);
+            return moves.first().map_or(Action::Pass, play);
+        };
+        let groups = analysis.best.plays;
+        let mut plain: Vec<&Move> = groups.iter().filter(|m| !m.play.kind.is_bomb()).collect();
+        if plain.is_empty() {
+            plain = groups.iter().collect();
+        }
+        let danger = closest_opponent(view);
+        if danger <= DANGER {
+            // Small singles or pairs would let them out; lead something they
+            // cannot match, or else the strongest of what is left.
+            let safe: Vec<&Move> = plain
+                .iter()
+                .copied()
+                .filter(|m| m.cards.len() > danger || !is_small_set(m.play.kind))
+                .collect();
+            if safe.is_empty() {
+                if let Some(top) = plain.iter().max_by_key(|m| m.play.main_rank) {
+                    return play(top);
+                }
+            } else {
+                plain = safe;
+            }
+        }
+        plain
+            .into_iter()
+            .min_by_key(|m| (m.play.main_rank, std::cmp::Reverse(m.cards.len())))
+            .map_or(Action::Pass, play)
+    }
+
+    fn follow(&self, view: &PlayerView, moves: Vec<Move>) -> Action {
+        if let Some(out) = moves.iter().find(|m| m.cards.len() == view.hand.len()) {
+            return play(out);
+        }
+        if view.last_player.is_some_and(|last| view.is_teammate(last)) {
+            return Action::Pass;
+        }
+
+        let danger = closest_opponent(view) <= DANGER;
+        let (bombs, plain): (Vec<&Move>, Vec<&Move>) =
+            moves.iter().partition(|m| m.play.kind.is_bomb());
+        // `hints` puts the cheapest beat first; with an opponent close to
+        // going out, beat as high as possible instead.
+        let beat = if danger {
+            plain.iter().max_by_key(|m| m.play.main_rank)
+        } else {
+            plain.first()
+        };
+        if let Some(beat) = beat {
+            return play(beat);
+        }
+        if let Some(bomb) = bombs.first() {
+            if danger || leaves_one_play(view, bomb) {
+                return play(bomb);
+            }
+        }
+        Action::Pass
+    }
+}
+
+fn play(candidate: &Move) -> Action {
+    Action::Play {
+        cards: candidate.cards.clone(),
+        kind: Some(candidate.play.kind),
+    }
+}
+
+fn is_small_set(kind: PlayKind) -> bool {
+    matches!(kind, PlayKind::Single | PlayKind::Pair)
+}
+
+/// Fewest cards held by any opponent.
+fn closest_opponent(view: &PlayerView) -> usize {
+    view.opponents()
+        .into_iter()
+        .map(|seat| view.hand_counts[seat])
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
+/// Whether the rest of the hand goes out in one play after `bomb`.
+fn leaves_one_play(view: &PlayerView, bomb: &Move) -> bool {
+    let mut rest = view.hand.clone();
+    for card in &bomb.cards {
+        if let Some(at) = rest.iter().position(|held| held == card) {
+            rest.swap_remove(at);
+        }
+    }
+    decompose(&rest, &view.rules).is_some_and(|analysis| analysis.play_count() <= 1)
+}
+
+/// Rough worth of a hand for bidding: jokers, 2s, aces and bombs.
+fn hand_strength(hand: &[Card]) -> u32 {
+    RankCounts::from_cards(hand)
+        .iter()
+        .map(|(rank, count)| {
+            let count = u32::from(count);
+            let bomb = if count >= 4 { 6 } else { 0 };
+            bomb + match rank {
+                Rank::RedJoker => 4 * count,
+                Rank::BlackJoker => 3 * count,
+                Rank::Two => 2 * count,
+                Rank::Ace => count,
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_core::notation::parse_hand;
+    use game_core::{classify_play, GamePhase, GameState, RulePreset};
+
+    fn play_game(preset: RulePreset, seed: u64) -> GameState {
+        let rules = preset.rules();
+        let ids: Vec<u64> = (1..=rules.variant.player_count() as u64).collect();
+        let mut state = GameState::with_rules(&ids, seed, rules);
+        let bot = HeuristicBot::new();
+        while let Some(action) = state.view(state.turn).and_then(|view| bot.act(&view)) {
+            let seat = state.turn;
+            state
+                .apply(seat, action.clone())
+                .unwrap_or_else(|err| panic!("{preset:?} seed {seed}: {action:?} {err:?}"));
+            assert!(state.events.len() < 2000, "game did not end");
+        }
+        state
+    }
+
+    /// Seat 0 of a three-player game in the playing phase.
+    fn view(hand: &str, landlord: usize, counts: [usize; 3]) -> PlayerView {
+        let mut state = GameState::new(&[1, 2, 3], 0);
+        state.phase = GamePhase::Playing;
+        state.landlord = Some(landlord);
+        state.turn = 0;
+        let mut view = state.view(0).unwrap();
+        view.hand = parse_hand(hand).unwrap();
+        view.hand_counts = counts.to_vec();
+        view
+    }
+
+    fn following(mut view: PlayerView, cards: &str, seat: usize) -> PlayerView {
+        view.last_play = classify_play(&parse_hand(cards).unwrap());
+        view.last_player = Some(seat);
+        view
+    }
+
+    fn played(action: Action) -> String {
+        match action {
+            Action::Play { cards, .. } => game_core::notation::format_hand(&cards),
+            other => format!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn plays_whole_games_legally_and_deterministically() {
+        // Splitting a 33-card four-player hand is slow in debug builds.
+        for (preset, games) in [
+            (RulePreset::Classic, 4),
+            (RulePreset::Happy, 4),
+            (RulePreset::Laizi, 4),
+            (RulePreset::FourPlayer, 1),
+            (RulePreset::TwoPlayer, 4),
+        ] {
+            for seed in 0..games {
+                let game = play_game(preset, seed);
+                assert!(game.winner.is_some());
+                assert_eq!(game, play_game(preset, seed));
+            }
+        }
+    }
+
+    #[test]
+    fn leads_the_weakest_group() {
+        let bot = HeuristicBot::new();
+        assert_eq!(
+            played(bot.choose_action(&view("3 QQ KK", 1, [5, 17, 17]))),
+            "3"
+        );
+        // A landlord on one card would follow a low single.
+        assert_eq!(
+            played(bot.choose_action(&view("3 QQ KK", 1, [5, 1, 17]))),
+            "QQ"
+        );
+        // A teammate on one card gets a low single to go out with.
+        assert_eq!(
+            played(bot.choose_action(&view("3 QQ KK", 2, [5, 1, 17]))),
+            "3"
+        );
+    }
+
+    #[test]
+    fn follows_cheaply_and_not_over_a_teammate() {
+        let bot = HeuristicBot::new();
+        let hand = view("7 9 2 8888", 1, [7, 12, 12]);
+        assert_eq!(
+            played(bot.choose_action(&following(hand.clone(), "5", 1))),
+            "7"
+        );
+        assert_eq!(bot.choose_action(&following(hand, "5", 2)), Action::Pass);
+    }
+
+    #[test]
+    fn saves_bombs_until_an_opponent_is_nearly_out() {
+        let bot = HeuristicBot::new();
+        let calm = following(view("33 4444 9 J", 1, [8, 10, 12]), "KK", 1);
+        assert_eq!(bot.choose_action(&calm), Action::Pass);
+        let urgent = following(view("33 4444 9 J", 1, [8, 2, 12]), "KK", 1);
+        assert_eq!(played(bot.choose_action(&urgent)), "4444");
+    }
+
+    #[test]
+    fn bids_by_hand_strength() {
+        let bot = HeuristicBot::new();
+        let mut weak = GameState::new(&[1, 2, 3], 0).view(0).unwrap();
+        weak.hand = parse_hand("3 4 5 7 8 9 T J").unwrap();
+        assert_eq!(bot.choose_bid(&weak), Action::Bid(0));
+        let mut strong = weak.clone();
+        strong.hand = parse_hand("22 A BJ RJ 5555").unwrap();
+        assert_eq!(bot.choose_bid(&strong), Action::Bid(3));
+        assert_eq!(bot.choose_double(&strong), Action::Double(4));
+        strong.bidding_mode = BiddingMode::Rob;
+        assert_eq!(bot.choose_bid(&strong), Action::Rob(true));
+    }
+}
@@ -0,0 +1,576 @@
+//! Information-set Monte Carlo tree search. Each iteration deals the unseen
+//! cards at random in a way that fits the view (a determinization), walks
+//! one shared tree with the moves that are legal in that deal, then plays
+//! the game out with `GameState::apply_play_as`/`pass`. The move tried most
+//! often at the root is played.
+
+use crate::{HeuristicBot, Strategy};
+use game_core::{
+    legal_moves_wild, Action, Card, GamePhase, GameState, Play, PlayKind, PlayerView, Rank,
+    RankCounts, RuleSet,
+};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How hard `IsmctsBot` thinks about each play.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchConfig {
+    /// Simulations per decision, shared out between the threads.
+    pub iterations: u32,
+    /// Stops early once this much time has passed. Without it the same view,
+    /// seed and thread count always give the same move.
+    pub think_time: Option<Duration>,
+    /// Threads that each grow their own tree; 1 searches on the calling
+    /// thread.
+    pub threads: usize,
+    /// UCB exploration constant.
+    pub exploration: f64,
+    pub seed: u64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            iterations: 1000,
+            think_time: None,
+            threads: 1,
+            exploration: 0.7,
+            seed: 0,
+        }
+    }
+}
+
+/// A search-based player. Bidding and doubling are left to `HeuristicBot`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IsmctsBot {
+    pub config: SearchConfig,
+}
+
+/// A move as the tree knows it: which ranks were played and how they were
+/// read, so the same move matches across deals with different suits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Step {
+    Pass,
+    Play(RankCounts, PlayKind),
+}
+
+/// Most moves the tree tries from one position.
+const MAX_BRANCHING: usize = 12;
+
+struct Node {
+    /// Seat that made the move leading here.
+    seat: usize,
+    visits: u32,
+    wins: f64,
+    /// Iterations in which this move was legal.
+    available: u32,
+    children: HashMap<Step, usize>,
+}
+
+impl Node {
+    fn new(seat: usize) -> Self {
+        Node {
+            seat,
+            visits: 0,
+            wins: 0.0,
+            available: 0,
+            children: HashMap::new(),
+        }
+    }
+}
+
+impl Strategy for IsmctsBot {
+    fn choose_bid(&self, view: &PlayerView) -> Action {
+        HeuristicBot.choose_bid(view)
+    }
+
+    fn choose_double(&self, view: &PlayerView) -> Action {
+        HeuristicBot.choose_double(view)
+    }
+
+    /// The play or pass with the most simulations behind it, or a pass the
+    /// table will refuse when there is no legal move at all.
+    fn choose_action(&self, view: &PlayerView) -> Action {
+        let root = prune(
+            &view.hand,
+            legal_steps(&view.hand, view.to_beat(), &view.rules, view.wild_rank),
+        );
+        if root.is_empty() {
+            return Action::Pass;
+        }
+        let best = if root.len() == 1 {
+            0
+        } else {
+            let visits = self.search(view, &root);
+            // First of the most visited, so ties keep the generator's order.
+            let most = visits.iter().copied().max().unwrap_or(0);
+            visits.iter().position(|n| *n == most).unwrap_or(0)
+        };
+        let (step, cards) = &root[best];
+        match step {
+            Step::Pass => Action::Pass,
+            Step::Play(_, kind) => Action::Play {
+                cards: cards.clone(),
+                kind: Some(*kind),
+            },
+        }
+    }
+}
+
+impl IsmctsBot {
+    pub fn new(config: SearchConfig) -> Self {
+        IsmctsBot { config }
+    }
+
+    /// Visits of each root move, summed over the threads' trees.
+    fn search(&self, view: &PlayerView, root: &[(Step, Vec<Card>)]) -> Vec<u32> {
+        let config = self.config;
+        let threads = config.threads.max(1);
+        let deadline = config.think_time.map(|limit| Instant::now() + limit);
+        let per_thread = config.iterations.div_ceil(threads as u32);
+        let played: usize = view.played.iter().map(Vec::len).sum();
+        let seed =
+            config.seed ^ (played as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ view.seat as u64;
+
+        let trees: Vec<Vec<u32>> = if threads == 1 {
+            vec![grow(view, root, config, seed, per_thread, deadline)]
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<_> = (0..threads as u64)
+                    .map(|i| {
+                        scope.spawn(move || {
+                            grow(
+                                view,
+                                root,
+                                config,
+                                seed.wrapping_add(i),
+                                per_thread,
+                                deadline,
+                            )
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("search thread panicked"))
+                    .collect()
+            })
+        };
+        (0..root.len())
+            .map(|index| trees.iter().map(|visits| visits[index]).sum())
+            .collect()
+    }
+}
+
+/// Runs one tree for up to `iterations` simulations and returns the visits
+/// of each root move.
+fn grow(
+    view: &PlayerView,
+    root: &[(Step, Vec<Card>)],
+    config: SearchConfig,
+    seed: u64,
+    iterations: u32,
+    deadline: Option<Instant>,
+) -> Vec<u32> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut nodes = vec![Node::new(view.seat)];
+    for _ in 0..iterations {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+        let mut state = determinize(view, &mut rng);
+        let mut path = vec![0];
+        let mut node = 0;
+        // Select through moves tried before until one is new, then expand it.
+        while state.phase != GamePhase::Finished {
+            let seat = state.turn;
+            let hand = &state.players[seat].hand;
+            let steps = prune(
+                hand,
+                legal_steps(
+                    hand,
+                    state.last_play.as_ref(),
+                    &state.rules,
+                    state.wild_rank,
+                ),
+            );
+            let untried: Vec<usize> = (0..steps.len())
+                .filter(|i| !nodes[node].children.contains_key(&steps[*i].0))
+                .collect();
+            if let Some(&pick) = untried.choose(&mut rng) {
+                let (step, cards) = steps[pick].clone();
+                apply_step(&mut state, step, cards);
+                let child = nodes.len();
+                nodes.push(Node::new(seat));
+                nodes[child].available = 1;
+                nodes[node].children.insert(step, child);
+                path.push(child);
+                break;
+            }
+
+            let mut best: Option<(f64, usize, usize)> = None;
+            for (index, (step, _)) in steps.iter().enumerate() {
+                let child = nodes[node].children[step];
+                nodes[child].available += 1;
+                let score = ucb(&nodes[child], config.exploration);
+                if best.is_none_or(|(top, _, _)| score > top) {
+                    best = Some((score, index, child));
+                }
+            }
+            let Some((_, index, child)) = best else {
+                break;
+            };
+            let (step, cards) = steps[index].clone();
+            apply_step(&mut state, step, cards);
+            path.push(child);
+            node = child;
+        }
+
+        // A deal where some seat is left without a move teaches nothing.
+        let Some(winner) = rollout(&mut state, &mut rng) else {
+            continue;
+        };
+        let landlord = state.landlord;
+        for &index in &path[1..] {
+            let node = &mut nodes[index];
+            node.visits += 1;
+            if same_side(node.seat, winner, landlord) {
+                node.wins += 1.0;
+            }
+        }
+    }
+
+    root.iter()
+        .map(|(step, _)| {
+            nodes[0]
+                .children
+                .get(step)
+                .map_or(0, |child| nodes[*child].visits)
+        })
+        .collect()
+}
+
+fn ucb(node: &Node, exploration: f64) -> f64 {
+    if node.visits == 0 {
+        return f64::INFINITY;
+    }
+    let visits = f64::from(node.visits);
+    node.wins / visits + exploration * (f64::from(node.available).ln() / visits).sqrt()
+}
+
+/// Deals the cards `view` cannot see among the other seats, keeping shown
+/// hands and the landlord's unplayed bottom cards where they are known to be.
+fn determinize(view: &PlayerView, rng: &mut StdRng) -> GameState {
+    let seats = view.player_count();
+    let ids: Vec<u64> = (1..=seats as u64).collect();
+    let mut state = GameState::with_rules(&ids, 0, view.rules);
+
+    let mut hands: Vec<Option<Vec<Card>>> = view.shown.clone();
+    hands[view.seat] = Some(view.hand.clone());
+    let mut unseen = view.rules.variant.deck().cards();
+    for cards in hands.iter().flatten().chain(&view.played) {
+        remove_cards(&mut unseen, cards);
+    }
+    let mut known = vec![Vec::new(); seats];
+    if let Some(landlord) = view.landlord.filter(|seat| hands[*seat].is_none()) {
+        let mut bottom = view.bottom.clone();
+        remove_cards(&mut bottom, &view.played[landlord]);
+        remove_cards(&mut unseen, &bottom);
+        known[landlord] = bottom;
+    }
+
+    unseen.shuffle(rng);
+    for (seat, hand) in hands.iter_mut().enumerate() {
+        if hand.is_none() {
+            let mut dealt = std::mem::take(&mut known[seat]);
+            let needed = view.hand_counts[seat].saturating_sub(dealt.len());
+            dealt.extend(unseen.drain(..needed.min(unseen.len())));
+            *hand = Some(dealt);
+        }
+    }
+    for (player, hand) in state.players.iter_mut().zip(hands) {
+        player.hand = hand.unwrap_or_default();
+    }
+    state.leftover = unseen;
+    state.bottom = view.bottom.clone();
+    state.phase = GamePhase::Playing;
+    state.landlord = view.landlord;
+    state.wild_rank = view.wild_rank;
+    state.turn = view.turn;
+    state.last_play = view.last_play.clone();
+    state.last_player = view.last_player;
+    state.pass_count = view.pass_count;
+    state
+}
+
+/// Plays the game out: going out when possible, letting a teammate's play
+/// stand, otherwise a random non-bomb play or pass, and a bomb only now and
+/// then when nothing else beats. Returns the winner, or `None` if a seat
+/// is left without a legal move.
+fn rollout(state: &mut GameState, rng: &mut StdRng) -> Option<usize> {
+    while state.phase != GamePhase::Finished {
+        let seat = state.turn;
+        let held = state.players[seat].hand.len();
+        let mut steps = legal_steps(
+            &state.players[seat].hand,
+            state.last_play.as_ref(),
+            &state.rules,
+            state.wild_rank,
+        );
+        if steps.is_empty() {
+            return None;
+        }
+        let following = state.last_play.is_some();
+        let pick = if let Some(out) = steps.iter().position(|(_, cards)| cards.len() == held) {
+            out
+        } else if following
+            && state
+                .last_player
+                .is_some_and(|last| same_side(last, seat, state.landlord))
+        {
+            steps.len() - 1
+        } else {
+            let plain: Vec<usize> = (0..steps.len())
+                .filter(|i| matches!(steps[*i].0, Step::Play(_, kind) if !kind.is_bomb()))
+                .collect();
+            match plain.choose(rng) {
+                // Passing is as likely as any one beat.
+                Some(&beat) if !following || rng.gen_range(0..=plain.len()) > 0 => beat,
+                Some(_) => steps.len() - 1,
+                None if !following || rng.gen_ratio(1, 4) => rng.gen_range(0..steps.len()),
+                None => steps.len() - 1,
+            }
+        };
+        let (step, cards) = steps.swap_remove(pick);
+        apply_step(state, step, cards);
+    }
+    state.winner
+}
+
+fn apply_step(state: &mut GameState, step: Step, cards: Vec<Card>) {
+    let seat = state.turn;
+    let applied = match step {
+        Step::Pass => state.pass(seat).map(drop),
+        Step::Play(_, kind) => state.apply_play_as(seat, cards, Some(kind)).map(drop),
+    };
+    applied.expect("generated moves are legal");
+}
+
+/// Every play from `hand` over `prev`, one per distinct move, then a pass
+/// when there is something to beat.
+fn legal_steps(
+    hand: &[Card],
+    prev: Option<&Play>,
+    rules: &RuleSet,
+    wild: Option<Rank>,
+) -> Vec<(Step, Vec<Card>)> {
+    let plays: Vec<(RankCounts, Play, Vec<Card>)> = match wild {
+        None => RankCounts::from_cards(hand)
+            .legal_moves(prev, rules)
+            .into_iter()
+            .map(|(counts, play)| {
+                let cards = counts.take_from(hand).expect("moves come from the hand");
+                (counts, play, cards)
+            })
+            .collect(),
+        Some(_) => legal_moves_wild(hand, prev, rules, wild)
+            .into_iter()
+            .map(|candidate| {
+                (
+                    RankCounts::from_cards(&candidate.cards),
+                    candidate.play,
+                    candidate.cards,
+                )
+            })
+            .collect(),
+    };
+    let mut seen = HashSet::new();
+    let mut steps: Vec<(Step, Vec<Card>)> = plays
+        .into_iter()
+        .map(|(counts, play, cards)| (Step::Play(counts, play.kind), cards))
+        .filter(|(step, _)| seen.insert(*step))
+        .collect();
+    if prev.is_some() {
+        steps.push((Step::Pass, Vec::new()));
+    }
+    steps
+}
+
+/// Moves the tree considers out of `steps`. Leading from a big hand offers
+/// hundreds of plays, which leaves most of them a single visit, so only the
+/// plays that break up the fewest ranks are kept, lowest first. Going out and
+/// passing are always kept, and a couple of the cheapest bombs.
+fn prune(hand: &[Card], steps: Vec<(Step, Vec<Card>)>) -> Vec<(Step, Vec<Card>)> {
+    if steps.len() <= MAX_BRANCHING {
+        return steps;
+    }
+    let held = RankCounts::from_cards(hand);
+    let mut ranked: Vec<_> = steps
+        .into_iter()
+        .map(|(step, cards)| {
+            let key = match step {
+                Step::Pass => None,
+                Step::Play(counts, _) if counts.len() == hand.len() => None,
+                Step::Play(counts, kind) => {
+                    let breakage: u32 = counts
+                        .iter()
+                        .map(|(rank, n)| match held.count(rank) {
+                            total if total == n => 0,
+                            total if total >= 4 => 4,
+                            _ => 1,
+                        })
+                        .sum();
+                    let lowest = counts.iter().map(|(rank, _)| rank).min();
+                    Some((kind.is_bomb(), breakage, lowest, Reverse(counts.len())))
+                }
+            };
+            (key, step, cards)
+        })
+        .collect();
+    ranked.sort_by_key(|(key, _, _)| *key);
+    let mut bombs = 0;
+    let mut others = 0;
+    ranked
+        .into_iter()
+        .filter(|(key, _, _)| match key {
+            None => true,
+            Some((true, ..)) => {
+                bombs += 1;
+                bombs <= 2
+            }
+            Some(_) => {
+                others += 1;
+                others <= MAX_BRANCHING - 2
+            }
+        })
+        .map(|(_, step, cards)| (step, cards))
+        .collect()
+}
+
+fn same_side(seat: usize, other: usize, landlord: Option<usize>) -> bool {
+    seat == other || (landlord.is_some_and(|landlord| seat != landlord && other != landlord))
+}
+
+fn remove_cards(pool: &mut Vec<Card>, cards: &[Card]) {
+    for card in cards {
+        if let Some(at) = pool.iter().position(|held| held == card) {
+            pool.swap_remove(at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_core::notation::parse_hand;
+    use game_core::RulePreset;
+
+    fn quick(seed: u64) -> IsmctsBot {
+        IsmctsBot::new(SearchConfig {
+            iterations: 60,
+            seed,
+            ..SearchConfig::default()
+        })
+    }
+
+    #[test]
+    fn determinizations_fit_the_view() {
+        let mut state = GameState::with_rules(&[1, 2, 3], 3, RulePreset::Classic.rules());
+        let bot = HeuristicBot::new();
+        for _ in 0..20 {
+            let action = bot.act(&state.view(state.turn).unwrap()).unwrap();
+            let seat = state.turn;
+            state.apply(seat, action).unwrap();
+        }
+        assert_eq!(state.phase, GamePhase::Playing);
+
+        let landlord = state.landlord.unwrap();
+        let farmer = (landlord + 1) % 3;
+        let view = state.view(farmer).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let sample = determinize(&view, &mut rng);
+            assert_eq!(sample.players[farmer].hand, view.hand);
+            let mut all: Vec<Card> = view.played.concat();
+            for (seat, player) in sample.players.iter().enumerate() {
+                assert_eq!(player.hand.len(), view.hand_counts[seat]);
+                all.extend(&player.hand);
+            }
+            let mut deck = view.rules.variant.deck().cards();
+            remove_cards(&mut deck, &all);
+            assert!(deck.is_empty());
+            assert_eq!(all.len(), 54);
+            let mut bottom = view.bottom.clone();
+            remove_cards(&mut bottom, &view.played[landlord]);
+            for card in &bottom {
+                assert!(sample.players[landlord].hand.contains(card));
+            }
+        }
+    }
+
+    #[test]
+    fn plays_the_rocket_before_a_single_the_farmer_can_beat() {
+        let mut state = GameState::new(&[1, 2, 3], 0);
+        state.phase = GamePhase::Playing;
+        state.landlord = Some(0);
+        state.turn = 0;
+        for (player, hand) in state.players.iter_mut().zip(["BJ RJ 3", "4", "5 6"]) {
+            player.hand = parse_hand(hand).unwrap();
+        }
+        let action = quick(0).choose_action(&state.view(0).unwrap());
+        assert_eq!(
+            action,
+            Action::Play {
+                cards: parse_hand("BJ RJ").unwrap(),
+                kind: Some(PlayKind::Rocket)
+            }
+        );
+    }
+
+    #[test]
+    fn same_seed_and_thread_count_give_the_same_move() {
+        let mut state = GameState::with_rules(&[1, 2, 3], 11, RulePreset::Classic.rules());
+        while state.phase != GamePhase::Playing {
+            let action = HeuristicBot.act(&state.view(state.turn).unwrap()).unwrap();
+            let seat = state.turn;
+            state.apply(seat, action).unwrap();
+        }
+        let view = state.view(state.turn).unwrap();
+        assert_eq!(quick(5).choose_action(&view), quick(5).choose_action(&view));
+        let pooled = IsmctsBot::new(SearchConfig {
+            threads: 3,
+            ..quick(5).config
+        });
+        assert_eq!(pooled.choose_action(&view), pooled.choose_action(&view));
+    }
+
+    #[test]
+    fn finishes_games_against_heuristic_players() {
+        for (preset, seed) in [(RulePreset::Classic, 2), (RulePreset::Laizi, 3)] {
+            let rules = preset.rules();
+            let mut state = GameState::with_rules(&[1, 2, 3], seed, rules);
+            let searcher = IsmctsBot::new(SearchConfig {
+                iterations: 20,
+                threads: 2,
+                think_time: Some(Duration::from_secs(5)),
+                ..SearchConfig::default()
+            });
+            while state.phase != GamePhase::Finished {
+                let seat = state.turn;
+                let view = state.view(seat).unwrap();
+                let action = if seat == 0 {
+                    searcher.act(&view)
+                } else {
+                    HeuristicBot.act(&view)
+                };
+                state.apply(seat, action.unwrap()).unwrap();
+            }
+            assert!(state.winner.is_some());
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Computer players for 斗地主. Bots only ever see a `PlayerView`, the same
+//! information a person at the seat has.
+
+pub mod heuristic;
+pub mod ismcts;
+pub mod sim;
+pub mod strategy;
+
+pub use heuristic::HeuristicBot;
+pub use ismcts::{IsmctsBot, SearchConfig};
+pub use strategy::{create, play_game, strategy_names, RandomBot, Strategy};
@@ -0,0 +1,415 @@
+//! Self-play between registered strategies, for comparing rule or bot
+//! changes over many seeded games. The `simulate` binary is a thin command
+//! line around this module.
+
+use crate::{create, Strategy};
+use game_core::{Action, GameState, RuleSet, Spring};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Two-sided 95% normal quantile.
+const Z: f64 = 1.96;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimConfig {
+    pub rules: RuleSet,
+    /// Strategy name per seat.
+    pub seats: Vec<String>,
+    /// One game per seed.
+    pub seeds: Range<u64>,
+    /// Shifts the seating by one seat each game, so every strategy sits in
+    /// every seat equally often.
+    pub rotate: bool,
+    pub threads: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SimError {
+    UnknownStrategy(String),
+    SeatCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::UnknownStrategy(name) => write!(f, "unknown strategy {name:?}"),
+            SimError::SeatCount { expected, found } => {
+                write!(
+                    f,
+                    "the rules seat {expected} players, got {found} strategies"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+/// How one game went.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GameRecord {
+    pub seed: u64,
+    /// Strategy per seat in this game.
+    pub seats: Vec<String>,
+    pub landlord: usize,
+    pub landlord_won: bool,
+    /// Plays and passes once the landlord was known.
+    pub length: usize,
+    pub bombs: u32,
+    pub spring: Spring,
+    pub multiplier: u32,
+    pub scores: Vec<i64>,
+}
+
+impl GameRecord {
+    pub fn landlord_score(&self) -> i64 {
+        self.scores[self.landlord]
+    }
+
+    fn seat_won(&self, seat: usize) -> bool {
+        (seat == self.landlord) == self.landlord_won
+    }
+}
+
+/// A mean or rate with its 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Estimate {
+    pub estimate: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Estimate {
+    /// Wilson score interval for `hits` out of `trials`.
+    pub fn rate(hits: usize, trials: usize) -> Self {
+        if trials == 0 {
+            return Estimate {
+                estimate: 0.0,
+                low: 0.0,
+                high: 1.0,
+            };
+        }
+        let n = trials as f64;
+        let p = hits as f64 / n;
+        let spread = 1.0 + Z * Z / n;
+        let center = (p + Z * Z / (2.0 * n)) / spread;
+        let half = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / spread;
+        Estimate {
+            estimate: p,
+            low: (center - half).max(0.0),
+            high: (center + half).min(1.0),
+        }
+    }
+
+    /// Normal interval around the sample mean.
+    pub fn mean(values: &[f64]) -> Self {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n.max(1.0);
+        let half = Z * std_dev(values) / n.max(1.0).sqrt();
+        Estimate {
+            estimate: mean,
+            low: mean - half,
+            high: mean + half,
+        }
+    }
+}
+
+/// How one strategy fared over every seat it played.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StrategySummary {
+    pub name: String,
+    pub seat_games: usize,
+    pub win_rate: Estimate,
+    pub landlord_games: usize,
+    pub landlord_win_rate: Estimate,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub games: usize,
+    pub landlord_win_rate: Estimate,
+    pub game_length: Estimate,
+    pub bombs_per_game: Estimate,
+    pub spring_rate: Estimate,
+    pub landlord_score: Estimate,
+    pub landlord_score_std_dev: f64,
+    /// Games per landlord score.
+    pub landlord_scores: BTreeMap<i64, usize>,
+    pub strategies: Vec<StrategySummary>,
+}
+
+/// Plays every seed in `config` and returns the games in seed order, however
+/// many threads share the work.
+pub fn run(config: &SimConfig) -> Result<Vec<GameRecord>, SimError> {
+    let expected = config.rules.variant.player_count();
+    if config.seats.len() != expected {
+        return Err(SimError::SeatCount {
+            expected,
+            found: config.seats.len(),
+        });
+    }
+    if let Some(unknown) = config.seats.iter().find(|name| create(name, 0).is_none()) {
+        return Err(SimError::UnknownStrategy(unknown.clone()));
+    }
+
+    let seeds: Vec<u64> = config.seeds.clone().collect();
+    let next = AtomicUsize::new(0);
+    let records = Mutex::new(Vec::with_capacity(seeds.len()));
+    thread::scope(|scope| {
+        for _ in 0..config.threads.max(1) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&seed) = seeds.get(index) else {
+                    break;
+                };
+                let record = play_seed(config, index, seed);
+                records.lock().expect("no thread panicked").push(record);
+            });
+        }
+    });
+    let mut records = records.into_inner().expect("no thread panicked");
+    records.sort_by_key(|record| record.seed);
+    Ok(records)
+}
+
+fn play_seed(config: &SimConfig, index: usize, seed: u64) -> GameRecord {
+    let count = config.seats.len();
+    let shift = if config.rotate { index % count } else { 0 };
+    let seats: Vec<String> = (0..count)
+        .map(|seat| config.seats[(seat + count - shift) % count].clone())
+        .collect();
+    let strategies: Vec<Box<dyn Strategy>> = seats
+        .iter()
+        .enumerate()
+        .map(|(seat, name)| {
+            create(name, seed.rotate_left(16) ^ seat as u64).expect("names were checked")
+        })
+        .collect();
+    let lineup: Vec<&dyn Strategy> = strategies
+        .iter()
+        .map(|strategy| strategy.as_ref())
+        .collect();
+    let state = crate::play_game(config.rules, seed, &lineup);
+    record(seed, seats, &state)
+}
+
+fn record(seed: u64, seats: Vec<String>, state: &GameState) -> GameRecord {
+    let settlement = state.settlement().expect("the game was played out");
+    let length = state
+        .events
+        .iter()
+        .filter(|event| matches!(event.action, Action::Play { .. } | Action::Pass))
+        .count();
+    GameRecord {
+        seed,
+        seats,
+        landlord: settlement.landlord,
+        landlord_won: settlement.landlord_won,
+        length,
+        bombs: settlement.bombs,
+        spring: settlement.spring,
+        multiplier: settlement.multiplier,
+        scores: settlement.deltas,
+    }
+}
+
+pub fn summarize(records: &[GameRecord]) -> Summary {
+    let games = records.len();
+    let values =
+        |value: fn(&GameRecord) -> f64| -> Vec<f64> { records.iter().map(value).collect() };
+    let scores = values(|record| record.landlord_score() as f64);
+    let mut landlord_scores = BTreeMap::new();
+    for record in records {
+        *landlord_scores.entry(record.landlord_score()).or_insert(0) += 1;
+    }
+
+    let mut names: Vec<&str> = Vec::new();
+    for record in records {
+        for name in &record.seats {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+    }
+    let strategies = names
+        .into_iter()
+        .map(|name| {
+            let (mut seat_games, mut wins, mut landlord_games, mut landlord_wins) = (0, 0, 0, 0);
+            for record in records {
+                for (seat, _) in record.seats.iter().enumerate().filter(|(_, n)| *n == name) {
+                    seat_games += 1;
+                    wins += usize::from(record.seat_won(seat));
+                    if seat == record.landlord {
+                        landlord_games += 1;
+                        landlord_wins += usize::from(record.landlord_won);
+                    }
+                }
+            }
+            StrategySummary {
+                name: name.to_string(),
+                seat_games,
+                win_rate: Estimate::rate(wins, seat_games),
+                landlord_games,
+                landlord_win_rate: Estimate::rate(landlord_wins, landlord_games),
+            }
+        })
+        .collect();
+
+    Summary {
+        games,
+        landlord_win_rate: Estimate::rate(
+            records.iter().filter(|record| record.landlord_won).count(),
+            games,
+        ),
+        game_length: Estimate::mean(&values(|record| record.length as f64)),
+        bombs_per_game: Estimate::mean(&values(|record| f64::from(record.bombs))),
+        spring_rate: Estimate::rate(
+            records
+                .iter()
+                .filter(|record| record.spring != Spring::None)
+                .count(),
+            games,
+        ),
+        landlord_score: Estimate::mean(&scores),
+        landlord_score_std_dev: std_dev(&scores),
+        landlord_scores,
+        strategies,
+    }
+}
+
+/// The summary as `metric,estimate,low,high` rows.
+pub fn summary_csv(summary: &Summary) -> String {
+    let mut rows = vec![
+        ("landlord_win_rate".to_string(), summary.landlord_win_rate),
+        ("game_length".to_string(), summary.game_length),
+        ("bombs_per_game".to_string(), summary.bombs_per_game),
+        ("spring_rate".to_string(), summary.spring_rate),
+        ("landlord_score".to_string(), summary.landlord_score),
+    ];
+    for strategy in &summary.strategies {
+        rows.push((format!("win_rate:{}", strategy.name), strategy.win_rate));
+        rows.push((
+            format!("landlord_win_rate:{}", strategy.name),
+            strategy.landlord_win_rate,
+        ));
+    }
+    let mut csv = String::from("metric,estimate,low,high\n");
+    for (metric, value) in rows {
+        csv.push_str(&format!(
+            "{metric},{:.6},{:.6},{:.6}\n",
+            value.estimate, value.low, value.high
+        ));
+    }
+    csv
+}
+
+/// One row per game.
+pub fn games_csv(records: &[GameRecord]) -> String {
+    let mut csv = String::from(
+        "seed,seats,landlord,landlord_strategy,landlord_won,length,bombs,spring,multiplier,landlord_score\n",
+    );
+    for record in records {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{:?},{},{}\n",
+            record.seed,
+            record.seats.join("|"),
+            record.landlord,
+            record.seats[record.landlord],
+            record.landlord_won,
+            record.length,
+            record.bombs,
+            record.spring,
+            record.multiplier,
+            record.landlord_score(),
+        ));
+    }
+    csv
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+    (squares / (n - 1.0)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_core::RulePreset;
+
+    fn config(seats: &[&str], threads: usize) -> SimConfig {
+        SimConfig {
+            rules: RulePreset::Classic.rules(),
+            seats: seats.iter().map(|name| name.to_string()).collect(),
+            seeds: 10..16,
+            rotate: true,
+            threads,
+        }
+    }
+
+    #[test]
+    fn same_seeds_give_the_same_games_on_any_thread_count() {
+        let single = run(&config(&["random", "easy", "easy"], 1)).unwrap();
+        let pooled = run(&config(&["random", "easy", "easy"], 3)).unwrap();
+        assert_eq!(single, pooled);
+        assert_eq!(
+            single.iter().map(|record| record.seed).collect::<Vec<_>>(),
+            (10..16).collect::<Vec<_>>()
+        );
+        // Rotation moves the odd strategy round the table.
+        assert_eq!(single[1].seats, ["easy", "random", "easy"]);
+
+        let summary = summarize(&single);
+        assert_eq!(summary, summarize(&pooled));
+        assert_eq!(summary.games, 6);
+        assert_eq!(summary.landlord_scores.values().sum::<usize>(), 6);
+        let seats: usize = summary.strategies.iter().map(|s| s.seat_games).sum();
+        assert_eq!(seats, 18);
+        for record in &single {
+            assert_eq!(record.scores.iter().sum::<i64>(), 0);
+        }
+        assert_eq!(summary_csv(&summary).lines().count(), 1 + 5 + 2 * 2);
+        assert_eq!(games_csv(&single).lines().count(), 7);
+    }
+
+    #[test]
+    fn rejects_unknown_names_and_seat_counts() {
+        assert_eq!(
+            run(&config(&["easy", "grandmaster", "easy"], 1)),
+            Err(SimError::UnknownStrategy("grandmaster".to_string()))
+        );
+        assert_eq!(
+            run(&config(&["easy", "easy"], 1)),
+            Err(SimError::SeatCount {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn intervals_cover_the_estimate() {
+        let half = Estimate::rate(50, 100);
+        assert_eq!(half.estimate, 0.5);
+        assert!((half.low - 0.4038).abs() < 1e-3 && (half.high - 0.5962).abs() < 1e-3);
+        let none = Estimate::rate(0, 10);
+        assert_eq!(none.low, 0.0);
+        assert!(none.high > 0.2);
+
+        let mean = Estimate::mean(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(mean.estimate, 2.5);
+        assert!(
+            (mean.high - mean.estimate - Z * std_dev(&[1.0, 2.0, 3.0, 4.0]) / 2.0).abs() < 1e-9
+        );
+        assert_eq!(Estimate::mean(&[7.0]).low, 7.0);
+    }
+}
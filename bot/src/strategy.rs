@@ -0,0 +1,213 @@
+use crate::{HeuristicBot, IsmctsBot, SearchConfig};
+use game_core::{Action, Card, GamePhase, GameState, PlayerView, RuleSet};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// A way of playing a seat, deciding from what the seat can see.
+pub trait Strategy: Send + Sync {
+    /// A `Bid` or `Rob`, depending on the bidding mode.
+    fn choose_bid(&self, view: &PlayerView) -> Action;
+
+    /// A `Double` with one of `DOUBLE_FACTORS`.
+    fn choose_double(&self, view: &PlayerView) -> Action;
+
+    /// A play or pass for the seat to move.
+    fn choose_action(&self, view: &PlayerView) -> Action;
+
+    /// What to do at `view`, or `None` once the game is over.
+    fn act(&self, view: &PlayerView) -> Option<Action> {
+        match view.phase {
+            GamePhase::Bidding => Some(self.choose_bid(view)),
+            GamePhase::Doubling => Some(self.choose_double(view)),
+            GamePhase::Playing => Some(self.choose_action(view)),
+            GamePhase::Finished => None,
+        }
+    }
+}
+
+/// Picks any legal action. The pick depends only on the seed and the view,
+/// so games stay reproducible.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomBot {
+    pub seed: u64,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> Self {
+        RandomBot { seed }
+    }
+
+    /// Seed for the pick at `view`. Mixed by hand rather than with
+    /// `DefaultHasher`, whose output may change between Rust releases.
+    fn seed_for(&self, view: &PlayerView) -> u64 {
+        let cards = |cards: &[Card]| {
+            cards
+                .iter()
+                .map(|card| (card.rank as u64) << 8 | card.suit as u64)
+                .collect::<Vec<_>>()
+        };
+        let mut words = vec![self.seed, view.seat as u64];
+        words.extend(cards(&view.hand));
+        for (seat, played) in view.played.iter().enumerate() {
+            words.push(u64::MAX - seat as u64);
+            words.extend(cards(played));
+        }
+        words.push(u64::from(view.current_bid));
+        words.push(view.last_player.map_or(0, |seat| seat as u64 + 1));
+        words.push(u64::from(view.pass_count));
+        words.into_iter().fold(0, |state, word| {
+            (state.rotate_left(5) ^ word).wrapping_mul(0x517c_c1b7_2722_0a95)
+        })
+    }
+
+    /// A pass when nothing is legal, which the table refuses.
+    fn pick(&self, view: &PlayerView) -> Action {
+        let mut rng = StdRng::seed_from_u64(self.seed_for(view));
+        view.legal_actions()
+            .choose(&mut rng)
+            .cloned()
+            .unwrap_or(Action::Pass)
+    }
+}
+
+impl Strategy for RandomBot {
+    fn choose_bid(&self, view: &PlayerView) -> Action {
+        self.pick(view)
+    }
+
+    fn choose_double(&self, view: &PlayerView) -> Action {
+        self.pick(view)
+    }
+
+    fn choose_action(&self, view: &PlayerView) -> Action {
+        self.pick(view)
+    }
+}
+
+type Factory = fn(u64) -> Box<dyn Strategy>;
+
+/// Registered strategies by name, weakest first. The seed lets seats playing
+/// the same strategy make different choices.
+const REGISTRY: [(&str, Factory); 4] = [
+    ("random", |seed| Box::new(RandomBot::new(seed))),
+    ("easy", |_| Box::new(HeuristicBot::new())),
+    ("normal", |seed| {
+        Box::new(IsmctsBot::new(SearchConfig {
+            iterations: 200,
+            seed,
+            ..SearchConfig::default()
+        }))
+    }),
+    ("hard", |seed| {
+        Box::new(IsmctsBot::new(SearchConfig {
+            iterations: 1500,
+            seed,
+            ..SearchConfig::default()
+        }))
+    }),
+];
+
+/// Names `create` accepts.
+pub fn strategy_names() -> impl Iterator<Item = &'static str> {
+    REGISTRY.iter().map(|(name, _)| *name)
+}
+
+/// The strategy registered as `name`, or `None` for an unknown name.
+pub fn create(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+    REGISTRY
+        .iter()
+        .find(|(registered, _)| *registered == name)
+        .map(|(_, factory)| factory(seed))
+}
+
+/// Plays a game dealt from `seed` to the end, `seats[i]` deciding for seat
+/// `i`.
+pub fn play_game(rules: RuleSet, seed: u64, seats: &[&dyn Strategy]) -> GameState {
+    assert_eq!(
+        seats.len(),
+        rules.variant.player_count(),
+        "one strategy per seat"
+    );
+    let ids: Vec<u64> = (1..=seats.len() as u64).collect();
+    let mut state = GameState::with_rules(&ids, seed, rules);
+    while let Some(action) = state
+        .view(state.turn)
+        .and_then(|view| seats[state.turn].act(&view))
+    {
+        let seat = state.turn;
+        state
+            .apply(seat, action)
+            .expect("strategies choose legal actions");
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_core::notation::parse_hand;
+    use game_core::{KindSet, PlayKind, RulePreset, MAX_BID};
+
+    #[test]
+    fn creates_every_registered_name() {
+        let names: Vec<&str> = strategy_names().collect();
+        assert_eq!(names, ["random", "easy", "normal", "hard"]);
+        for name in names {
+            assert!(create(name, 1).is_some());
+        }
+        assert!(create("impossible", 1).is_none());
+    }
+
+    #[test]
+    fn any_two_strategies_can_play_each_other() {
+        let rules = RulePreset::Classic.rules();
+        let cheap = ["random", "easy"];
+        for (i, first) in cheap.iter().enumerate() {
+            for second in cheap {
+                let a = create(first, i as u64).unwrap();
+                let b = create(second, 7).unwrap();
+                let game = play_game(rules, 3, &[a.as_ref(), b.as_ref(), b.as_ref()]);
+                assert!(game.winner.is_some());
+                let again = play_game(rules, 3, &[a.as_ref(), b.as_ref(), b.as_ref()]);
+                assert_eq!(game.events, again.events);
+            }
+        }
+        let searcher = IsmctsBot::new(SearchConfig {
+            iterations: 20,
+            ..SearchConfig::default()
+        });
+        let random = RandomBot::new(2);
+        let game = play_game(rules, 4, &[&random, &searcher, &random]);
+        assert!(game.winner.is_some());
+    }
+
+    #[test]
+    fn random_picks_do_not_depend_on_the_toolchain() {
+        let view = GameState::new(&[1, 2, 3], 0).view(0).unwrap();
+        // Changing this value changes every recorded random-bot game.
+        assert_eq!(RandomBot::new(7).seed_for(&view), 9_954_744_223_776_801_104);
+        let other = GameState::new(&[1, 2, 3], 0).view(1).unwrap();
+        assert_ne!(
+            RandomBot::new(7).seed_for(&other),
+            RandomBot::new(7).seed_for(&view)
+        );
+    }
+
+    #[test]
+    fn strategies_pass_when_nothing_can_be_led() {
+        let rules = RuleSet {
+            enabled_kinds: KindSet::ALL.without(PlayKind::Single),
+            ..RuleSet::default()
+        };
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 5, rules, 0);
+        state.apply(0, Action::Bid(MAX_BID)).unwrap();
+        state.players[0].hand = parse_hand("3 5").unwrap();
+        let view = state.view(0).unwrap();
+        assert!(view.legal_actions().is_empty());
+        for name in strategy_names() {
+            let strategy = create(name, 1).unwrap();
+            assert_eq!(strategy.act(&view), Some(Action::Pass), "{name}");
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Time to split a hand into its fewest plays, per hand size, with the tail
+//! as well as the mean. Run with `cargo bench -p game-core --bench decompose`.
+
+use game_core::{decompose, RuleSet, Variant};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SEEDS: u64 = 500;
+
+fn main() {
+    let rules = RuleSet::default();
+    for (variant, size) in [
+        (Variant::Classic, 17),
+        (Variant::Classic, 20),
+        (Variant::FourPlayer, 33),
+    ] {
+        let mut times: Vec<Duration> = (0..SEEDS)
+            .map(|seed| {
+                let hand = &variant.shuffled_deck(seed)[..size];
+                let start = Instant::now();
+                black_box(decompose(black_box(hand), &rules));
+                start.elapsed()
+            })
+            .collect();
+        times.sort();
+        let mean = times.iter().sum::<Duration>() / times.len() as u32;
+        let at = |share: f64| times[((times.len() - 1) as f64 * share) as usize];
+        println!(
+            "{size:>2} cards  mean {mean:>10.2?}  median {:>10.2?}  p99 {:>10.2?}  max {:>10.2?}",
+            at(0.5),
+            at(0.99),
+            at(1.0)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Card lists against `RankCounts` on the rule paths bots call most.
+//! Run with `cargo bench -p game-core --bench hand`.
+
+use game_core::{classify_all_with, legal_moves, Card, Play, RankCounts, RuleSet, Variant};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SEEDS: u64 = 200;
+
+fn time<T>(rounds: u32, mut run: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..rounds {
+        black_box(run());
+    }
+    start.elapsed() / rounds
+}
+
+fn report(name: &str, cards: Duration, counts: Duration) {
+    println!(
+        "{name:<26} cards {:>10.2?}  counts {:>10.2?}  speedup {:>5.1}x",
+        cards,
+        counts,
+        cards.as_secs_f64() / counts.as_secs_f64()
+    );
+}
+
+fn main() {
+    let rules = RuleSet::default();
+    let deals: Vec<Vec<Card>> = (0..SEEDS)
+        .map(|seed| Variant::Classic.shuffled_deck(seed)[..17].to_vec())
+        .collect();
+    let hands: Vec<RankCounts> = deals
+        .iter()
+        .map(|hand| RankCounts::from_cards(hand))
+        .collect();
+
+    let plays: Vec<Vec<Card>> = deals
+        .iter()
+        .flat_map(|hand| legal_moves(hand, None, &rules))
+        .map(|candidate| candidate.cards)
+        .collect();
+    let play_counts: Vec<RankCounts> = plays
+        .iter()
+        .map(|cards| RankCounts::from_cards(cards))
+        .collect();
+    let cards = time(5, || {
+        plays
+            .iter()
+            .map(|cards| classify_all_with(black_box(cards), &rules, None).len())
+            .sum::<usize>()
+    });
+    let counts = time(5, || {
+        play_counts
+            .iter()
+            .map(|counts| black_box(*counts).classify_all(&rules).len())
+            .sum::<usize>()
+    });
+    report(&format!("classify x{}", plays.len()), cards, counts);
+
+    // Each hand answers a lead picked from the next one.
+    let leads: Vec<Option<Play>> = (0..deals.len())
+        .map(|idx| {
+            let other = &deals[(idx + 1) % deals.len()];
+            let candidate = legal_moves(other, None, &rules).into_iter().nth(7);
+            candidate.map(|candidate| candidate.play)
+        })
+        .collect();
+    for (name, leading) in [("lead", true), ("follow", false)] {
+        let lead = |idx: usize| if leading { None } else { leads[idx].as_ref() };
+        let cards = time(5, || {
+            deals
+                .iter()
+                .enumerate()
+                .map(|(idx, hand)| legal_moves(black_box(hand), lead(idx), &rules).len())
+                .sum::<usize>()
+        });
+        let counts = time(5, || {
+            hands
+                .iter()
+                .enumerate()
+                .map(|(idx, hand)| black_box(*hand).legal_moves(lead(idx), &rules).len())
+                .sum::<usize>()
+        });
+        report(&format!("legal moves ({name}) x{SEEDS}"), cards, counts);
+    }
+}
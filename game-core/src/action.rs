@@ -0,0 +1,361 @@
+use crate::{
+    Bidding, Card, GameError, GamePhase, GameState, Play, PlayKind, RuleSet, DOUBLE_FACTORS,
+    MAX_BID,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Anything a seat can do at the table, applied through `GameState::apply`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    /// 叫分 with 0 (pass) to 3 points.
+    Bid(u8),
+    /// 叫地主 / 抢地主, or declining to.
+    Rob(bool),
+    /// 加倍 with a factor from `DOUBLE_FACTORS`.
+    Double(u32),
+    /// 明牌.
+    ShowHand,
+    /// Cards read as `kind` when one is given, as in `apply_play_as`.
+    Play {
+        cards: Vec<Card>,
+        kind: Option<PlayKind>,
+    },
+    Pass,
+}
+
+/// Table state wiped by an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reset {
+    /// Nobody took the landlord seat; cards were dealt again from `seed`.
+    Redeal { seed: u64 },
+    /// Everybody else passed, so the next seat leads anew.
+    NewLead,
+}
+
+/// One accepted action in `GameState::events`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameEvent {
+    pub seat: usize,
+    pub action: Action,
+    /// How a `Play` was read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub play: Option<Play>,
+    /// Seat to act after this one.
+    pub turn: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset: Option<Reset>,
+}
+
+/// Deal a game started from, before any redeal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Opening {
+    pub seed: u64,
+    pub first_bidder: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayError {
+    /// Index of the rejected action.
+    pub step: usize,
+    pub error: GameError,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "action {} was rejected: {:?}", self.step, self.error)
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl GameState {
+    /// Applies `action` for the seat and returns the event it logged.
+    pub fn apply(&mut self, player_idx: usize, action: Action) -> Result<GameEvent, GameError> {
+        match action {
+            Action::Bid(value) => self.bid(player_idx, value).map(drop),
+            Action::Rob(take) => self.rob(player_idx, take).map(drop),
+            Action::Double(factor) => self.double(player_idx, factor).map(drop),
+            Action::ShowHand => self.show_hand(player_idx).map(drop),
+            Action::Play { cards, kind } => self.apply_play_as(player_idx, cards, kind).map(drop),
+            Action::Pass => self.pass(player_idx).map(drop),
+        }?;
+        Ok(self
+            .events
+            .last()
+            .cloned()
+            .expect("accepted actions are logged"))
+    }
+
+    /// Every action the seat to move may take: its bids, its doubling
+    /// factors, or each legal play plus a pass when passing is allowed.
+    /// 明牌 is never listed since it is optional at any point.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        match self.phase {
+            GamePhase::Bidding => match &self.bidding {
+                Bidding::Points(bidding) => {
+                    let highest = bidding.highest().map_or(0, |(_, value)| value);
+                    std::iter::once(0)
+                        .chain(highest + 1..=MAX_BID)
+                        .map(Action::Bid)
+                        .collect()
+                }
+                Bidding::Rob(_) => vec![Action::Rob(true), Action::Rob(false)],
+            },
+            GamePhase::Doubling => DOUBLE_FACTORS.iter().copied().map(Action::Double).collect(),
+            GamePhase::Playing => {
+                let mut actions: Vec<Action> = self
+                    .legal_moves(self.turn)
+                    .into_iter()
+                    .map(|candidate| Action::Play {
+                        cards: candidate.cards,
+                        kind: Some(candidate.play.kind),
+                    })
+                    .collect();
+                if self.last_play.is_some() && self.last_player != Some(self.turn) {
+                    actions.push(Action::Pass);
+                }
+                actions
+            }
+            GamePhase::Finished => Vec::new(),
+        }
+    }
+
+    /// Rebuilds a game from its opening deal and the seat and action of each
+    /// step, e.g. taken from another game's `events`.
+    pub fn replay(
+        player_ids: &[u64],
+        opening: Opening,
+        rules: RuleSet,
+        actions: impl IntoIterator<Item = (usize, Action)>,
+    ) -> Result<GameState, ReplayError> {
+        let mut state =
+            GameState::with_first_bidder(player_ids, opening.seed, rules, opening.first_bidder);
+        for (step, (seat, action)) in actions.into_iter().enumerate() {
+            state
+                .apply(seat, action)
+                .map_err(|error| ReplayError { step, error })?;
+        }
+        Ok(state)
+    }
+
+    /// This game rebuilt from its log, which is always equal to `self`. Fails
+    /// with `IncompleteLog` when the log doesn't account for the state.
+    pub fn replayed(&self) -> Result<GameState, ReplayError> {
+        let state = self.replay_first(self.events.len())?;
+        if state != *self {
+            return Err(ReplayError {
+                step: self.events.len(),
+                error: GameError::IncompleteLog,
+            });
+        }
+        Ok(state)
+    }
+
+    /// Takes back the last accepted action and returns it.
+    pub fn undo(&mut self) -> Result<GameEvent, GameError> {
+        let last = self
+            .events
+            .last()
+            .cloned()
+            .ok_or(GameError::NothingToUndo)?;
+        self.rewind_to(self.events.len() - 1)?;
+        Ok(last)
+    }
+
+    /// Goes back to the position after the first `n` actions, hands in the
+    /// order they had then.
+    pub fn rewind_to(&mut self, n: usize) -> Result<(), GameError> {
+        if n > self.events.len() {
+            return Err(GameError::NothingToUndo);
+        }
+        *self = self.replay_first(n).map_err(|err| err.error)?;
+        Ok(())
+    }
+
+    fn replay_first(&self, n: usize) -> Result<GameState, ReplayError> {
+        let opening = self.opening.ok_or(ReplayError {
+            step: 0,
+            error: GameError::IncompleteLog,
+        })?;
+        let ids: Vec<u64> = self.players.iter().map(|player| player.id).collect();
+        let actions = self.events[..n]
+            .iter()
+            .map(|event| (event.seat, event.action.clone()));
+        Self::replay(&ids, opening, self.rules, actions)
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        seat: usize,
+        action: Action,
+        play: Option<Play>,
+        reset: Option<Reset>,
+    ) {
+        self.events.push(GameEvent {
+            seat,
+            action,
+            play,
+            turn: self.turn,
+            reset,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RulePreset;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    /// Plays random accepted actions until the game ends.
+    fn play_out(state: &mut GameState, rng: &mut StdRng) {
+        while state.phase != GamePhase::Finished {
+            let seat = state.turn;
+            if rng.gen_ratio(1, 20) {
+                let _ = state.apply(rng.gen_range(0..state.player_count()), Action::ShowHand);
+            }
+            let actions = state.legal_actions();
+            for action in &actions {
+                let mut tried = state.clone();
+                assert!(tried.apply(seat, action.clone()).is_ok(), "{action:?}");
+            }
+            let action = actions
+                .choose(rng)
+                .expect("the seat to move can act")
+                .clone();
+            state.apply(seat, action).unwrap();
+        }
+    }
+
+    #[test]
+    fn replay_rebuilds_every_position() {
+        for (seed, preset) in [
+            RulePreset::Classic,
+            RulePreset::Happy,
+            RulePreset::Laizi,
+            RulePreset::FourPlayer,
+            RulePreset::TwoPlayer,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let rules = preset.rules();
+            let ids: Vec<u64> = (1..=rules.variant.player_count() as u64).collect();
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            let mut state = GameState::with_rules(&ids, seed as u64, rules);
+            play_out(&mut state, &mut rng);
+            assert_eq!(state.replayed(), Ok(state.clone()));
+
+            for cut in [1, state.events.len() / 3, state.events.len() - 1] {
+                let prefix = state.events[..cut]
+                    .iter()
+                    .map(|event| (event.seat, event.action.clone()));
+                let rebuilt =
+                    GameState::replay(&ids, state.opening.unwrap(), rules, prefix).unwrap();
+                assert_eq!(rebuilt.events, state.events[..cut]);
+                assert_eq!(rebuilt.turn, state.events[cut - 1].turn);
+                assert_eq!(rebuilt.players, {
+                    let mut rewound = state.clone();
+                    rewound.rewind_to(cut).unwrap();
+                    rewound.players
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn log_records_turns_and_resets() {
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 5, RuleSet::default(), 0);
+        for seat in 0..3 {
+            state.apply(seat, Action::Bid(0)).unwrap();
+        }
+        let redeal = state.events.last().unwrap();
+        assert_eq!(
+            redeal.reset,
+            Some(Reset::Redeal {
+                seed: state.deck_seed
+            })
+        );
+        assert_ne!(state.deck_seed, state.opening.unwrap().seed);
+
+        let bidder = state.turn;
+        let event = state.apply(bidder, Action::Bid(3)).unwrap();
+        assert_eq!(event.turn, bidder);
+        let lead = state.legal_moves(bidder).remove(0);
+        let event = state
+            .apply(
+                bidder,
+                Action::Play {
+                    cards: lead.cards,
+                    kind: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(event.play, Some(lead.play));
+        let next = event.turn;
+        let pass = state.apply(next, Action::Pass).unwrap();
+        assert_eq!(pass.reset, None);
+        let pass = state.apply(pass.turn, Action::Pass).unwrap();
+        assert_eq!(pass.reset, Some(Reset::NewLead));
+        assert_eq!(pass.turn, bidder);
+
+        let logged = state.events.len();
+        assert_eq!(state.apply(next, Action::Pass), Err(GameError::NotYourTurn));
+        assert_eq!(state.events.len(), logged);
+        assert_eq!(state.replayed(), Ok(state.clone()));
+    }
+
+    #[test]
+    fn undo_restores_each_earlier_position() {
+        for (seed, preset) in [RulePreset::Happy, RulePreset::Laizi]
+            .into_iter()
+            .enumerate()
+        {
+            let rules = preset.rules();
+            let mut state = GameState::with_rules(&[1, 2, 3], 40 + seed as u64, rules);
+            let fresh = state.clone();
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            play_out(&mut state, &mut rng);
+
+            let mut history = Vec::new();
+            let mut replayed = fresh.clone();
+            for event in &state.events {
+                history.push(replayed.clone());
+                replayed.apply(event.seat, event.action.clone()).unwrap();
+            }
+            while let Some(earlier) = history.pop() {
+                let undone = state.undo().unwrap();
+                assert_eq!(state, earlier);
+                assert_eq!(undone, replayed.events[history.len()]);
+            }
+            assert_eq!(state, fresh);
+            assert_eq!(state.undo(), Err(GameError::NothingToUndo));
+
+            let mut rewound = replayed.clone();
+            rewound.rewind_to(replayed.events.len() / 2).unwrap();
+            assert_eq!(rewound.events, replayed.events[..replayed.events.len() / 2]);
+            assert_eq!(
+                rewound.rewind_to(replayed.events.len()),
+                Err(GameError::NothingToUndo)
+            );
+            rewound.rewind_to(0).unwrap();
+            assert_eq!(rewound, fresh);
+        }
+    }
+
+    #[test]
+    fn replay_reports_rejected_step() {
+        let state = GameState::with_first_bidder(&[1, 2, 3], 9, RuleSet::default(), 1);
+        let actions = [(1, Action::Bid(2)), (2, Action::Pass)];
+        assert_eq!(
+            GameState::replay(&[1, 2, 3], state.opening.unwrap(), state.rules, actions),
+            Err(ReplayError {
+                step: 1,
+                error: GameError::WrongPhase
+            })
+        );
+    }
+}
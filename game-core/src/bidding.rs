@@ -0,0 +1,340 @@
+use crate::GameError;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_BID: u8 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BiddingMode {
+    /// 叫分: bid 1, 2 or 3 points, highest bid wins.
+    #[default]
+    Points,
+    /// 叫地主/抢地主: call once, then every rob doubles the multiplier.
+    Rob,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BidOutcome {
+    Next { next_bidder: usize },
+    Landlord { landlord: usize, bid: u8 },
+    Redeal { first_bidder: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bidding {
+    Points(PointBidding),
+    Rob(RobBidding),
+}
+
+impl Bidding {
+    pub fn new(mode: BiddingMode, first_bidder: usize, players: usize) -> Self {
+        match mode {
+            BiddingMode::Points => Bidding::Points(PointBidding::new(first_bidder, players)),
+            BiddingMode::Rob => Bidding::Rob(RobBidding::new(first_bidder, players)),
+        }
+    }
+
+    pub fn mode(&self) -> BiddingMode {
+        match self {
+            Bidding::Points(_) => BiddingMode::Points,
+            Bidding::Rob(_) => BiddingMode::Rob,
+        }
+    }
+
+    pub fn first_bidder(&self) -> usize {
+        match self {
+            Bidding::Points(bidding) => bidding.first_bidder,
+            Bidding::Rob(bidding) => bidding.first_bidder,
+        }
+    }
+
+    pub fn turn(&self) -> usize {
+        match self {
+            Bidding::Points(bidding) => bidding.turn,
+            Bidding::Rob(bidding) => bidding.turn,
+        }
+    }
+
+    /// Highest point bid so far; rob bidding always plays for one point.
+    pub fn current_bid(&self) -> u8 {
+        match self {
+            Bidding::Points(bidding) => bidding.highest().map(|(_, value)| value).unwrap_or(0),
+            Bidding::Rob(bidding) => u8::from(bidding.caller.is_some()),
+        }
+    }
+
+    /// Stake multiplier produced by the bidding itself (2 per rob).
+    pub fn multiplier(&self) -> u32 {
+        match self {
+            Bidding::Points(_) => 1,
+            Bidding::Rob(bidding) => 1 << bidding.rob_count,
+        }
+    }
+}
+
+/// 叫分 state: every seat bids once, starting from `first_bidder`. A bid of
+/// zero is a pass, any other bid must beat the current highest one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointBidding {
+    pub first_bidder: usize,
+    pub turn: usize,
+    pub bids: Vec<Option<u8>>,
+}
+
+impl PointBidding {
+    pub fn new(first_bidder: usize, players: usize) -> Self {
+        PointBidding {
+            first_bidder,
+            turn: first_bidder,
+            bids: vec![None; players],
+        }
+    }
+
+    pub fn highest(&self) -> Option<(usize, u8)> {
+        self.bids
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, bid)| bid.filter(|value| *value > 0).map(|value| (idx, value)))
+            .max_by_key(|(_, value)| *value)
+    }
+
+    pub fn bid(&mut self, player_idx: usize, value: u8) -> Result<BidOutcome, GameError> {
+        if player_idx != self.turn {
+            return Err(GameError::NotYourTurn);
+        }
+        if value > MAX_BID {
+            return Err(GameError::InvalidBid);
+        }
+        if value > 0 {
+            if let Some((_, highest)) = self.highest() {
+                if value <= highest {
+                    return Err(GameError::InvalidBid);
+                }
+            }
+        }
+        self.bids[player_idx] = Some(value);
+        if value == MAX_BID {
+            return Ok(BidOutcome::Landlord {
+                landlord: player_idx,
+                bid: value,
+            });
+        }
+        if self.bids.iter().all(|bid| bid.is_some()) {
+            return Ok(match self.highest() {
+                Some((landlord, bid)) => BidOutcome::Landlord { landlord, bid },
+                None => BidOutcome::Redeal {
+                    first_bidder: (self.first_bidder + 1) % self.bids.len(),
+                },
+            });
+        }
+        self.turn = (self.turn + 1) % self.bids.len();
+        Ok(BidOutcome::Next {
+            next_bidder: self.turn,
+        })
+    }
+}
+
+/// 抢地主 state. Seats decide in turn whether to call; once someone calls,
+/// each other seat may rob once and the caller gets the final rob if anyone
+/// robbed. The last seat to call or rob becomes landlord.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RobBidding {
+    pub first_bidder: usize,
+    pub turn: usize,
+    pub caller: Option<usize>,
+    pub holder: Option<usize>,
+    pub calls: Vec<Option<bool>>,
+    pub robs: Vec<Option<bool>>,
+    pub rob_count: u32,
+}
+
+impl RobBidding {
+    pub fn new(first_bidder: usize, players: usize) -> Self {
+        RobBidding {
+            first_bidder,
+            turn: first_bidder,
+            caller: None,
+            holder: None,
+            calls: vec![None; players],
+            robs: vec![None; players],
+            rob_count: 0,
+        }
+    }
+
+    /// Latest call or rob decision for a seat.
+    pub fn decision(&self, player_idx: usize) -> Option<bool> {
+        self.robs[player_idx].or(self.calls[player_idx])
+    }
+
+    pub fn rob(&mut self, player_idx: usize, take: bool) -> Result<BidOutcome, GameError> {
+        if player_idx != self.turn {
+            return Err(GameError::NotYourTurn);
+        }
+        let players = self.calls.len();
+        let Some(caller) = self.caller else {
+            self.calls[player_idx] = Some(take);
+            if take {
+                self.caller = Some(player_idx);
+                self.holder = Some(player_idx);
+            } else if self.calls.iter().all(|call| *call == Some(false)) {
+                return Ok(BidOutcome::Redeal {
+                    first_bidder: (self.first_bidder + 1) % players,
+                });
+            }
+            self.turn = (player_idx + 1) % players;
+            return Ok(BidOutcome::Next {
+                next_bidder: self.turn,
+            });
+        };
+        if self.robs[player_idx].is_some() {
+            return Err(GameError::NotYourTurn);
+        }
+        self.robs[player_idx] = Some(take);
+        if take {
+            self.rob_count += 1;
+            self.holder = Some(player_idx);
+        }
+        let next = (player_idx + 1) % players;
+        let others_done =
+            (1..players).all(|offset| self.robs[(caller + offset) % players].is_some());
+        if player_idx == caller || (others_done && self.rob_count == 0) {
+            return Ok(BidOutcome::Landlord {
+                landlord: self.holder.unwrap_or(caller),
+                bid: 1,
+            });
+        }
+        self.turn = if others_done { caller } else { next };
+        Ok(BidOutcome::Next {
+            next_bidder: self.turn,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highest_bidder_becomes_landlord() {
+        let mut bidding = PointBidding::new(1, 3);
+        assert_eq!(bidding.bid(1, 1), Ok(BidOutcome::Next { next_bidder: 2 }));
+        assert_eq!(bidding.bid(2, 0), Ok(BidOutcome::Next { next_bidder: 0 }));
+        assert_eq!(
+            bidding.bid(0, 2),
+            Ok(BidOutcome::Landlord {
+                landlord: 0,
+                bid: 2
+            })
+        );
+    }
+
+    #[test]
+    fn max_bid_ends_bidding_immediately() {
+        let mut bidding = PointBidding::new(0, 3);
+        assert_eq!(
+            bidding.bid(0, 3),
+            Ok(BidOutcome::Landlord {
+                landlord: 0,
+                bid: 3
+            })
+        );
+    }
+
+    #[test]
+    fn bid_must_beat_highest() {
+        let mut bidding = PointBidding::new(0, 3);
+        bidding.bid(0, 2).unwrap();
+        assert_eq!(bidding.bid(1, 2), Err(GameError::InvalidBid));
+        assert_eq!(bidding.bid(1, 4), Err(GameError::InvalidBid));
+        assert_eq!(bidding.bid(2, 0), Err(GameError::NotYourTurn));
+    }
+
+    #[test]
+    fn all_pass_requests_redeal_with_next_first_bidder() {
+        let mut bidding = PointBidding::new(2, 3);
+        bidding.bid(2, 0).unwrap();
+        bidding.bid(0, 0).unwrap();
+        assert_eq!(
+            bidding.bid(1, 0),
+            Ok(BidOutcome::Redeal { first_bidder: 0 })
+        );
+    }
+
+    #[test]
+    fn unopposed_call_takes_landlord() {
+        let mut bidding = RobBidding::new(0, 3);
+        assert_eq!(
+            bidding.rob(0, false),
+            Ok(BidOutcome::Next { next_bidder: 1 })
+        );
+        assert_eq!(
+            bidding.rob(1, true),
+            Ok(BidOutcome::Next { next_bidder: 2 })
+        );
+        assert_eq!(
+            bidding.rob(2, false),
+            Ok(BidOutcome::Next { next_bidder: 0 })
+        );
+        assert_eq!(
+            bidding.rob(0, false),
+            Ok(BidOutcome::Landlord {
+                landlord: 1,
+                bid: 1
+            })
+        );
+        assert_eq!(bidding.rob_count, 0);
+    }
+
+    #[test]
+    fn each_rob_doubles_and_caller_robs_last() {
+        let mut bidding = Bidding::new(BiddingMode::Rob, 0, 3);
+        let Bidding::Rob(rob) = &mut bidding else {
+            unreachable!()
+        };
+        rob.rob(0, true).unwrap();
+        rob.rob(1, true).unwrap();
+        assert_eq!(rob.rob(2, true), Ok(BidOutcome::Next { next_bidder: 0 }));
+        assert_eq!(
+            rob.rob(0, true),
+            Ok(BidOutcome::Landlord {
+                landlord: 0,
+                bid: 1
+            })
+        );
+        assert_eq!(bidding.multiplier(), 8);
+    }
+
+    #[test]
+    fn last_robber_wins_when_caller_declines_final_rob() {
+        let mut bidding = RobBidding::new(2, 3);
+        bidding.rob(2, true).unwrap();
+        bidding.rob(0, true).unwrap();
+        bidding.rob(1, false).unwrap();
+        assert_eq!(
+            bidding.rob(2, false),
+            Ok(BidOutcome::Landlord {
+                landlord: 0,
+                bid: 1
+            })
+        );
+        assert_eq!(bidding.rob_count, 1);
+    }
+
+    #[test]
+    fn nobody_calling_requests_redeal() {
+        let mut bidding = RobBidding::new(1, 3);
+        bidding.rob(1, false).unwrap();
+        bidding.rob(2, false).unwrap();
+        assert_eq!(
+            bidding.rob(0, false),
+            Ok(BidOutcome::Redeal { first_bidder: 2 })
+        );
+    }
+
+    #[test]
+    fn out_of_turn_rob_is_rejected() {
+        let mut bidding = RobBidding::new(0, 3);
+        bidding.rob(0, true).unwrap();
+        assert_eq!(bidding.rob(2, true), Err(GameError::NotYourTurn));
+        assert_eq!(bidding.rob(0, true), Err(GameError::NotYourTurn));
+    }
+}
@@ -0,0 +1,589 @@
+use crate::movegen::{
+    kind_order, rank_index, take_cards, Counts, BLACK_JOKER, CHAIN_RANKS, RANKS, RED_JOKER,
+};
+use crate::{bomb_strength, classify_plain_all, sort_hand, Card, Move, PlayKind, RuleSet};
+use std::collections::{BTreeSet, HashMap};
+
+/// Most decompositions `decompose` lists besides the best one.
+pub const MAX_ALTERNATIVES: usize = 16;
+
+/// Plays tried while collecting alternatives, so hands with many equivalent
+/// splits stay cheap.
+const MAX_VISITS: usize = 4096;
+
+/// A way to play out a whole hand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decomposition {
+    pub plays: Vec<Move>,
+    /// 手数: plays that need the lead. Bombs and the rocket can be played over
+    /// anything, so they are not counted.
+    pub steps: usize,
+}
+
+/// Result of splitting a hand (拆牌) into as few plays as possible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandAnalysis {
+    /// Fewest plays, keeping as many bombs as possible among those.
+    pub best: Decomposition,
+    /// Other splits with the same number of plays, best steps first.
+    pub alternatives: Vec<Decomposition>,
+}
+
+impl HandAnalysis {
+    pub fn play_count(&self) -> usize {
+        self.best.plays.len()
+    }
+
+    pub fn steps(&self) -> usize {
+        self.best.steps
+    }
+}
+
+/// Splits `hand` into the fewest legal plays under `rules`, reading wild
+/// cards at face value. `None` when the rules leave some card unplayable,
+/// e.g. with singles disabled.
+pub fn decompose(hand: &[Card], rules: &RuleSet) -> Option<HandAnalysis> {
+    let mut solver = Solver::new(rules);
+    let mut counts = [0u8; 15];
+    for card in hand {
+        counts[rank_index(card.rank)] += 1;
+    }
+    let (plays, _) = solver.solve(&counts);
+    if plays == INFEASIBLE {
+        return None;
+    }
+
+    let mut search = PathSearch {
+        seen: BTreeSet::new(),
+        paths: Vec::new(),
+        budget: MAX_VISITS,
+    };
+    search.add(solver.best_path(counts));
+    solver.optimal_paths(&counts, &mut Vec::new(), &mut search);
+    let mut found: Vec<Decomposition> = search
+        .paths
+        .iter()
+        .map(|path| materialize(hand, path, rules))
+        .collect();
+    // Stable, so ties keep the search order.
+    found.sort_by_key(|decomposition| decomposition.steps);
+    let mut found = found.into_iter();
+    let best = found.next()?;
+    Some(HandAnalysis {
+        best,
+        alternatives: found.collect(),
+    })
+}
+
+/// Turns a path of rank counts into concrete cards, taking each play's cards
+/// from what is left of the hand.
+fn materialize(hand: &[Card], path: &[(Counts, PlayKind)], rules: &RuleSet) -> Decomposition {
+    let mut left = hand.to_vec();
+    sort_hand(&mut left);
+    let mut plays = Vec::with_capacity(path.len());
+    for (taken, kind) in path {
+        let cards = take_cards(&left, taken);
+        for card in &cards {
+            if let Some(idx) = left.iter().position(|other| other == card) {
+                left.remove(idx);
+            }
+        }
+        let readings = classify_plain_all(&cards, rules);
+        let play = readings
+            .iter()
+            .find(|play| play.kind == *kind)
+            .or(readings.first())
+            .cloned();
+        if let Some(play) = play {
+            plays.push(Move { cards, play });
+        }
+    }
+    plays.sort_by_cached_key(|candidate| {
+        let play = &candidate.play;
+        (
+            bomb_strength(play),
+            kind_order(play.kind),
+            play.size,
+            play.main_rank,
+        )
+    });
+    let steps = plays
+        .iter()
+        .filter(|candidate| !candidate.play.kind.is_bomb())
+        .count();
+    Decomposition { plays, steps }
+}
+
+const INFEASIBLE: u8 = u8::MAX;
+
+/// Fewest plays and, among those, fewest steps for the cards left.
+type Score = (u8, u8);
+
+/// Decompositions found so far, keyed by their plays in any order.
+struct PathSearch {
+    seen: BTreeSet<Vec<(Counts, usize)>>,
+    paths: Vec<Vec<(Counts, PlayKind)>>,
+    budget: usize,
+}
+
+impl PathSearch {
+    fn add(&mut self, path: Vec<(Counts, PlayKind)>) {
+        let mut key: Vec<(Counts, usize)> = path
+            .iter()
+            .map(|(taken, kind)| (*taken, kind_order(*kind)))
+            .collect();
+        key.sort();
+        if self.seen.insert(key) {
+            self.paths.push(path);
+        }
+    }
+}
+
+/// Memoized search over rank counts. Every decomposition has a play holding
+/// the lowest rank left, so only those plays are tried at each step.
+struct Solver<'a> {
+    rules: &'a RuleSet,
+    decks: u8,
+    memo: HashMap<u64, Score>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(rules: &'a RuleSet) -> Self {
+        Solver {
+            rules,
+            decks: rules.variant.decks() as u8,
+            memo: HashMap::new(),
+        }
+    }
+
+    fn solve(&mut self, counts: &Counts) -> Score {
+        let Some(low) = counts.iter().position(|count| *count > 0) else {
+            return (0, 0);
+        };
+        if let Some(score) = self.memo.get(&pack(counts)) {
+            return *score;
+        }
+        let mut best = (INFEASIBLE, INFEASIBLE);
+        let moves = self.plays_with(counts, low);
+        for (taken, kind) in moves {
+            let (plays, steps) = self.solve(&minus(counts, &taken));
+            if plays == INFEASIBLE {
+                continue;
+            }
+            best = best.min((plays + 1, steps + u8::from(!kind.is_bomb())));
+        }
+        self.memo.insert(pack(counts), best);
+        best
+    }
+
+    /// Follows the first play at each step that keeps both the play count
+    /// and the steps optimal.
+    fn best_path(&mut self, mut counts: Counts) -> Vec<(Counts, PlayKind)> {
+        let mut path = Vec::new();
+        while let Some(low) = counts.iter().position(|count| *count > 0) {
+            let score = self.solve(&counts);
+            let next = self
+                .plays_with(&counts, low)
+                .into_iter()
+                .find(|(taken, kind)| {
+                    let (plays, steps) = self.solve(&minus(&counts, taken));
+                    plays != INFEASIBLE && (plays + 1, steps + u8::from(!kind.is_bomb())) == score
+                });
+            let Some((taken, kind)) = next else {
+                break;
+            };
+            counts = minus(&counts, &taken);
+            path.push((taken, kind));
+        }
+        path
+    }
+
+    /// Collects distinct decompositions that reach the optimum, in search
+    /// order, until enough are found or the visit budget runs out.
+    fn optimal_paths(
+        &mut self,
+        counts: &Counts,
+        path: &mut Vec<(Counts, PlayKind)>,
+        search: &mut PathSearch,
+    ) {
+        let Some(low) = counts.iter().position(|count| *count > 0) else {
+            search.add(path.clone());
+            return;
+        };
+        let (plays, _) = self.solve(counts);
+        for (taken, kind) in self.plays_with(counts, low) {
+            if search.budget == 0 || search.paths.len() > MAX_ALTERNATIVES {
+                return;
+            }
+            search.budget -= 1;
+            let rest = minus(counts, &taken);
+            if self.solve(&rest).0.saturating_add(1) != plays {
+                continue;
+            }
+            path.push((taken, kind));
+            self.optimal_paths(&rest, path, search);
+            path.pop();
+        }
+    }
+
+    /// Every play that uses at least one card of `low`, the lowest rank left.
+    /// Chains holding `low` must start there; kicker plays may carry `low`
+    /// under a higher core.
+    fn plays_with(&self, counts: &Counts, low: usize) -> Vec<(Counts, PlayKind)> {
+        let mut out = Vec::new();
+        let allows = |kind: PlayKind| self.rules.allows(kind);
+        let have = |rank: usize, size: u8| counts[rank] >= size;
+
+        let sets = [
+            (PlayKind::Single, 1),
+            (PlayKind::Pair, 2),
+            (PlayKind::Triple, 3),
+        ];
+        for (kind, size) in sets {
+            if allows(kind) && have(low, size) {
+                out.push((one(low, size), kind));
+            }
+        }
+        if allows(PlayKind::Bomb) && low < BLACK_JOKER {
+            for size in 4..=counts[low].min(4 * self.decks) {
+                out.push((one(low, size), PlayKind::Bomb));
+            }
+        }
+        if allows(PlayKind::Rocket)
+            && low == BLACK_JOKER
+            && have(BLACK_JOKER, self.decks)
+            && have(RED_JOKER, self.decks)
+        {
+            let mut rocket = one(BLACK_JOKER, self.decks);
+            rocket[RED_JOKER] = self.decks;
+            out.push((rocket, PlayKind::Rocket));
+        }
+
+        // Triples and quads with kickers, `low` either as the core or as a
+        // kicker under a higher core. Kickers are walked lowest first, so a
+        // kicker set holds `low` exactly when it starts with it.
+        let present = |from: usize, core: usize| {
+            (from..RANKS.len()).filter(move |rank| *rank != core && counts[*rank] > 0)
+        };
+        for core in (low..RANKS.len()).filter(|core| have(*core, 3)) {
+            let holds_low = |first: usize| core == low || first == low;
+            for kicker in present(low, core).take_while(|kicker| holds_low(*kicker)) {
+                if allows(PlayKind::TripleSingle) {
+                    out.push((add(&one(core, 3), &one(kicker, 1)), PlayKind::TripleSingle));
+                }
+                if allows(PlayKind::TriplePair) && have(kicker, 2) {
+                    out.push((add(&one(core, 3), &one(kicker, 2)), PlayKind::TriplePair));
+                }
+            }
+            if !have(core, 4) {
+                continue;
+            }
+            for first in present(low, core).take_while(|first| holds_low(*first)) {
+                if allows(PlayKind::FourTwoSingle) {
+                    for second in present(first + 1, core) {
+                        let kick = add(&one(first, 1), &one(second, 1));
+                        out.push((add(&one(core, 4), &kick), PlayKind::FourTwoSingle));
+                    }
+                }
+                if allows(PlayKind::FourTwoPair) && core < BLACK_JOKER && have(first, 2) {
+                    if have(first, 4) {
+                        out.push((add(&one(core, 4), &one(first, 4)), PlayKind::FourTwoPair));
+                    }
+                    for second in present(first + 1, core).filter(|second| have(*second, 2)) {
+                        let kick = add(&one(first, 2), &one(second, 2));
+                        out.push((add(&one(core, 4), &kick), PlayKind::FourTwoPair));
+                    }
+                }
+            }
+        }
+
+        let chains = [
+            (PlayKind::Straight, 1, self.rules.min_straight),
+            (PlayKind::DoubleStraight, 2, self.rules.min_double_straight),
+            (PlayKind::Airplane, 3, self.rules.min_airplane),
+        ];
+        for (kind, width, min_len) in chains {
+            if allows(kind) {
+                for end in chain_ends(counts, low, width, min_len) {
+                    out.push((chain(low, end, width), kind));
+                }
+            }
+        }
+
+        // Airplanes with wings: the chain starts at `low`, or `low` is a wing.
+        let wings_kinds = [PlayKind::AirplaneSingles, PlayKind::AirplanePairs];
+        if wings_kinds.iter().any(|kind| allows(*kind)) {
+            for start in low..CHAIN_RANKS {
+                for end in chain_ends(counts, start, 3, self.rules.min_airplane) {
+                    let body = chain(start, end, 3);
+                    let len = (end - start) as u8;
+                    let mut pool = [0u8; 15];
+                    for rank in (0..RANKS.len()).filter(|rank| !(start..end).contains(rank)) {
+                        pool[rank] = counts[rank];
+                    }
+                    // A wing of `low` is set aside first so every pick holds one.
+                    let mut forced = [0u8; 15];
+                    if start != low {
+                        if pool[low] == 0 {
+                            continue;
+                        }
+                        pool[low] -= 1;
+                        forced[low] = 1;
+                    }
+                    let wings_left = len - forced[low];
+                    if allows(PlayKind::AirplaneSingles) {
+                        let mut picks = Vec::new();
+                        pick(&pool, wings_left, 0, forced, &mut picks);
+                        for wings in picks {
+                            if wings[BLACK_JOKER] == 0 || wings[RED_JOKER] == 0 {
+                                out.push((add(&body, &wings), PlayKind::AirplaneSingles));
+                            }
+                        }
+                    }
+                    if allows(PlayKind::AirplanePairs) && (forced[low] == 0 || pool[low] >= 1) {
+                        let pairs: Vec<usize> = (0..RANKS.len())
+                            .filter(|rank| forced[*rank] == 0 && pool[*rank] >= 2)
+                            .collect();
+                        let mut combos = Vec::new();
+                        choose(&pairs, wings_left as usize, 0, &mut Vec::new(), &mut combos);
+                        for combo in combos {
+                            let wings = combo.iter().fold(add(&forced, &forced), |wings, rank| {
+                                add(&wings, &one(*rank, 2))
+                            });
+                            out.push((add(&body, &wings), PlayKind::AirplanePairs));
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Four bits per rank, enough for two decks.
+fn pack(counts: &Counts) -> u64 {
+    counts
+        .iter()
+        .fold(0, |key, count| (key << 4) | u64::from(*count))
+}
+
+fn one(rank: usize, size: u8) -> Counts {
+    let mut counts = [0u8; 15];
+    counts[rank] = size;
+    counts
+}
+
+fn add(a: &Counts, b: &Counts) -> Counts {
+    let mut sum = *a;
+    for (slot, extra) in sum.iter_mut().zip(b) {
+        *slot += extra;
+    }
+    sum
+}
+
+fn minus(a: &Counts, b: &Counts) -> Counts {
+    let mut rest = *a;
+    for (slot, taken) in rest.iter_mut().zip(b) {
+        *slot -= taken;
+    }
+    rest
+}
+
+/// `width` cards of each rank in `start..end`.
+fn chain(start: usize, end: usize, width: u8) -> Counts {
+    let mut counts = [0u8; 15];
+    for slot in &mut counts[start..end] {
+        *slot = width;
+    }
+    counts
+}
+
+/// Exclusive ends of every chain from `start` at least `min_len` long with
+/// `width` cards of each rank.
+fn chain_ends(counts: &Counts, start: usize, width: u8, min_len: usize) -> Vec<usize> {
+    let min_len = min_len.max(2);
+    let mut ends = Vec::new();
+    let mut end = start;
+    while end < CHAIN_RANKS && counts[end] >= width {
+        end += 1;
+        if end - start >= min_len {
+            ends.push(end);
+        }
+    }
+    ends
+}
+
+/// Multisets of `size` cards from `pool` added to `picked`, built rank by
+/// rank from `from`.
+fn pick(pool: &Counts, size: u8, from: usize, picked: Counts, out: &mut Vec<Counts>) {
+    if size == 0 {
+        out.push(picked);
+        return;
+    }
+    for rank in from..pool.len() {
+        for count in 1..=pool[rank].min(size) {
+            let mut next = picked;
+            next[rank] += count;
+            pick(pool, size - count, rank + 1, next, out);
+        }
+    }
+}
+
+/// `count`-element subsets of `items`, in order.
+fn choose(
+    items: &[usize],
+    count: usize,
+    from: usize,
+    chosen: &mut Vec<usize>,
+    out: &mut Vec<Vec<usize>>,
+) {
+    if chosen.len() == count {
+        out.push(chosen.clone());
+        return;
+    }
+    for idx in from..items.len() {
+        chosen.push(items[idx]);
+        choose(items, count, idx + 1, chosen, out);
+        chosen.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_hand;
+    use crate::{legal_moves, shuffled_deck, Variant};
+
+    /// Fewest plays by trying every legal lead, memoized on the cards left.
+    fn brute_force(hand: &[Card], rules: &RuleSet, memo: &mut HashMap<Vec<Card>, usize>) -> usize {
+        if hand.is_empty() {
+            return 0;
+        }
+        let mut key = hand.to_vec();
+        sort_hand(&mut key);
+        if let Some(best) = memo.get(&key) {
+            return *best;
+        }
+        let mut best = usize::MAX;
+        for candidate in legal_moves(hand, None, rules) {
+            let mut rest = key.clone();
+            for card in &candidate.cards {
+                let idx = rest.iter().position(|other| other == card).unwrap();
+                rest.remove(idx);
+            }
+            best = best.min(1 + brute_force(&rest, rules, memo));
+        }
+        memo.insert(key, best);
+        best
+    }
+
+    fn check_plays(hand: &[Card], analysis: &HandAnalysis, rules: &RuleSet) {
+        for decomposition in std::iter::once(&analysis.best).chain(&analysis.alternatives) {
+            assert_eq!(decomposition.plays.len(), analysis.play_count());
+            let mut used: Vec<Card> = decomposition
+                .plays
+                .iter()
+                .flat_map(|candidate| candidate.cards.clone())
+                .collect();
+            let mut all = hand.to_vec();
+            sort_hand(&mut used);
+            sort_hand(&mut all);
+            assert_eq!(used, all, "a decomposition uses every card once");
+            for candidate in &decomposition.plays {
+                assert!(classify_plain_all(&candidate.cards, rules).contains(&candidate.play));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_on_small_hands() {
+        let rules = RuleSet::default();
+        for seed in 0..30 {
+            let deck = shuffled_deck(seed);
+            let hand = &deck[..10];
+            let analysis = decompose(hand, &rules).unwrap();
+            let expected = brute_force(hand, &rules, &mut HashMap::new());
+            assert_eq!(analysis.play_count(), expected, "hand {hand:?}");
+            check_plays(hand, &analysis, &rules);
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_with_two_decks() {
+        let rules = RuleSet {
+            variant: Variant::FourPlayer,
+            ..RuleSet::default()
+        };
+        for seed in 0..10 {
+            let deck = Variant::FourPlayer.shuffled_deck(seed);
+            let hand = &deck[..10];
+            let analysis = decompose(hand, &rules).unwrap();
+            let expected = brute_force(hand, &rules, &mut HashMap::new());
+            assert_eq!(analysis.play_count(), expected, "hand {hand:?}");
+            check_plays(hand, &analysis, &rules);
+        }
+    }
+
+    #[test]
+    fn uses_chains_and_kickers() {
+        let hand = parse_hand("34567888999JQ2222").unwrap();
+        let rules = RuleSet::default();
+        let analysis = decompose(&hand, &rules).unwrap();
+        check_plays(&hand, &analysis, &rules);
+        let kinds: Vec<PlayKind> = analysis
+            .best
+            .plays
+            .iter()
+            .map(|candidate| candidate.play.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                PlayKind::Straight,
+                PlayKind::AirplaneSingles,
+                PlayKind::Bomb
+            ]
+        );
+        assert_eq!(analysis.steps(), 2);
+        assert!(
+            analysis.alternatives.iter().any(|other| other.steps == 3),
+            "splitting the bomb as four with two also takes three plays"
+        );
+    }
+
+    #[test]
+    fn keeps_bombs_when_splitting_them_saves_nothing() {
+        let hand = parse_hand("5555 9Q").unwrap();
+        let analysis = decompose(&hand, &RuleSet::default()).unwrap();
+        assert_eq!(analysis.play_count(), 1);
+        assert_eq!(analysis.best.plays[0].play.kind, PlayKind::FourTwoSingle);
+        let strict = RuleSet {
+            four_with_two: false,
+            ..RuleSet::default()
+        };
+        let analysis = decompose(&hand, &strict).unwrap();
+        assert_eq!(analysis.play_count(), 3);
+        assert_eq!(analysis.steps(), 2, "the bomb stays whole");
+    }
+
+    #[test]
+    fn reports_unplayable_hands() {
+        let rules = RuleSet {
+            enabled_kinds: crate::KindSet::ALL.without(PlayKind::Single),
+            ..RuleSet::default()
+        };
+        let hand = parse_hand("34").unwrap();
+        assert_eq!(decompose(&hand, &rules), None);
+        assert!(decompose(&[], &rules).unwrap().best.plays.is_empty());
+    }
+
+    #[test]
+    fn full_hands_split_into_their_own_cards() {
+        let rules = RuleSet::default();
+        for seed in 0..50 {
+            let deck = shuffled_deck(seed);
+            let analysis = decompose(&deck[..20], &rules).unwrap();
+            check_plays(&deck[..20], &analysis, &rules);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::GameError;
+use serde::{Deserialize, Serialize};
+
+/// Factors a seat may pick: no double, 加倍 and 超级加倍.
+pub const DOUBLE_FACTORS: [u32; 3] = [1, 2, 4];
+
+/// 加倍 state, entered once the landlord is known. The farmers decide in seat
+/// order after the landlord, then the landlord decides last.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Doubling {
+    pub order: Vec<usize>,
+    pub factors: Vec<Option<u32>>,
+}
+
+impl Doubling {
+    pub fn new(landlord: usize, players: usize) -> Self {
+        let order = (1..=players)
+            .map(|offset| (landlord + offset) % players)
+            .collect();
+        Doubling {
+            order,
+            factors: vec![None; players],
+        }
+    }
+
+    /// Seat that decides next, or `None` once everybody has decided.
+    pub fn turn(&self) -> Option<usize> {
+        self.order
+            .iter()
+            .copied()
+            .find(|idx| self.factors[*idx].is_none())
+    }
+
+    /// Factor a seat settled on; seats that have not decided count as 1.
+    pub fn factor(&self, player_idx: usize) -> u32 {
+        self.factors.get(player_idx).copied().flatten().unwrap_or(1)
+    }
+
+    /// Records a decision and returns the next seat to decide, if any.
+    pub fn double(&mut self, player_idx: usize, factor: u32) -> Result<Option<usize>, GameError> {
+        if self.turn() != Some(player_idx) {
+            return Err(GameError::NotYourTurn);
+        }
+        if !DOUBLE_FACTORS.contains(&factor) {
+            return Err(GameError::InvalidDouble);
+        }
+        self.factors[player_idx] = Some(factor);
+        Ok(self.turn())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn farmers_decide_before_landlord() {
+        let mut doubling = Doubling::new(1, 3);
+        assert_eq!(doubling.order, vec![2, 0, 1]);
+        assert_eq!(doubling.double(1, 2), Err(GameError::NotYourTurn));
+        assert_eq!(doubling.double(2, 4), Ok(Some(0)));
+        assert_eq!(doubling.double(0, 1), Ok(Some(1)));
+        assert_eq!(doubling.double(1, 2), Ok(None));
+        assert_eq!(
+            (0..3).map(|idx| doubling.factor(idx)).collect::<Vec<_>>(),
+            vec![1, 2, 4]
+        );
+    }
+
+    #[test]
+    fn only_known_factors_are_accepted() {
+        let mut doubling = Doubling::new(0, 2);
+        assert_eq!(doubling.double(1, 3), Err(GameError::InvalidDouble));
+        assert_eq!(doubling.double(1, 0), Err(GameError::InvalidDouble));
+        assert_eq!(doubling.turn(), Some(1));
+    }
+}
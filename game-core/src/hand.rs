@@ -0,0 +1,590 @@
+use crate::movegen::{rank_index, BLACK_JOKER, CHAIN_RANKS, RANKS, RED_JOKER};
+use crate::{can_beat_with, Card, Play, PlayKind, Rank, RuleSet, Suit};
+use std::fmt;
+
+const SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+/// Cards per rank packed four bits to a rank, in `RANKS` order from the
+/// three up to the red joker. Suits are dropped, which is all the rules look
+/// at, so classification, beat checks and move generation never allocate a
+/// card list. Holds up to eight cards of a rank, enough for two decks.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RankCounts(u64);
+
+impl RankCounts {
+    pub const EMPTY: RankCounts = RankCounts(0);
+
+    pub fn from_cards(cards: &[Card]) -> Self {
+        cards
+            .iter()
+            .fold(Self::EMPTY, |counts, card| counts.with(card.rank, 1))
+    }
+
+    pub fn count(self, rank: Rank) -> u8 {
+        self.at(rank_index(rank))
+    }
+
+    /// `self` with `extra` more cards of `rank`.
+    pub fn with(self, rank: Rank, extra: u8) -> Self {
+        self.plus(rank_index(rank), extra)
+    }
+
+    pub fn len(self) -> usize {
+        // Sum the nibbles pairwise into bytes, then add the bytes up.
+        let bytes = (self.0 & 0x0F0F_0F0F_0F0F_0F0F) + ((self.0 >> 4) & 0x0F0F_0F0F_0F0F_0F0F);
+        (bytes.wrapping_mul(0x0101_0101_0101_0101) >> 56) as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every card of `other` is in `self`.
+    pub fn contains(self, other: RankCounts) -> bool {
+        (0..RANKS.len()).all(|idx| self.at(idx) >= other.at(idx))
+    }
+
+    /// `self` with `other` added. Counts must stay below 16 per rank.
+    pub fn merge(self, other: RankCounts) -> Self {
+        RankCounts(self.0 + other.0)
+    }
+
+    /// `self` without `other`, or `None` if `other` is not contained.
+    pub fn remove(self, other: RankCounts) -> Option<Self> {
+        self.contains(other).then(|| RankCounts(self.0 - other.0))
+    }
+
+    /// Ranks held, lowest first, with their counts.
+    pub fn iter(self) -> impl Iterator<Item = (Rank, u8)> {
+        (0..RANKS.len())
+            .map(move |idx| (RANKS[idx], self.at(idx)))
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// Cards with these ranks, suits handed out clubs first.
+    pub fn to_cards(self) -> Vec<Card> {
+        let mut cards = Vec::with_capacity(self.len());
+        for (rank, count) in self.iter() {
+            for copy in 0..usize::from(count) {
+                let suit = if rank.is_joker() {
+                    Suit::Joker
+                } else {
+                    SUITS[copy % SUITS.len()]
+                };
+                cards.push(Card { rank, suit });
+            }
+        }
+        cards
+    }
+
+    /// The first cards of each rank in `hand` that make up these counts, or
+    /// `None` if `hand` is short of some rank.
+    pub fn take_from(self, hand: &[Card]) -> Option<Vec<Card>> {
+        let mut left = self;
+        let mut cards = Vec::with_capacity(self.len());
+        for card in hand {
+            let idx = rank_index(card.rank);
+            if left.at(idx) > 0 {
+                left = left.minus(idx, 1);
+                cards.push(*card);
+            }
+        }
+        left.is_empty().then_some(cards)
+    }
+
+    /// First face-value reading allowed by `rules`, like `classify_all_with`
+    /// without a wild rank.
+    pub fn classify(self, rules: &RuleSet) -> Option<Play> {
+        let mut first = None;
+        self.readings(rules, |play| {
+            first.get_or_insert(play);
+        });
+        first
+    }
+
+    /// Every face-value reading allowed by `rules`, in `classify_all` order.
+    pub fn classify_all(self, rules: &RuleSet) -> Vec<Play> {
+        let mut plays = Vec::new();
+        self.readings(rules, |play| plays.push(play));
+        plays
+    }
+
+    /// First reading of these cards that beats `last`.
+    pub fn beats(self, last: &Play, rules: &RuleSet) -> Option<Play> {
+        let mut found = None;
+        self.readings(rules, |play| {
+            if found.is_none() && can_beat_with(last, &play, rules) {
+                found = Some(play);
+            }
+        });
+        found
+    }
+
+    /// Every play this hand can make against `last_play` (or lead with when
+    /// `None`), with the ranks it uses. Covers the same plays as
+    /// `legal_moves` on natural cards, in generation order.
+    pub fn legal_moves(self, last_play: Option<&Play>, rules: &RuleSet) -> Vec<(RankCounts, Play)> {
+        let mut candidates = Vec::new();
+        self.candidates(last_play.map(|play| play.kind), rules, &mut candidates);
+        candidates.sort_unstable();
+        candidates.dedup();
+        let mut moves = Vec::new();
+        for taken in candidates {
+            taken.readings(rules, |play| {
+                if last_play.is_none_or(|prev| can_beat_with(prev, &play, rules)) {
+                    moves.push((taken, play));
+                }
+            });
+        }
+        moves
+    }
+
+    fn at(self, idx: usize) -> u8 {
+        ((self.0 >> (4 * idx)) & 0xF) as u8
+    }
+
+    fn plus(self, idx: usize, extra: u8) -> Self {
+        RankCounts(self.0 + (u64::from(extra) << (4 * idx)))
+    }
+
+    fn minus(self, idx: usize, fewer: u8) -> Self {
+        RankCounts(self.0 - (u64::from(fewer) << (4 * idx)))
+    }
+
+    fn single(idx: usize, count: u8) -> Self {
+        Self::EMPTY.plus(idx, count)
+    }
+
+    /// `count` cards of every rank in `ranks`.
+    fn run(ranks: std::ops::Range<usize>, count: u8) -> Self {
+        ranks.fold(Self::EMPTY, |run, idx| run.plus(idx, count))
+    }
+
+    /// Face-value classification mirroring `classify_face`, reporting each
+    /// reading the rules allow in the same order.
+    fn readings(self, rules: &RuleSet, mut emit: impl FnMut(Play)) {
+        let mut push = |kind: PlayKind, idx: usize, size: usize| {
+            if rules.allows(kind) {
+                emit(Play::new(kind, RANKS[idx], size));
+            }
+        };
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let decks = rules.variant.decks() as u8;
+        let mut unique = 0;
+        let mut lowest = usize::MAX;
+        let mut highest = 0;
+        let mut uniform = None;
+        let mut same_count = true;
+        for idx in 0..RANKS.len() {
+            let count = self.at(idx);
+            if count == 0 {
+                continue;
+            }
+            unique += 1;
+            lowest = lowest.min(idx);
+            highest = idx;
+            match uniform {
+                None => uniform = Some(count),
+                Some(first) if first != count => same_count = false,
+                Some(_) => {}
+            }
+        }
+        let with_count = |wanted: u8| (0..RANKS.len()).find(|idx| self.at(*idx) == wanted);
+
+        if len == 2 * usize::from(decks)
+            && self.at(BLACK_JOKER) == decks
+            && self.at(RED_JOKER) == decks
+        {
+            push(PlayKind::Rocket, RED_JOKER, len);
+        }
+        if len >= 4 && len <= 4 * usize::from(decks) && unique == 1 && lowest < BLACK_JOKER {
+            push(PlayKind::Bomb, lowest, len);
+        }
+        match (len, unique) {
+            (1, _) => push(PlayKind::Single, lowest, 1),
+            (2, 1) => push(PlayKind::Pair, lowest, 2),
+            (3, 1) => push(PlayKind::Triple, lowest, 3),
+            (4, 2) => {
+                if let Some(idx) = with_count(3) {
+                    push(PlayKind::TripleSingle, idx, 4);
+                }
+            }
+            (5, 2) => {
+                if let Some(idx) = with_count(3) {
+                    push(PlayKind::TriplePair, idx, 5);
+                }
+            }
+            (6, 3) => {
+                if let Some(idx) = with_count(4) {
+                    push(PlayKind::FourTwoSingle, idx, 6);
+                }
+            }
+            _ => {}
+        }
+        if len == 8 {
+            let pairs: usize = (0..RANKS.len())
+                .map(|idx| usize::from(self.at(idx) / 2))
+                .sum();
+            for quad in (0..BLACK_JOKER).rev().filter(|idx| self.at(*idx) == 4) {
+                let kickers_paired =
+                    (0..RANKS.len()).all(|idx| idx == quad || self.at(idx).is_multiple_of(2));
+                if kickers_paired && pairs == 4 {
+                    push(PlayKind::FourTwoPair, quad, 8);
+                }
+            }
+        }
+
+        let chained = same_count && highest < CHAIN_RANKS && highest - lowest + 1 == unique;
+        let chain = |width: u8, min_len: usize| {
+            chained && uniform == Some(width) && unique >= min_len.max(2)
+        };
+        if chain(1, rules.min_straight) {
+            push(PlayKind::Straight, highest, len);
+        }
+        if chain(2, rules.min_double_straight) {
+            push(PlayKind::DoubleStraight, highest, unique);
+        }
+        if chain(3, rules.min_airplane) {
+            push(PlayKind::Airplane, highest, unique);
+        }
+        let min_airplane = rules.min_airplane.max(2);
+        if len >= 5 * min_airplane && len.is_multiple_of(5) {
+            for top in self.airplane_tops(len / 5, 2) {
+                push(PlayKind::AirplanePairs, top, len / 5);
+            }
+        }
+        if len >= 4 * min_airplane && len.is_multiple_of(4) {
+            for top in self.airplane_tops(len / 4, 1) {
+                push(PlayKind::AirplaneSingles, top, len / 4);
+            }
+        }
+    }
+
+    /// Tops of the chains of exactly-three triples, `chain_len` long, whose
+    /// other cards make valid wings, highest first.
+    fn airplane_tops(self, chain_len: usize, wing_size: u8) -> Vec<usize> {
+        let mut tops = Vec::new();
+        if !(2..=CHAIN_RANKS).contains(&chain_len) {
+            return tops;
+        }
+        for start in (0..=CHAIN_RANKS - chain_len).rev() {
+            let body = start..start + chain_len;
+            if body.clone().any(|idx| self.at(idx) != 3) {
+                continue;
+            }
+            let Some(wings) = self.remove(Self::run(body.clone(), 3)) else {
+                continue;
+            };
+            let valid = if wing_size == 1 {
+                wings.at(BLACK_JOKER) == 0 || wings.at(RED_JOKER) == 0
+            } else {
+                wings.iter().count() == chain_len && wings.iter().all(|(_, count)| count == 2)
+            };
+            if valid {
+                tops.push(body.end - 1);
+            }
+        }
+        tops
+    }
+
+    /// Pushes card sets shaped like every kind that could answer `lead`
+    /// (every kind when leading), plus all bombs.
+    fn candidates(self, lead: Option<PlayKind>, rules: &RuleSet, out: &mut Vec<RankCounts>) {
+        let wants = |kind: PlayKind| rules.allows(kind) && lead.is_none_or(|lead| lead == kind);
+        let held: Vec<usize> = (0..RANKS.len()).filter(|idx| self.at(*idx) > 0).collect();
+        let decks = rules.variant.decks() as u8;
+
+        for (kind, size) in [
+            (PlayKind::Single, 1),
+            (PlayKind::Pair, 2),
+            (PlayKind::Triple, 3),
+        ] {
+            if wants(kind) {
+                for &idx in held.iter().filter(|idx| self.at(**idx) >= size) {
+                    out.push(Self::single(idx, size));
+                }
+            }
+        }
+        for &core in &held {
+            let count = self.at(core);
+            if count >= 3 && (wants(PlayKind::TripleSingle) || wants(PlayKind::TriplePair)) {
+                let triple = Self::single(core, 3);
+                for &kicker in held.iter().filter(|idx| **idx != core) {
+                    if wants(PlayKind::TripleSingle) {
+                        out.push(triple.plus(kicker, 1));
+                    }
+                    if wants(PlayKind::TriplePair) && self.at(kicker) >= 2 {
+                        out.push(triple.plus(kicker, 2));
+                    }
+                }
+            }
+            if count >= 4 && core < BLACK_JOKER {
+                let quad = Self::single(core, 4);
+                let kickers: Vec<usize> = held.iter().copied().filter(|idx| *idx != core).collect();
+                for (pos, &first) in kickers.iter().enumerate() {
+                    if wants(PlayKind::FourTwoPair) && self.at(first) >= 4 {
+                        out.push(quad.plus(first, 4));
+                    }
+                    for &second in &kickers[pos + 1..] {
+                        if wants(PlayKind::FourTwoSingle) {
+                            out.push(quad.plus(first, 1).plus(second, 1));
+                        }
+                        if wants(PlayKind::FourTwoPair)
+                            && self.at(first) >= 2
+                            && self.at(second) >= 2
+                        {
+                            out.push(quad.plus(first, 2).plus(second, 2));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (kind, width, min_len) in [
+            (PlayKind::Straight, 1, rules.min_straight),
+            (PlayKind::DoubleStraight, 2, rules.min_double_straight),
+            (PlayKind::Airplane, 3, rules.min_airplane),
+        ] {
+            if wants(kind) {
+                self.chains(width, min_len, |body| out.push(body));
+            }
+        }
+        if wants(PlayKind::AirplaneSingles) || wants(PlayKind::AirplanePairs) {
+            self.chains(3, rules.min_airplane, |body| {
+                let len = body.iter().count();
+                let pool = self.remove(body).unwrap_or_default();
+                let pool = (0..CHAIN_RANKS)
+                    .filter(|idx| body.at(*idx) > 0)
+                    .fold(pool, |pool, idx| pool.minus(idx, pool.at(idx)));
+                if wants(PlayKind::AirplaneSingles) {
+                    pool.wings(len as u8, 0, body, &mut |wings| {
+                        if wings.at(BLACK_JOKER) == 0 || wings.at(RED_JOKER) == 0 {
+                            out.push(wings);
+                        }
+                    });
+                }
+                if wants(PlayKind::AirplanePairs) {
+                    let pairs: Vec<usize> =
+                        (0..RANKS.len()).filter(|idx| pool.at(*idx) >= 2).collect();
+                    pair_wings(&pairs, len, body, &mut |wings| out.push(wings));
+                }
+            });
+        }
+
+        for &idx in held.iter().filter(|idx| **idx < BLACK_JOKER) {
+            for size in 4..=self.at(idx).min(4 * decks) {
+                out.push(Self::single(idx, size));
+            }
+        }
+        if self.at(BLACK_JOKER) >= decks && self.at(RED_JOKER) >= decks {
+            out.push(Self::single(BLACK_JOKER, decks).plus(RED_JOKER, decks));
+        }
+    }
+
+    /// Every run of at least `min_len` chain ranks held `width` deep.
+    fn chains(self, width: u8, min_len: usize, mut emit: impl FnMut(RankCounts)) {
+        let min_len = min_len.max(2);
+        for start in 0..CHAIN_RANKS {
+            let mut end = start;
+            while end < CHAIN_RANKS && self.at(end) >= width {
+                end += 1;
+                if end - start >= min_len {
+                    emit(Self::run(start..end, width));
+                }
+            }
+        }
+    }
+
+    /// Adds every multiset of `size` cards from `self`, ranks from `from` on,
+    /// to `picked`.
+    fn wings(self, size: u8, from: usize, picked: RankCounts, emit: &mut impl FnMut(RankCounts)) {
+        if size == 0 {
+            emit(picked);
+            return;
+        }
+        for idx in from..RANKS.len() {
+            for count in 1..=self.at(idx).min(size) {
+                self.wings(size - count, idx + 1, picked.plus(idx, count), emit);
+            }
+        }
+    }
+}
+
+/// Adds a pair of each of `count` distinct ranks from `ranks` to `picked`.
+fn pair_wings(
+    ranks: &[usize],
+    count: usize,
+    picked: RankCounts,
+    emit: &mut impl FnMut(RankCounts),
+) {
+    if count == 0 {
+        emit(picked);
+        return;
+    }
+    for (pos, &idx) in ranks.iter().enumerate() {
+        pair_wings(&ranks[pos + 1..], count - 1, picked.plus(idx, 2), emit);
+    }
+}
+
+impl From<&[Card]> for RankCounts {
+    fn from(cards: &[Card]) -> Self {
+        Self::from_cards(cards)
+    }
+}
+
+impl From<RankCounts> for Vec<Card> {
+    fn from(counts: RankCounts) -> Self {
+        counts.to_cards()
+    }
+}
+
+impl FromIterator<Rank> for RankCounts {
+    fn from_iter<I: IntoIterator<Item = Rank>>(ranks: I) -> Self {
+        ranks
+            .into_iter()
+            .fold(Self::EMPTY, |counts, rank| counts.with(rank, 1))
+    }
+}
+
+impl fmt::Debug for RankCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movegen::kind_order;
+    use crate::{classify_all_with, legal_moves, shuffled_deck, sort_hand, Variant};
+    use std::collections::BTreeSet;
+
+    fn rules_under_test() -> Vec<RuleSet> {
+        vec![
+            RuleSet::default(),
+            RuleSet {
+                min_straight: 6,
+                min_airplane: 3,
+                four_with_two: false,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                variant: Variant::FourPlayer,
+                ..RuleSet::default()
+            },
+        ]
+    }
+
+    type Key = (RankCounts, usize, Rank, usize);
+
+    fn key(counts: RankCounts, play: &Play) -> Key {
+        (counts, kind_order(play.kind), play.main_rank, play.size)
+    }
+
+    fn moves_by_counts(hand: &[Card], last_play: Option<&Play>, rules: &RuleSet) -> BTreeSet<Key> {
+        legal_moves(hand, last_play, rules)
+            .iter()
+            .map(|candidate| key(RankCounts::from_cards(&candidate.cards), &candidate.play))
+            .collect()
+    }
+
+    #[test]
+    fn converts_to_and_from_cards() {
+        let deck = shuffled_deck(3);
+        let hand = &deck[..17];
+        let counts = RankCounts::from(hand);
+        assert_eq!(counts.len(), 17);
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(_, count)| usize::from(count))
+                .sum::<usize>(),
+            17
+        );
+        let cards: Vec<Card> = counts.into();
+        assert_eq!(RankCounts::from_cards(&cards), counts);
+        let mut taken = counts.take_from(hand).unwrap();
+        let mut expected = hand.to_vec();
+        sort_hand(&mut taken);
+        sort_hand(&mut expected);
+        assert_eq!(taken, expected);
+
+        let pair = RankCounts::single(rank_index(hand[0].rank), 1).with(hand[0].rank, 1);
+        assert_eq!(pair.count(hand[0].rank), 2);
+        assert_eq!(counts.contains(pair), counts.count(hand[0].rank) >= 2);
+        assert_eq!(counts.remove(pair).is_some(), counts.contains(pair));
+        assert!(RankCounts::EMPTY.take_from(hand).unwrap().is_empty());
+
+        let two_decks = RankCounts::from_cards(&Variant::FourPlayer.deck().cards());
+        assert_eq!(two_decks.len(), 108);
+        assert_eq!(two_decks.count(Rank::Three), 8);
+        assert_eq!(two_decks.count(Rank::RedJoker), 2);
+        assert_eq!(two_decks.remove(counts.merge(counts)).unwrap().len(), 74);
+    }
+
+    #[test]
+    fn classification_matches_card_classifier() {
+        for rules in rules_under_test() {
+            let deck = rules.variant.shuffled_deck(7);
+            for seed in 0..30 {
+                let hand = &deck[seed..seed + 17];
+                for candidate in legal_moves(hand, None, &rules) {
+                    let counts = RankCounts::from_cards(&candidate.cards);
+                    assert_eq!(
+                        counts.classify_all(&rules),
+                        classify_all_with(&candidate.cards, &rules, None),
+                        "{:?}",
+                        candidate.cards
+                    );
+                }
+                // Arbitrary slices too, which are mostly not plays.
+                for len in 1..=12 {
+                    let cards = &deck[seed..seed + len];
+                    let counts = RankCounts::from_cards(cards);
+                    assert_eq!(
+                        counts.classify_all(&rules),
+                        classify_all_with(cards, &rules, None)
+                    );
+                    assert_eq!(
+                        counts.classify(&rules),
+                        classify_all_with(cards, &rules, None).into_iter().next()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn move_generation_matches_card_generator() {
+        for rules in rules_under_test() {
+            for seed in 0..20 {
+                let deck = rules.variant.shuffled_deck(seed);
+                let hand = &deck[..17];
+                let counts = RankCounts::from_cards(hand);
+                let mut leads = vec![None];
+                let others = legal_moves(&deck[20..37], None, &rules);
+                leads.extend(others.into_iter().step_by(5).map(|other| Some(other.play)));
+                for lead in &leads {
+                    let moves = counts.legal_moves(lead.as_ref(), &rules);
+                    let unique: BTreeSet<_> = moves
+                        .iter()
+                        .map(|(taken, play)| key(*taken, play))
+                        .collect();
+                    assert_eq!(unique.len(), moves.len());
+                    assert_eq!(unique, moves_by_counts(hand, lead.as_ref(), &rules));
+                    for (taken, play) in &moves {
+                        let cards = taken.take_from(hand).unwrap();
+                        if let Some(lead) = lead {
+                            let first = taken.beats(lead, &rules).unwrap();
+                            assert!(can_beat_with(lead, &first, &rules));
+                        }
+                        assert!(classify_all_with(&cards, &rules, None).contains(play));
+                    }
+                }
+            }
+        }
+    }
+}
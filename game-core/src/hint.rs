@@ -0,0 +1,104 @@
+use crate::{legal_moves_wild, Card, Move, Play, Rank, RuleSet};
+use std::collections::BTreeMap;
+
+/// Legal plays ranked the way a "提示" button cycles through them: plays
+/// that keep the rest of the hand intact first, then the lowest main rank,
+/// with bombs and the rocket held back until last.
+pub fn hints(
+    hand: &[Card],
+    last_play: Option<&Play>,
+    rules: &RuleSet,
+    wild: Option<Rank>,
+) -> Vec<Move> {
+    let mut moves = legal_moves_wild(hand, last_play, rules, wild);
+    let held = rank_counts(hand);
+    // Stable, so moves that tie keep the generator's order.
+    moves.sort_by_cached_key(|candidate| {
+        (
+            candidate.play.kind.is_bomb(),
+            breakage(&held, &candidate.cards),
+            candidate.play.substitutions.len(),
+            candidate.play.main_rank,
+        )
+    });
+    moves
+}
+
+fn rank_counts(cards: &[Card]) -> BTreeMap<Rank, usize> {
+    let mut counts = BTreeMap::new();
+    for card in cards {
+        *counts.entry(card.rank).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// How badly a play splits the hand: one point per rank it leaves partly in
+/// hand, four when that rank was a bomb, and three for splitting the rocket.
+fn breakage(held: &BTreeMap<Rank, usize>, cards: &[Card]) -> usize {
+    let used = rank_counts(cards);
+    let mut cost: usize = used
+        .iter()
+        .filter(|(rank, count)| held[*rank] > **count)
+        .map(|(rank, _)| if held[rank] >= 4 { 4 } else { 1 })
+        .sum();
+    let jokers = [Rank::BlackJoker, Rank::RedJoker];
+    let holds_rocket = jokers.iter().all(|rank| held.contains_key(rank));
+    let uses_one_joker = jokers.iter().filter(|rank| used.contains_key(rank)).count() == 1;
+    if holds_rocket && uses_one_joker {
+        cost += 3;
+    }
+    cost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{classify_play, PlayKind, Suit};
+
+    fn card(rank: Rank, suit: Suit) -> Card {
+        Card { rank, suit }
+    }
+
+    fn hand() -> Vec<Card> {
+        vec![
+            card(Rank::Four, Suit::Clubs),
+            card(Rank::Six, Suit::Clubs),
+            card(Rank::Six, Suit::Hearts),
+            card(Rank::Nine, Suit::Clubs),
+            card(Rank::Nine, Suit::Diamonds),
+            card(Rank::Nine, Suit::Hearts),
+            card(Rank::Nine, Suit::Spades),
+            card(Rank::King, Suit::Spades),
+            card(Rank::BlackJoker, Suit::Joker),
+            card(Rank::RedJoker, Suit::Joker),
+        ]
+    }
+
+    fn ranks(candidate: &Move) -> Vec<Rank> {
+        candidate.cards.iter().map(|card| card.rank).collect()
+    }
+
+    #[test]
+    fn follows_with_whole_groups_before_splitting() {
+        let lead = classify_play(&[card(Rank::Five, Suit::Spades)]).unwrap();
+        let hints = hints(&hand(), Some(&lead), &RuleSet::default(), None);
+        let order: Vec<Vec<Rank>> = hints.iter().map(ranks).collect();
+        assert_eq!(order[0], vec![Rank::King]);
+        assert_eq!(order[1], vec![Rank::Six], "then split the pair");
+        assert_eq!(
+            order[2],
+            vec![Rank::BlackJoker],
+            "the rocket counts as a group"
+        );
+        assert_eq!(order[4], vec![Rank::Nine], "splitting the bomb comes last");
+        let bombs: Vec<PlayKind> = hints[5..].iter().map(|hint| hint.play.kind).collect();
+        assert_eq!(bombs, vec![PlayKind::Bomb, PlayKind::Rocket]);
+    }
+
+    #[test]
+    fn leading_suggests_the_smallest_loose_card() {
+        let hints = hints(&hand(), None, &RuleSet::default(), None);
+        assert_eq!(ranks(&hints[0]), vec![Rank::Four]);
+        assert_eq!(hints.last().unwrap().play.kind, PlayKind::Rocket);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{classify_plain_all, Card, Play, PlayKind, Rank, RuleSet, Substitution, SUITED_RANKS};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+
+/// Draws the wild rank for a deal from the ranks left in the deck. Jokers are
+/// never wild.
+pub fn draw_wild_rank(seed: u64, removed: &[Rank]) -> Rank {
+    let candidates: Vec<Rank> = SUITED_RANKS
+        .into_iter()
+        .filter(|rank| !removed.contains(rank))
+        .collect();
+    let mut rng = StdRng::seed_from_u64(seed ^ 0x1A12_5EED_0BAD_CAFE);
+    candidates[rng.gen_range(0..candidates.len())]
+}
+
+/// Classifies a play where cards of the `wild` rank may stand in for any
+/// non-joker rank. A reading that uses every card at face value wins; four
+/// wild cards alone are a `LaiziBomb`. Otherwise the substituted readings are
+/// searched, preferring non-bombs, then the highest main rank, then the
+/// fewest substitutions.
+pub fn classify_play_wild(cards: &[Card], wild: Option<Rank>) -> Option<Play> {
+    classify(cards, &RuleSet::default(), wild)
+}
+
+pub(crate) fn classify(cards: &[Card], rules: &RuleSet, wild: Option<Rank>) -> Option<Play> {
+    classify_all(cards, rules, wild).into_iter().next()
+}
+
+/// Every reading of `cards`, best first: the `LaiziBomb`, then face-value
+/// readings, then substituted readings in the order `classify` prefers them.
+/// Substituted readings that only differ in which ranks the wild cards took
+/// are listed once.
+pub(crate) fn classify_all(cards: &[Card], rules: &RuleSet, wild: Option<Rank>) -> Vec<Play> {
+    let Some(wild) = wild.filter(|wild| cards.iter().any(|card| card.rank == *wild)) else {
+        return classify_plain_all(cards, rules);
+    };
+    let mut plays = Vec::new();
+    if cards.iter().all(|card| card.rank == wild)
+        && (4..=4 * rules.variant.decks()).contains(&cards.len())
+        && rules.allows(PlayKind::LaiziBomb)
+    {
+        plays.push(Play::new(PlayKind::LaiziBomb, wild, cards.len()));
+    }
+    plays.extend(classify_plain_all(cards, rules));
+    let mut substituted = substituted_plays(cards, rules, wild);
+    substituted.sort_by_key(|play| {
+        Reverse((
+            !play.kind.is_bomb(),
+            play.main_rank,
+            Reverse(play.substitutions.len()),
+        ))
+    });
+    for play in substituted {
+        let seen = plays.iter().any(|other| {
+            other.kind == play.kind && other.main_rank == play.main_rank && other.size == play.size
+        });
+        if !seen {
+            plays.push(play);
+        }
+    }
+    plays
+}
+
+/// Every reading of `cards` that substitutes at least one wild card, in a
+/// deterministic order.
+pub(crate) fn substituted_plays(cards: &[Card], rules: &RuleSet, wild: Rank) -> Vec<Play> {
+    let wild_cards: Vec<Card> = cards.iter().copied().filter(|c| c.rank == wild).collect();
+    let natural: Vec<Card> = cards.iter().copied().filter(|c| c.rank != wild).collect();
+    let mut targets = Vec::with_capacity(wild_cards.len());
+    let mut plays = Vec::new();
+    let search = Search {
+        natural: &natural,
+        wild_cards: &wild_cards,
+        rules,
+        wild,
+    };
+    search.run(0, &mut targets, &mut plays);
+    plays
+}
+
+struct Search<'a> {
+    natural: &'a [Card],
+    wild_cards: &'a [Card],
+    rules: &'a RuleSet,
+    wild: Rank,
+}
+
+impl Search<'_> {
+    fn run(&self, min_target: usize, targets: &mut Vec<usize>, plays: &mut Vec<Play>) {
+        let Search {
+            natural,
+            wild_cards,
+            rules,
+            wild,
+        } = *self;
+        if targets.len() == wild_cards.len() {
+            let substitutions: Vec<Substitution> = wild_cards
+                .iter()
+                .zip(targets.iter())
+                .filter(|(_, target)| SUITED_RANKS[**target] != wild)
+                .map(|(card, target)| Substitution {
+                    card: *card,
+                    rank: SUITED_RANKS[*target],
+                })
+                .collect();
+            if substitutions.is_empty() {
+                return;
+            }
+            let mut cards = natural.to_vec();
+            cards.extend(
+                wild_cards
+                    .iter()
+                    .zip(targets.iter())
+                    .map(|(card, target)| Card {
+                        rank: SUITED_RANKS[*target],
+                        suit: card.suit,
+                    }),
+            );
+            for mut play in classify_plain_all(&cards, rules) {
+                if play.kind == PlayKind::Bomb {
+                    play.kind = PlayKind::SoftBomb;
+                }
+                if rules.allows(play.kind) {
+                    play.substitutions = substitutions.clone();
+                    plays.push(play);
+                }
+            }
+            return;
+        }
+        for target in min_target..SUITED_RANKS.len() {
+            targets.push(target);
+            self.run(target, targets, plays);
+            targets.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{can_beat, classify_play, Suit};
+
+    fn card(rank: Rank, suit: Suit) -> Card {
+        Card { rank, suit }
+    }
+
+    #[test]
+    fn wild_completes_straight() {
+        let cards = [
+            card(Rank::Five, Suit::Clubs),
+            card(Rank::Six, Suit::Clubs),
+            card(Rank::Seven, Suit::Clubs),
+            card(Rank::Eight, Suit::Clubs),
+            card(Rank::Two, Suit::Hearts),
+        ];
+        let play = classify_play_wild(&cards, Some(Rank::Two)).unwrap();
+        assert_eq!(play.kind, PlayKind::Straight);
+        assert_eq!(play.main_rank, Rank::Nine);
+        assert_eq!(
+            play.substitutions,
+            vec![Substitution {
+                card: card(Rank::Two, Suit::Hearts),
+                rank: Rank::Nine,
+            }]
+        );
+    }
+
+    #[test]
+    fn wild_forms_pairs_and_triples() {
+        let pair = [
+            card(Rank::King, Suit::Clubs),
+            card(Rank::Four, Suit::Spades),
+        ];
+        let play = classify_play_wild(&pair, Some(Rank::Four)).unwrap();
+        assert_eq!((play.kind, play.main_rank), (PlayKind::Pair, Rank::King));
+
+        let triple = [
+            card(Rank::Nine, Suit::Clubs),
+            card(Rank::Four, Suit::Spades),
+            card(Rank::Four, Suit::Hearts),
+        ];
+        let play = classify_play_wild(&triple, Some(Rank::Four)).unwrap();
+        assert_eq!((play.kind, play.main_rank), (PlayKind::Triple, Rank::Nine));
+        assert_eq!(play.substitutions.len(), 2);
+    }
+
+    #[test]
+    fn face_value_reading_is_kept_without_substitution() {
+        let pair = [
+            card(Rank::Four, Suit::Clubs),
+            card(Rank::Four, Suit::Spades),
+        ];
+        let play = classify_play_wild(&pair, Some(Rank::Four)).unwrap();
+        assert_eq!((play.kind, play.main_rank), (PlayKind::Pair, Rank::Four));
+        assert!(play.substitutions.is_empty());
+    }
+
+    #[test]
+    fn jokers_are_never_substituted() {
+        let cards = [
+            card(Rank::BlackJoker, Suit::Joker),
+            card(Rank::Six, Suit::Hearts),
+        ];
+        assert!(classify_play_wild(&cards, Some(Rank::Six)).is_none());
+    }
+
+    #[test]
+    fn wild_kicker_keeps_face_value() {
+        let cards = [
+            card(Rank::Ace, Suit::Clubs),
+            card(Rank::Ace, Suit::Diamonds),
+            card(Rank::Seven, Suit::Hearts),
+            card(Rank::Seven, Suit::Spades),
+        ];
+        let play = classify_play_wild(&cards, Some(Rank::Seven)).unwrap();
+        assert_eq!(
+            (play.kind, play.main_rank),
+            (PlayKind::TripleSingle, Rank::Ace)
+        );
+        assert_eq!(play.substitutions.len(), 1);
+    }
+
+    #[test]
+    fn soft_bomb_ranks_below_hard_bomb() {
+        let soft = substituted_plays(
+            &[
+                card(Rank::Ace, Suit::Clubs),
+                card(Rank::Ace, Suit::Diamonds),
+                card(Rank::Ace, Suit::Hearts),
+                card(Rank::Seven, Suit::Spades),
+            ],
+            &RuleSet::default(),
+            Rank::Seven,
+        )
+        .into_iter()
+        .find(|play| play.kind == PlayKind::SoftBomb)
+        .unwrap();
+        assert_eq!(soft.main_rank, Rank::Ace);
+
+        let hard = classify_play(&[
+            card(Rank::Three, Suit::Clubs),
+            card(Rank::Three, Suit::Diamonds),
+            card(Rank::Three, Suit::Hearts),
+            card(Rank::Three, Suit::Spades),
+        ])
+        .unwrap();
+        assert!(can_beat(&soft, &hard));
+        assert!(!can_beat(&hard, &soft));
+    }
+
+    #[test]
+    fn four_wild_cards_are_a_laizi_bomb() {
+        let cards = [
+            card(Rank::Seven, Suit::Clubs),
+            card(Rank::Seven, Suit::Diamonds),
+            card(Rank::Seven, Suit::Hearts),
+            card(Rank::Seven, Suit::Spades),
+        ];
+        let laizi = classify_play_wild(&cards, Some(Rank::Seven)).unwrap();
+        assert_eq!(laizi.kind, PlayKind::LaiziBomb);
+        let hard = classify_play(&[
+            card(Rank::Two, Suit::Clubs),
+            card(Rank::Two, Suit::Diamonds),
+            card(Rank::Two, Suit::Hearts),
+            card(Rank::Two, Suit::Spades),
+        ])
+        .unwrap();
+        let rocket = classify_play(&[
+            card(Rank::BlackJoker, Suit::Joker),
+            card(Rank::RedJoker, Suit::Joker),
+        ])
+        .unwrap();
+        assert!(can_beat(&hard, &laizi));
+        assert!(can_beat(&laizi, &rocket));
+    }
+
+    #[test]
+    fn wild_rank_is_never_a_joker() {
+        for seed in 0..64 {
+            assert!(SUITED_RANKS.contains(&draw_wild_rank(seed, &[])));
+            let removed = [Rank::Three, Rank::Four];
+            assert!(!removed.contains(&draw_wild_rank(seed, &removed)));
+        }
+    }
+}
@@ -1,11 +1,44 @@
 use rand::rngs::StdRng;
-use rand::seq::SliceRandom;
 use rand::RngCore;
 use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
+pub mod action;
+pub mod bidding;
+pub mod decompose;
+pub mod doubling;
+pub mod hand;
+pub mod hint;
+pub mod laizi;
+pub mod movegen;
+pub mod notation;
+pub mod result;
+pub mod rules;
+pub mod scoring;
+pub mod snapshot;
+pub mod solver;
+pub mod variant;
+pub mod view;
+
+pub use action::{Action, GameEvent, Opening, ReplayError, Reset};
+pub use bidding::{BidOutcome, Bidding, BiddingMode, PointBidding, RobBidding, MAX_BID};
+pub use decompose::{decompose, Decomposition, HandAnalysis};
+pub use doubling::{Doubling, DOUBLE_FACTORS};
+pub use hand::RankCounts;
+pub use hint::hints;
+pub use laizi::classify_play_wild;
+pub use movegen::{legal_moves, legal_moves_wild, Move};
+pub use notation::NotationError;
+pub use result::{GameResult, PlayerResult, Side};
+pub use rules::{KindSet, RuleError, RulePreset, RuleSet, MAX_BASE_SCORE};
+pub use scoring::{ScoreInput, ScoringRules, Settlement, Spring};
+pub use snapshot::{migrate, SnapshotError, SNAPSHOT_VERSION};
+pub use solver::{solve, solve_within, Solution, SolveError};
+pub use variant::{Deal, DeckSpec, Variant};
+pub use view::PlayerView;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Suit {
     Clubs,
@@ -36,7 +69,7 @@ impl Suit {
         }
     }
 
-    fn to_char(&self) -> char {
+    fn to_char(self) -> char {
         match self {
             Suit::Clubs => 'C',
             Suit::Diamonds => 'D',
@@ -88,7 +121,7 @@ impl Rank {
         }
     }
 
-    fn to_str(&self) -> &'static str {
+    fn to_str(self) -> &'static str {
         match self {
             Rank::Three => "3",
             Rank::Four => "4",
@@ -117,7 +150,9 @@ impl Rank {
     }
 }
 
+/// Serialized as its code, e.g. `"H10"` or `"BJ"`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub struct Card {
     pub rank: Rank,
     pub suit: Suit,
@@ -131,24 +166,24 @@ impl Card {
         format!("{}{}", self.suit.to_char(), self.rank.to_str())
     }
 
+    /// Reads a suit-qualified code in any form `notation::parse_card` takes:
+    /// `"SA"`, `"AS"`, `"H10"`, `"HT"`, `"0H"`, `"BJ"`.
     pub fn from_code(code: &str) -> Option<Self> {
-        if code == "BJ" {
-            return Some(Card {
-                rank: Rank::BlackJoker,
-                suit: Suit::Joker,
-            });
-        }
-        if code == "RJ" {
-            return Some(Card {
-                rank: Rank::RedJoker,
-                suit: Suit::Joker,
-            });
-        }
-        let mut chars = code.chars();
-        let suit = Suit::from_char(chars.next()?)?;
-        let rank_str: String = chars.collect();
-        let rank = Rank::from_str(rank_str.as_str())?;
-        Some(Card { rank, suit })
+        notation::parse_card(code)
+    }
+}
+
+impl From<Card> for String {
+    fn from(card: Card) -> Self {
+        card.code()
+    }
+}
+
+impl TryFrom<String> for Card {
+    type Error = String;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Card::from_code(&code).ok_or_else(|| format!("unknown card code {code:?}"))
     }
 }
 
@@ -158,69 +193,41 @@ impl fmt::Display for Card {
     }
 }
 
+/// Every rank except the jokers, lowest first.
+pub const SUITED_RANKS: [Rank; 13] = [
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Jack,
+    Rank::Queen,
+    Rank::King,
+    Rank::Ace,
+    Rank::Two,
+];
+
 pub fn standard_deck() -> Vec<Card> {
-    let mut deck = Vec::with_capacity(54);
-    let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
-    let ranks = [
-        Rank::Three,
-        Rank::Four,
-        Rank::Five,
-        Rank::Six,
-        Rank::Seven,
-        Rank::Eight,
-        Rank::Nine,
-        Rank::Ten,
-        Rank::Jack,
-        Rank::Queen,
-        Rank::King,
-        Rank::Ace,
-        Rank::Two,
-    ];
-    for suit in suits.iter() {
-        for rank in ranks.iter() {
-            deck.push(Card {
-                rank: *rank,
-                suit: *suit,
-            });
-        }
-    }
-    deck.push(Card {
-        rank: Rank::BlackJoker,
-        suit: Suit::Joker,
-    });
-    deck.push(Card {
-        rank: Rank::RedJoker,
-        suit: Suit::Joker,
-    });
-    deck
+    Variant::Classic.deck().cards()
 }
 
 pub fn shuffled_deck(seed: u64) -> Vec<Card> {
-    let mut deck = standard_deck();
-    let mut rng = StdRng::seed_from_u64(seed);
-    deck.shuffle(&mut rng);
-    deck
+    Variant::Classic.shuffled_deck(seed)
 }
 
 pub fn deal(seed: u64) -> (Vec<Vec<Card>>, Vec<Card>) {
-    let mut deck = shuffled_deck(seed);
-    let mut hands = vec![
-        Vec::with_capacity(17),
-        Vec::with_capacity(17),
-        Vec::with_capacity(17),
-    ];
-    for i in 0..51 {
-        hands[i % 3].push(deck[i]);
-    }
-    let bottom = deck.split_off(51);
+    let Deal { hands, bottom, .. } = Variant::Classic.deal(seed);
     (hands, bottom)
 }
 
-pub fn sort_hand(hand: &mut Vec<Card>) {
+pub fn sort_hand(hand: &mut [Card]) {
     hand.sort_by_key(|card| (card.rank, card.suit.order()))
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PlayKind {
     Single,
     Pair,
@@ -230,17 +237,82 @@ pub enum PlayKind {
     Straight,
     DoubleStraight,
     Airplane,
+    AirplaneSingles,
+    AirplanePairs,
     Bomb,
+    /// Bomb completed with wild cards (软炸); ranks below every hard bomb.
+    SoftBomb,
+    /// Four wild cards (纯癞子炸); ranks above every hard bomb.
+    LaiziBomb,
     Rocket,
     FourTwoSingle,
     FourTwoPair,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl PlayKind {
+    pub const ALL: [PlayKind; 16] = [
+        PlayKind::Single,
+        PlayKind::Pair,
+        PlayKind::Triple,
+        PlayKind::TripleSingle,
+        PlayKind::TriplePair,
+        PlayKind::Straight,
+        PlayKind::DoubleStraight,
+        PlayKind::Airplane,
+        PlayKind::AirplaneSingles,
+        PlayKind::AirplanePairs,
+        PlayKind::Bomb,
+        PlayKind::SoftBomb,
+        PlayKind::LaiziBomb,
+        PlayKind::Rocket,
+        PlayKind::FourTwoSingle,
+        PlayKind::FourTwoPair,
+    ];
+
+    pub fn is_bomb(&self) -> bool {
+        self.bomb_tier().is_some()
+    }
+
+    pub fn is_rocket(&self) -> bool {
+        *self == PlayKind::Rocket
+    }
+
+    fn bomb_tier(&self) -> Option<u8> {
+        match self {
+            PlayKind::SoftBomb => Some(1),
+            PlayKind::Bomb => Some(2),
+            PlayKind::LaiziBomb => Some(3),
+            PlayKind::Rocket => Some(4),
+            _ => None,
+        }
+    }
+}
+
+/// A wild card standing in for another rank.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Substitution {
+    pub card: Card,
+    pub rank: Rank,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Play {
     pub kind: PlayKind,
     pub main_rank: Rank,
     pub size: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub substitutions: Vec<Substitution>,
+}
+
+impl Play {
+    pub fn new(kind: PlayKind, main_rank: Rank, size: usize) -> Self {
+        Play {
+            kind,
+            main_rank,
+            size,
+            substitutions: Vec::new(),
+        }
+    }
 }
 
 fn counts_by_rank(cards: &[Card]) -> BTreeMap<Rank, usize> {
@@ -265,9 +337,93 @@ fn is_consecutive(ranks: &[Rank]) -> bool {
     true
 }
 
+/// Finds every chain of `chain_len` consecutive triples whose leftover cards
+/// form valid wings, highest chain first. Wings never reuse a chain rank, and
+/// the two jokers cannot be split into single wings.
+fn airplane_chains(
+    counts: &BTreeMap<Rank, usize>,
+    chain_len: usize,
+    wing_size: usize,
+) -> Vec<Rank> {
+    if chain_len < 2 {
+        return Vec::new();
+    }
+    let triple_ranks: Vec<Rank> = counts
+        .iter()
+        .filter(|(rank, count)| **count >= 3 && rank.is_straightable())
+        .map(|(rank, _)| *rank)
+        .collect();
+    if triple_ranks.len() < chain_len {
+        return Vec::new();
+    }
+    let mut tops = Vec::new();
+    for chain in triple_ranks.windows(chain_len).rev() {
+        if !is_consecutive(chain) {
+            continue;
+        }
+        if chain.iter().any(|rank| counts[rank] != 3) {
+            continue;
+        }
+        let wings: Vec<(Rank, usize)> = counts
+            .iter()
+            .filter(|(rank, _)| !chain.contains(rank))
+            .map(|(rank, count)| (*rank, *count))
+            .collect();
+        let valid = if wing_size == 1 {
+            let rocket = wings.iter().any(|(rank, _)| *rank == Rank::BlackJoker)
+                && wings.iter().any(|(rank, _)| *rank == Rank::RedJoker);
+            !rocket
+        } else {
+            wings.len() == chain_len && wings.iter().all(|(_, count)| *count == 2)
+        };
+        if valid {
+            tops.extend(chain.last().copied());
+        }
+    }
+    tops
+}
+
+/// Classifies `cards` under the classic rules.
 pub fn classify_play(cards: &[Card]) -> Option<Play> {
+    classify_plain(cards, &RuleSet::default())
+}
+
+/// Classifies `cards` under a table's rules, with `wild` as the 癞子 rank.
+pub fn classify_play_with(cards: &[Card], rules: &RuleSet, wild: Option<Rank>) -> Option<Play> {
+    laizi::classify(cards, rules, wild)
+}
+
+/// Every reading of `cards` under the classic rules, in the order
+/// `classify_play` prefers them.
+pub fn classify_all(cards: &[Card]) -> Vec<Play> {
+    classify_all_with(cards, &RuleSet::default(), None)
+}
+
+/// Every reading of `cards` under a table's rules. The first entry is the one
+/// `classify_play_with` returns.
+pub fn classify_all_with(cards: &[Card], rules: &RuleSet, wild: Option<Rank>) -> Vec<Play> {
+    laizi::classify_all(cards, rules, wild)
+}
+
+/// Face-value classification, dropping kinds the rules do not allow.
+pub(crate) fn classify_plain(cards: &[Card], rules: &RuleSet) -> Option<Play> {
+    classify_plain_all(cards, rules).into_iter().next()
+}
+
+pub(crate) fn classify_plain_all(cards: &[Card], rules: &RuleSet) -> Vec<Play> {
+    let mut plays = classify_face(cards, rules);
+    plays.retain(|play| rules.allows(play.kind));
+    plays
+}
+
+/// All face-value readings, most natural first. Two decks allow bombs of up to
+/// eight cards and need all four jokers for a rocket; chain lengths come from
+/// the rules.
+fn classify_face(cards: &[Card], rules: &RuleSet) -> Vec<Play> {
+    let decks = rules.variant.decks();
+    let mut plays = Vec::new();
     if cards.is_empty() {
-        return None;
+        return plays;
     }
     let mut ranks: Vec<Rank> = cards.iter().map(|card| card.rank).collect();
     ranks.sort();
@@ -275,166 +431,195 @@ pub fn classify_play(cards: &[Card]) -> Option<Play> {
     let unique = counts.len();
     let len = cards.len();
 
-    if len == 2 && ranks.contains(&Rank::BlackJoker) && ranks.contains(&Rank::RedJoker) {
-        return Some(Play {
-            kind: PlayKind::Rocket,
-            main_rank: Rank::RedJoker,
-            size: 2,
-        });
+    if len == 2 * decks
+        && counts.get(&Rank::BlackJoker) == Some(&decks)
+        && counts.get(&Rank::RedJoker) == Some(&decks)
+    {
+        plays.push(Play::new(PlayKind::Rocket, Rank::RedJoker, len));
     }
 
-    if len == 4 && unique == 1 {
-        return Some(Play {
-            kind: PlayKind::Bomb,
-            main_rank: ranks[0],
-            size: 4,
-        });
+    if len >= 4 && len <= 4 * decks && unique == 1 && !ranks[0].is_joker() {
+        plays.push(Play::new(PlayKind::Bomb, ranks[0], len));
     }
 
     if len == 1 {
-        return Some(Play {
-            kind: PlayKind::Single,
-            main_rank: ranks[0],
-            size: 1,
-        });
+        plays.push(Play::new(PlayKind::Single, ranks[0], 1));
     }
 
     if len == 2 && unique == 1 {
-        return Some(Play {
-            kind: PlayKind::Pair,
-            main_rank: ranks[0],
-            size: 2,
-        });
+        plays.push(Play::new(PlayKind::Pair, ranks[0], 2));
     }
 
     if len == 3 && unique == 1 {
-        return Some(Play {
-            kind: PlayKind::Triple,
-            main_rank: ranks[0],
-            size: 3,
-        });
+        plays.push(Play::new(PlayKind::Triple, ranks[0], 3));
     }
 
     if len == 4 && unique == 2 {
         if let Some((rank, _)) = counts.iter().find(|(_, count)| **count == 3) {
-            return Some(Play {
-                kind: PlayKind::TripleSingle,
-                main_rank: *rank,
-                size: 4,
-            });
+            plays.push(Play::new(PlayKind::TripleSingle, *rank, 4));
         }
     }
 
     if len == 5 && unique == 2 {
         if let Some((rank, _)) = counts.iter().find(|(_, count)| **count == 3) {
-            return Some(Play {
-                kind: PlayKind::TriplePair,
-                main_rank: *rank,
-                size: 5,
-            });
+            plays.push(Play::new(PlayKind::TriplePair, *rank, 5));
         }
     }
 
     if len == 6 && unique == 3 {
         if let Some((rank, _)) = counts.iter().find(|(_, count)| **count == 4) {
-            return Some(Play {
-                kind: PlayKind::FourTwoSingle,
-                main_rank: *rank,
-                size: 6,
-            });
+            plays.push(Play::new(PlayKind::FourTwoSingle, *rank, 6));
         }
     }
 
-    if len == 8 && unique == 3 {
-        if let Some((rank, _)) = counts.iter().find(|(_, count)| **count == 4) {
-            let pair_count = counts.values().filter(|count| **count == 2).count();
-            if pair_count == 2 {
-                return Some(Play {
-                    kind: PlayKind::FourTwoPair,
-                    main_rank: *rank,
-                    size: 8,
-                });
+    // Four of a kind plus two pairs; a second quad counts as both pairs.
+    if len == 8 {
+        let pairs: usize = counts.values().map(|count| count / 2).sum();
+        let quads = counts
+            .iter()
+            .rev()
+            .filter(|(rank, count)| **count == 4 && !rank.is_joker());
+        for (rank, _) in quads {
+            let kickers_paired = counts
+                .iter()
+                .all(|(other, count)| other == rank || count % 2 == 0);
+            if kickers_paired && pairs == 4 {
+                plays.push(Play::new(PlayKind::FourTwoPair, *rank, 8));
             }
         }
     }
 
-    if counts.values().all(|count| *count == 1) && len >= 5 {
-        if ranks.iter().all(|rank| rank.is_straightable()) && is_consecutive(&ranks) {
-            return Some(Play {
-                kind: PlayKind::Straight,
-                main_rank: *ranks.last().unwrap(),
-                size: len,
-            });
-        }
+    if counts.values().all(|count| *count == 1)
+        && len >= rules.min_straight.max(2)
+        && ranks.iter().all(|rank| rank.is_straightable())
+        && is_consecutive(&ranks)
+    {
+        plays.push(Play::new(PlayKind::Straight, *ranks.last().unwrap(), len));
     }
 
-    if counts.values().all(|count| *count == 2) && len >= 6 && len % 2 == 0 {
+    if counts.values().all(|count| *count == 2)
+        && len >= 2 * rules.min_double_straight.max(2)
+        && len.is_multiple_of(2)
+    {
         let mut pair_ranks: Vec<Rank> = counts.keys().copied().collect();
         pair_ranks.sort();
         if pair_ranks.iter().all(|rank| rank.is_straightable()) && is_consecutive(&pair_ranks) {
-            return Some(Play {
-                kind: PlayKind::DoubleStraight,
-                main_rank: *pair_ranks.last().unwrap(),
-                size: pair_ranks.len(),
-            });
+            plays.push(Play::new(
+                PlayKind::DoubleStraight,
+                *pair_ranks.last().unwrap(),
+                pair_ranks.len(),
+            ));
         }
     }
 
-    if counts.values().all(|count| *count == 3) && len >= 6 && len % 3 == 0 {
+    let min_airplane = rules.min_airplane.max(2);
+    if counts.values().all(|count| *count == 3) && len >= 3 * min_airplane && len.is_multiple_of(3)
+    {
         let mut triple_ranks: Vec<Rank> = counts.keys().copied().collect();
         triple_ranks.sort();
         if triple_ranks.iter().all(|rank| rank.is_straightable()) && is_consecutive(&triple_ranks) {
-            return Some(Play {
-                kind: PlayKind::Airplane,
-                main_rank: *triple_ranks.last().unwrap(),
-                size: triple_ranks.len(),
-            });
+            plays.push(Play::new(
+                PlayKind::Airplane,
+                *triple_ranks.last().unwrap(),
+                triple_ranks.len(),
+            ));
+        }
+    }
+
+    if len >= 5 * min_airplane && len.is_multiple_of(5) {
+        for main_rank in airplane_chains(&counts, len / 5, 2) {
+            plays.push(Play::new(PlayKind::AirplanePairs, main_rank, len / 5));
         }
     }
 
-    None
+    if len >= 4 * min_airplane && len.is_multiple_of(4) {
+        for main_rank in airplane_chains(&counts, len / 4, 1) {
+            plays.push(Play::new(PlayKind::AirplaneSingles, main_rank, len / 4));
+        }
+    }
+
+    plays
+}
+
+/// Bombs order by rocket first, then card count, then hardness, then rank.
+fn bomb_strength(play: &Play) -> Option<(bool, usize, u8, Rank)> {
+    let tier = play.kind.bomb_tier()?;
+    Some((play.kind.is_rocket(), play.size, tier, play.main_rank))
+}
+
+/// `can_beat` restricted to kinds the rules allow.
+pub fn can_beat_with(prev: &Play, next: &Play, rules: &RuleSet) -> bool {
+    rules.allows(next.kind) && can_beat(prev, next)
 }
 
 pub fn can_beat(prev: &Play, next: &Play) -> bool {
-    if prev.kind == PlayKind::Rocket {
-        return false;
-    }
-    if next.kind == PlayKind::Rocket {
-        return true;
-    }
-    if next.kind == PlayKind::Bomb && prev.kind != PlayKind::Bomb {
-        return true;
-    }
-    if prev.kind == PlayKind::Bomb && next.kind != PlayKind::Bomb {
-        return false;
+    match (bomb_strength(prev), bomb_strength(next)) {
+        (Some(prev_strength), Some(next_strength)) => return next_strength > prev_strength,
+        (None, Some(_)) => return true,
+        (Some(_), None) => return false,
+        (None, None) => {}
     }
     if prev.kind != next.kind {
         return false;
     }
     match prev.kind {
-        PlayKind::Straight | PlayKind::DoubleStraight | PlayKind::Airplane => {
-            prev.size == next.size && next.main_rank > prev.main_rank
-        }
+        PlayKind::Straight
+        | PlayKind::DoubleStraight
+        | PlayKind::Airplane
+        | PlayKind::AirplaneSingles
+        | PlayKind::AirplanePairs => prev.size == next.size && next.main_rank > prev.main_rank,
         _ => next.main_rank > prev.main_rank,
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlayerState {
     pub id: u64,
     pub hand: Vec<Card>,
     pub out: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamePhase {
+    Bidding,
+    /// 加倍 round between bidding and the first play, when the rules enable it.
+    Doubling,
+    Playing,
+    Finished,
+}
+
+/// Serializes field by field; use `GameState::to_snapshot` for the
+/// versioned layout meant to be stored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GameState {
-    pub players: [PlayerState; 3],
-    pub landlord: usize,
+    pub players: Vec<PlayerState>,
+    pub rules: RuleSet,
+    pub phase: GamePhase,
+    pub bidding: Bidding,
+    pub doubling: Doubling,
+    pub landlord: Option<usize>,
+    pub bid: u8,
+    pub multiplier: u32,
+    pub bottom: Vec<Card>,
+    /// Cards dealt to nobody; stays face down for the whole game.
+    pub leftover: Vec<Card>,
+    pub wild_rank: Option<Rank>,
     pub turn: usize,
     pub last_play: Option<Play>,
     pub last_player: Option<usize>,
     pub pass_count: u8,
+    pub bombs_played: u32,
+    /// 明牌 factor per seat, for seats that showed their hand.
+    pub shown: Vec<Option<u32>>,
+    pub plays: Vec<u32>,
+    pub winner: Option<usize>,
     pub deck_seed: u64,
+    /// `None` for games loaded from snapshots older than the event log,
+    /// which can't be replayed.
+    pub opening: Option<Opening>,
+    /// Every accepted action in order; `GameState::replay` rebuilds the game
+    /// from it.
+    pub events: Vec<GameEvent>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -445,49 +630,116 @@ pub enum GameError {
     MustBeatPrevious,
     GameOver,
     CannotPass,
+    InvalidBid,
+    WrongPhase,
+    WrongBiddingMode,
+    InvalidDouble,
+    CannotShowHand,
+    NothingToUndo,
+    /// The event log doesn't lead back to the state, as in games loaded from
+    /// snapshots written before there was one.
+    IncompleteLog,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// 明牌 factor for showing a hand before the bidding ends.
+pub const SHOW_HAND_AT_DEAL: u32 = 4;
+/// 明牌 factor for the landlord showing its hand at the start of play.
+pub const SHOW_HAND_AT_PLAY: u32 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlayOutcome {
     pub play: Play,
     pub next_turn: usize,
     pub winner: Option<usize>,
 }
 
+fn next_deal_seed(seed: u64) -> u64 {
+    seed.wrapping_mul(6_364_136_223_846_793_005)
+        .wrapping_add(1_442_695_040_888_963_407)
+}
+
 impl GameState {
-    pub fn new(player_ids: [u64; 3], seed: u64) -> Self {
-        let (mut hands, bottom) = deal(seed);
+    pub fn new(player_ids: &[u64], seed: u64) -> Self {
+        Self::with_rules(player_ids, seed, RuleSet::default())
+    }
+
+    pub fn with_rules(player_ids: &[u64], seed: u64, rules: RuleSet) -> Self {
         let mut rng = StdRng::seed_from_u64(seed ^ 0x9E37_79B9_7F4A_7C15);
-        let landlord = (rng.next_u64() % 3) as usize;
-        hands[landlord].extend(bottom);
-        for hand in hands.iter_mut() {
-            sort_hand(hand);
-        }
-        GameState {
-            players: [
-                PlayerState {
-                    id: player_ids[0],
-                    hand: hands[0].clone(),
-                    out: false,
-                },
-                PlayerState {
-                    id: player_ids[1],
-                    hand: hands[1].clone(),
+        let first_bidder = (rng.next_u64() % player_ids.len() as u64) as usize;
+        Self::with_first_bidder(player_ids, seed, rules, first_bidder)
+    }
+
+    /// Panics if `player_ids` does not match the variant's player count.
+    pub fn with_first_bidder(
+        player_ids: &[u64],
+        seed: u64,
+        rules: RuleSet,
+        first_bidder: usize,
+    ) -> Self {
+        assert_eq!(
+            player_ids.len(),
+            rules.variant.player_count(),
+            "player count must match the variant"
+        );
+        let mut state = GameState {
+            players: player_ids
+                .iter()
+                .map(|id| PlayerState {
+                    id: *id,
+                    hand: Vec::new(),
                     out: false,
-                },
-                PlayerState {
-                    id: player_ids[2],
-                    hand: hands[2].clone(),
-                    out: false,
-                },
-            ],
-            landlord,
-            turn: landlord,
+                })
+                .collect(),
+            rules,
+            phase: GamePhase::Bidding,
+            bidding: Bidding::new(rules.bidding, first_bidder, player_ids.len()),
+            doubling: Doubling::default(),
+            landlord: None,
+            bid: 0,
+            multiplier: 1,
+            bottom: Vec::new(),
+            leftover: Vec::new(),
+            wild_rank: None,
+            turn: first_bidder,
             last_play: None,
             last_player: None,
             pass_count: 0,
+            bombs_played: 0,
+            shown: vec![None; player_ids.len()],
+            plays: vec![0; player_ids.len()],
+            winner: None,
             deck_seed: seed,
+            opening: Some(Opening { seed, first_bidder }),
+            events: Vec::new(),
+        };
+        state.deal_hands(seed, first_bidder);
+        state
+    }
+
+    fn deal_hands(&mut self, seed: u64, first_bidder: usize) {
+        let Deal {
+            hands,
+            bottom,
+            leftover,
+        } = self.rules.variant.deal(seed);
+        for (player, mut hand) in self.players.iter_mut().zip(hands) {
+            sort_hand(&mut hand);
+            player.hand = hand;
         }
+        self.bottom = bottom;
+        self.leftover = leftover;
+        let removed = self.rules.variant.deck().removed_ranks;
+        self.wild_rank = self
+            .rules
+            .laizi
+            .then(|| laizi::draw_wild_rank(seed, &removed));
+        self.bidding = Bidding::new(self.rules.bidding, first_bidder, self.players.len());
+        self.turn = first_bidder;
+        self.deck_seed = seed;
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
     }
 
     pub fn player_index(&self, player_id: u64) -> Option<usize> {
@@ -496,25 +748,131 @@ impl GameState {
             .position(|player| player.id == player_id)
     }
 
+    fn ensure_bidding(&self) -> Result<(), GameError> {
+        match self.phase {
+            GamePhase::Bidding => Ok(()),
+            GamePhase::Doubling | GamePhase::Playing => Err(GameError::WrongPhase),
+            GamePhase::Finished => Err(GameError::GameOver),
+        }
+    }
+
+    /// 叫分 with 0 (pass) to 3 points; only valid in `BiddingMode::Points`.
+    pub fn bid(&mut self, player_idx: usize, value: u8) -> Result<BidOutcome, GameError> {
+        self.ensure_bidding()?;
+        let Bidding::Points(bidding) = &mut self.bidding else {
+            return Err(GameError::WrongBiddingMode);
+        };
+        let outcome = bidding.bid(player_idx, value)?;
+        self.apply_bid_outcome(player_idx, Action::Bid(value), &outcome);
+        Ok(outcome)
+    }
+
+    /// 叫地主 when nobody has called yet, 抢地主 afterwards; only valid in
+    /// `BiddingMode::Rob`.
+    pub fn rob(&mut self, player_idx: usize, take: bool) -> Result<BidOutcome, GameError> {
+        self.ensure_bidding()?;
+        let Bidding::Rob(bidding) = &mut self.bidding else {
+            return Err(GameError::WrongBiddingMode);
+        };
+        let outcome = bidding.rob(player_idx, take)?;
+        self.apply_bid_outcome(player_idx, Action::Rob(take), &outcome);
+        Ok(outcome)
+    }
+
+    fn apply_bid_outcome(&mut self, player_idx: usize, action: Action, outcome: &BidOutcome) {
+        let mut reset = None;
+        match *outcome {
+            BidOutcome::Next { next_bidder } => {
+                self.turn = next_bidder;
+            }
+            BidOutcome::Landlord { landlord, bid } => {
+                let hand = &mut self.players[landlord].hand;
+                hand.extend(self.bottom.iter().copied());
+                sort_hand(hand);
+                self.landlord = Some(landlord);
+                self.bid = bid;
+                self.multiplier = self.bidding.multiplier();
+                self.turn = landlord;
+                self.phase = GamePhase::Playing;
+                if self.rules.doubling {
+                    self.doubling = Doubling::new(landlord, self.players.len());
+                    self.turn = self.doubling.turn().unwrap_or(landlord);
+                    self.phase = GamePhase::Doubling;
+                }
+            }
+            BidOutcome::Redeal { first_bidder } => {
+                let seed = next_deal_seed(self.deck_seed);
+                self.deal_hands(seed, first_bidder);
+                reset = Some(Reset::Redeal { seed });
+            }
+        }
+        self.record(player_idx, action, None, reset);
+    }
+
+    /// 加倍 with a factor from `DOUBLE_FACTORS`; the landlord leads once every
+    /// seat has decided.
+    pub fn double(&mut self, player_idx: usize, factor: u32) -> Result<usize, GameError> {
+        match self.phase {
+            GamePhase::Doubling => {}
+            GamePhase::Finished => return Err(GameError::GameOver),
+            _ => return Err(GameError::WrongPhase),
+        }
+        match self.doubling.double(player_idx, factor)? {
+            Some(next) => self.turn = next,
+            None => {
+                self.turn = self.landlord.unwrap_or(player_idx);
+                self.phase = GamePhase::Playing;
+            }
+        }
+        self.record(player_idx, Action::Double(factor), None, None);
+        Ok(self.turn)
+    }
+
+    fn ensure_playing(&self) -> Result<(), GameError> {
+        match self.phase {
+            GamePhase::Playing => Ok(()),
+            GamePhase::Bidding | GamePhase::Doubling => Err(GameError::WrongPhase),
+            GamePhase::Finished => Err(GameError::GameOver),
+        }
+    }
+
     pub fn apply_play(
         &mut self,
         player_idx: usize,
         cards: Vec<Card>,
     ) -> Result<PlayOutcome, GameError> {
-        if self.players.iter().any(|p| p.out) {
-            return Err(GameError::GameOver);
-        }
+        self.apply_play_as(player_idx, cards, None)
+    }
+
+    /// Plays `cards` read as the `intended` kind when one is given. Among the
+    /// remaining readings the first one that beats the previous play is used.
+    pub fn apply_play_as(
+        &mut self,
+        player_idx: usize,
+        cards: Vec<Card>,
+        intended: Option<PlayKind>,
+    ) -> Result<PlayOutcome, GameError> {
+        self.ensure_playing()?;
         if player_idx != self.turn {
             return Err(GameError::NotYourTurn);
         }
-        let play = classify_play(&cards).ok_or(GameError::InvalidPlay)?;
-        if let Some(prev) = &self.last_play {
-            if let Some(last_player) = self.last_player {
-                if last_player != player_idx && !can_beat(prev, &play) {
-                    return Err(GameError::MustBeatPrevious);
-                }
-            }
+        let mut readings = classify_all_with(&cards, &self.rules, self.wild_rank)
+            .into_iter()
+            .filter(|play| intended.is_none_or(|kind| play.kind == kind))
+            .peekable();
+        if readings.peek().is_none() {
+            return Err(GameError::InvalidPlay);
         }
+        let prev = self
+            .last_play
+            .as_ref()
+            .filter(|_| self.last_player.is_some_and(|last| last != player_idx));
+        let play = match prev {
+            Some(prev) => readings
+                .find(|play| can_beat_with(prev, play, &self.rules))
+                .ok_or(GameError::MustBeatPrevious)?,
+            None => readings.next().ok_or(GameError::InvalidPlay)?,
+        };
         let hand = &mut self.players[player_idx].hand;
         let mut needed = HashMap::new();
         for card in cards.iter() {
@@ -531,13 +889,25 @@ impl GameState {
                 hand.remove(pos);
             }
         }
+        let emptied = hand.is_empty();
+        if play.kind.is_bomb() {
+            self.bombs_played += 1;
+        }
+        self.plays[player_idx] += 1;
         self.last_play = Some(play.clone());
         self.last_player = Some(player_idx);
         self.pass_count = 0;
-        let next_turn = (player_idx + 1) % 3;
+        let next_turn = (player_idx + 1) % self.players.len();
         self.turn = next_turn;
-        if hand.is_empty() {
+        let action = Action::Play {
+            cards,
+            kind: intended,
+        };
+        self.record(player_idx, action, Some(play.clone()), None);
+        if emptied {
             self.players[player_idx].out = true;
+            self.winner = Some(player_idx);
+            self.phase = GamePhase::Finished;
             return Ok(PlayOutcome {
                 play,
                 next_turn,
@@ -552,9 +922,7 @@ impl GameState {
     }
 
     pub fn pass(&mut self, player_idx: usize) -> Result<usize, GameError> {
-        if self.players.iter().any(|p| p.out) {
-            return Err(GameError::GameOver);
-        }
+        self.ensure_playing()?;
         if player_idx != self.turn {
             return Err(GameError::NotYourTurn);
         }
@@ -562,14 +930,168 @@ impl GameState {
             return Err(GameError::CannotPass);
         }
         self.pass_count = self.pass_count.saturating_add(1);
-        if self.pass_count >= 2 {
+        let mut reset = None;
+        if usize::from(self.pass_count) + 1 >= self.players.len() {
             self.last_play = None;
             self.last_player = None;
             self.pass_count = 0;
+            reset = Some(Reset::NewLead);
         }
-        self.turn = (self.turn + 1) % 3;
+        self.turn = (self.turn + 1) % self.players.len();
+        self.record(player_idx, Action::Pass, None, reset);
         Ok(self.turn)
     }
+
+    /// 明牌: plays with the hand face up. Any seat may show while the bidding
+    /// is still running (`SHOW_HAND_AT_DEAL`); once play starts only the
+    /// landlord may, before the first card (`SHOW_HAND_AT_PLAY`). Returns the
+    /// factor applied to the stakes.
+    pub fn show_hand(&mut self, player_idx: usize) -> Result<u32, GameError> {
+        if !self.rules.show_hand
+            || player_idx >= self.players.len()
+            || self.shown[player_idx].is_some()
+        {
+            return Err(GameError::CannotShowHand);
+        }
+        let factor = match self.phase {
+            GamePhase::Bidding => SHOW_HAND_AT_DEAL,
+            GamePhase::Doubling | GamePhase::Playing
+                if self.landlord == Some(player_idx) && self.plays.iter().all(|n| *n == 0) =>
+            {
+                SHOW_HAND_AT_PLAY
+            }
+            GamePhase::Finished => return Err(GameError::GameOver),
+            _ => return Err(GameError::CannotShowHand),
+        };
+        self.shown[player_idx] = Some(factor);
+        self.record(player_idx, Action::ShowHand, None, None);
+        Ok(factor)
+    }
+
+    /// Largest 明牌 factor declared at the table, or 1.
+    pub fn show_hand_factor(&self) -> u32 {
+        self.shown.iter().flatten().copied().max().unwrap_or(1)
+    }
+
+    /// Multiplier at this point of the game, before spring is known.
+    pub fn current_multiplier(&self) -> u32 {
+        let multiplier = match self.phase {
+            GamePhase::Bidding => self.bidding.multiplier(),
+            _ => self.multiplier,
+        };
+        let raw = (u64::from(multiplier) * u64::from(self.show_hand_factor()))
+            << self.bombs_played.min(32);
+        scoring::capped_multiplier(raw, &self.rules.scoring)
+    }
+
+    /// 加倍 factor per seat; 1 for seats that did not double.
+    pub fn doubles(&self) -> Vec<u32> {
+        (0..self.players.len())
+            .map(|idx| self.doubling.factor(idx))
+            .collect()
+    }
+
+    /// Multiplier a seat is playing for: a farmer's own stake including both
+    /// its and the landlord's doubling, or the sum over all farmers for the
+    /// landlord.
+    pub fn player_multiplier(&self, player_idx: usize) -> u32 {
+        let table = self.current_multiplier();
+        let Some(landlord) = self.landlord else {
+            return table;
+        };
+        let stake = |farmer: usize| {
+            let raw = u64::from(table)
+                * u64::from(self.doubling.factor(farmer))
+                * u64::from(self.doubling.factor(landlord));
+            scoring::capped_multiplier(raw, &self.rules.scoring)
+        };
+        if player_idx != landlord {
+            return stake(player_idx);
+        }
+        (0..self.players.len())
+            .filter(|idx| *idx != landlord)
+            .map(stake)
+            .fold(0u32, u32::saturating_add)
+    }
+
+    /// Score settlement, available once somebody has gone out.
+    pub fn settlement(&self) -> Option<Settlement> {
+        let landlord = self.landlord?;
+        let winner = self.winner?;
+        let input = ScoreInput {
+            landlord,
+            winner,
+            bid: self.bid,
+            bid_multiplier: self.multiplier,
+            bombs: self.bombs_played,
+            show_hand: self.show_hand_factor(),
+            plays: self.plays.clone(),
+            doubles: self.doubles(),
+        };
+        Some(scoring::settle(&input, &self.rules.scoring))
+    }
+
+    /// Every play the seat could make now against the current lead.
+    pub fn legal_moves(&self, player_idx: usize) -> Vec<Move> {
+        legal_moves_wild(
+            &self.players[player_idx].hand,
+            self.last_play.as_ref(),
+            &self.rules,
+            self.wild_rank,
+        )
+    }
+
+    /// `legal_moves` ranked as hints for the seat.
+    pub fn hints(&self, player_idx: usize) -> Vec<Move> {
+        hints(
+            &self.players[player_idx].hand,
+            self.last_play.as_ref(),
+            &self.rules,
+            self.wild_rank,
+        )
+    }
+
+    /// Side a seat plays for, once the landlord is known.
+    pub fn side_of(&self, player_idx: usize) -> Option<Side> {
+        let landlord = self.landlord?;
+        Some(if player_idx == landlord {
+            Side::Landlord
+        } else {
+            Side::Farmers
+        })
+    }
+
+    /// Result for the whole table, available once the game is finished.
+    pub fn result(&self) -> Option<GameResult> {
+        if self.phase != GamePhase::Finished {
+            return None;
+        }
+        let first_out = self.winner?;
+        let settlement = self.settlement()?;
+        let winning_side = self.side_of(first_out)?;
+        let players = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(idx, player)| {
+                let side = self.side_of(idx).unwrap_or(Side::Farmers);
+                PlayerResult {
+                    id: player.id,
+                    side,
+                    won: side == winning_side,
+                    remaining: player.hand.clone(),
+                    plays: self.plays[idx],
+                    delta: settlement.deltas[idx],
+                }
+            })
+            .collect();
+        Some(GameResult {
+            winning_side,
+            first_out,
+            players,
+            settlement,
+        })
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -579,6 +1101,13 @@ mod tests {
         Card { rank, suit }
     }
 
+    fn started_game(seed: u64) -> GameState {
+        let mut state = GameState::new(&[1, 2, 3], seed);
+        let bidder = state.turn;
+        state.bid(bidder, MAX_BID).unwrap();
+        state
+    }
+
     #[test]
     fn deck_has_54_cards() {
         let deck = standard_deck();
@@ -736,6 +1265,119 @@ mod tests {
         assert_eq!(play.size, 2);
     }
 
+    fn cards_of(ranks: &[Rank]) -> Vec<Card> {
+        let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+        let mut used: HashMap<Rank, usize> = HashMap::new();
+        ranks
+            .iter()
+            .map(|rank| {
+                if rank.is_joker() {
+                    return card(*rank, Suit::Joker);
+                }
+                let n = used.entry(*rank).or_insert(0);
+                *n += 1;
+                card(*rank, suits[*n - 1])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classify_airplane_with_single_wings() {
+        use Rank::*;
+        let play = classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, Five, Six,
+        ]))
+        .unwrap();
+        assert_eq!(play.kind, PlayKind::AirplaneSingles);
+        assert_eq!(play.main_rank, Four);
+        assert_eq!(play.size, 2);
+
+        let pair_as_wings = classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, Nine, Nine,
+        ]))
+        .unwrap();
+        assert_eq!(pair_as_wings.kind, PlayKind::AirplaneSingles);
+    }
+
+    #[test]
+    fn classify_airplane_with_pair_wings() {
+        use Rank::*;
+        let play = classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, Five, Five, Six, Six,
+        ]))
+        .unwrap();
+        assert_eq!(play.kind, PlayKind::AirplanePairs);
+        assert_eq!(play.main_rank, Four);
+        assert_eq!(play.size, 2);
+    }
+
+    #[test]
+    fn airplane_wing_count_must_match_triples() {
+        use Rank::*;
+        assert!(classify_play(&cards_of(&[Three, Three, Three, Four, Four, Four, Five])).is_none());
+        assert!(classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, Five, Five, Six
+        ]))
+        .is_none());
+        assert!(classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, Five, Five, Six, Seven
+        ]))
+        .is_none());
+    }
+
+    #[test]
+    fn airplane_rejects_rocket_as_single_wings() {
+        use Rank::*;
+        let play = classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, BlackJoker, RedJoker,
+        ]));
+        assert!(play.is_none());
+    }
+
+    #[test]
+    fn airplane_wings_cannot_reuse_chain_rank() {
+        use Rank::*;
+        let play = classify_play(&cards_of(&[
+            Three, Three, Three, Three, Four, Four, Four, Five,
+        ]));
+        assert!(play.is_none());
+    }
+
+    #[test]
+    fn airplane_with_wings_picks_highest_chain() {
+        use Rank::*;
+        let play = classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, Five, Five, Five, Nine, Nine, Nine,
+        ]))
+        .unwrap();
+        assert_eq!(play.kind, PlayKind::AirplaneSingles);
+        assert_eq!(play.main_rank, Five);
+        assert_eq!(play.size, 3);
+    }
+
+    #[test]
+    fn airplane_with_wings_compares_chain_length_and_top() {
+        use Rank::*;
+        let low = classify_play(&cards_of(&[
+            Three, Three, Three, Four, Four, Four, Ace, King,
+        ]))
+        .unwrap();
+        let high =
+            classify_play(&cards_of(&[Five, Five, Five, Six, Six, Six, Three, Four])).unwrap();
+        let longer = classify_play(&cards_of(&[
+            Six, Six, Six, Seven, Seven, Seven, Eight, Eight, Eight, Three, Four, Five,
+        ]))
+        .unwrap();
+        let pairs = classify_play(&cards_of(&[
+            Eight, Eight, Eight, Nine, Nine, Nine, Three, Three, Four, Four,
+        ]))
+        .unwrap();
+        assert!(can_beat(&low, &high));
+        assert!(!can_beat(&high, &low));
+        assert!(!can_beat(&low, &longer));
+        assert!(!can_beat(&low, &pairs));
+    }
+
     #[test]
     fn classify_four_two_single() {
         let play = classify_play(&[
@@ -825,15 +1467,177 @@ mod tests {
     }
 
     #[test]
-    fn game_state_starts_with_landlord_turn() {
-        let state = GameState::new([1, 2, 3], 7);
-        assert_eq!(state.turn, state.landlord);
-        assert_eq!(state.players[state.landlord].hand.len(), 20);
+    fn game_state_starts_in_bidding_phase() {
+        let state = GameState::new(&[1, 2, 3], 7);
+        assert_eq!(state.phase, GamePhase::Bidding);
+        assert_eq!(state.landlord, None);
+        assert_eq!(state.turn, state.bidding.first_bidder());
+        assert!(state.players.iter().all(|p| p.hand.len() == 17));
+        assert_eq!(state.bottom.len(), 3);
+    }
+
+    #[test]
+    fn landlord_takes_bottom_cards_and_leads() {
+        let state = started_game(7);
+        let landlord = state.landlord.unwrap();
+        assert_eq!(state.phase, GamePhase::Playing);
+        assert_eq!(state.turn, landlord);
+        assert_eq!(state.bid, MAX_BID);
+        assert_eq!(state.players[landlord].hand.len(), 20);
+        assert!(state
+            .bottom
+            .iter()
+            .all(|card| state.players[landlord].hand.contains(card)));
+    }
+
+    #[test]
+    fn all_passing_redeals_with_rotated_first_bidder() {
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 7, RuleSet::default(), 1);
+        let before = state.players[0].hand.clone();
+        state.bid(1, 0).unwrap();
+        state.bid(2, 0).unwrap();
+        let outcome = state.bid(0, 0).unwrap();
+        assert_eq!(outcome, BidOutcome::Redeal { first_bidder: 2 });
+        assert_eq!(state.phase, GamePhase::Bidding);
+        assert_eq!(state.turn, 2);
+        assert_ne!(state.deck_seed, 7);
+        assert_ne!(state.players[0].hand, before);
+        assert_eq!(state.bidding, Bidding::new(BiddingMode::Points, 2, 3));
+    }
+
+    #[test]
+    fn rob_mode_assigns_landlord_with_doubled_multiplier() {
+        let rules = RuleSet {
+            bidding: BiddingMode::Rob,
+            ..RuleSet::default()
+        };
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 11, rules, 0);
+        assert_eq!(state.bid(0, 1).err(), Some(GameError::WrongBiddingMode));
+        state.rob(0, true).unwrap();
+        assert_eq!(state.rob(2, true).err(), Some(GameError::NotYourTurn));
+        state.rob(1, true).unwrap();
+        state.rob(2, false).unwrap();
+        let outcome = state.rob(0, false).unwrap();
+        assert_eq!(
+            outcome,
+            BidOutcome::Landlord {
+                landlord: 1,
+                bid: 1
+            }
+        );
+        assert_eq!(state.phase, GamePhase::Playing);
+        assert_eq!(state.landlord, Some(1));
+        assert_eq!(state.multiplier, 2);
+        assert_eq!(state.players[1].hand.len(), 20);
+    }
+
+    #[test]
+    fn points_mode_rejects_rob() {
+        let mut state = GameState::new(&[1, 2, 3], 11);
+        let idx = state.turn;
+        assert_eq!(
+            state.rob(idx, true).err(),
+            Some(GameError::WrongBiddingMode)
+        );
+    }
+
+    #[test]
+    fn settlement_only_after_game_over() {
+        let mut state = started_game(21);
+        assert!(state.settlement().is_none());
+        let landlord = state.landlord.unwrap();
+        let cards = state.players[landlord].hand.clone();
+        state.players[landlord].hand = vec![cards[0]];
+        state.apply_play(landlord, vec![cards[0]]).unwrap();
+        assert_eq!(state.phase, GamePhase::Finished);
+        let settlement = state.settlement().unwrap();
+        assert!(settlement.landlord_won);
+        assert_eq!(settlement.spring, Spring::Spring);
+        assert_eq!(settlement.bid, MAX_BID);
+        assert_eq!(settlement.multiplier, 2);
+        assert_eq!(settlement.deltas.iter().sum::<i64>(), 0);
+        assert_eq!(settlement.deltas[landlord], 12);
+    }
+
+    #[test]
+    fn farmer_going_out_wins_for_both_farmers() {
+        let mut state = started_game(23);
+        let landlord = state.landlord.unwrap();
+        let lead = card(Rank::Three, Suit::Clubs);
+        state.players[landlord].hand[0] = lead;
+        state.apply_play(landlord, vec![lead]).unwrap();
+        assert!(state.result().is_none());
+        let farmer = state.turn;
+        let other = (0..3)
+            .find(|idx| *idx != landlord && *idx != farmer)
+            .unwrap();
+        let last = card(Rank::RedJoker, Suit::Joker);
+        state.players[farmer].hand = vec![last];
+        state.apply_play(farmer, vec![last]).unwrap();
+
+        let result = state.result().unwrap();
+        assert_eq!(result.winning_side, Side::Farmers);
+        assert_eq!(result.first_out, farmer);
+        let mut winners = vec![farmer, other];
+        winners.sort();
+        assert_eq!(result.winners(), winners);
+        assert!(!result.players[landlord].won);
+        assert_eq!(result.players[landlord].side, Side::Landlord);
+        assert_eq!(result.players[landlord].remaining.len(), 19);
+        assert_eq!(result.players[other].remaining.len(), 17);
+        assert!(result.players[farmer].remaining.is_empty());
+        assert_eq!(result.plays_by(Side::Landlord), 1);
+        assert_eq!(result.plays_by(Side::Farmers), 1);
+        assert_eq!(result.settlement.spring, Spring::AntiSpring);
+        assert_eq!(result.players[other].delta, result.settlement.deltas[other]);
+    }
+
+    #[test]
+    fn bombs_raise_current_multiplier() {
+        let mut state = started_game(22);
+        let landlord = state.landlord.unwrap();
+        let bomb: Vec<Card> = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades]
+            .iter()
+            .map(|suit| card(Rank::Nine, *suit))
+            .collect();
+        state.players[landlord].hand.extend(bomb.iter().copied());
+        state.apply_play(landlord, bomb).unwrap();
+        assert_eq!(state.bombs_played, 1);
+        assert_eq!(state.current_multiplier(), 2);
+    }
+
+    #[test]
+    fn laizi_option_draws_wild_rank_after_deal() {
+        let classic = GameState::new(&[1, 2, 3], 23);
+        assert_eq!(classic.wild_rank, None);
+        let rules = RuleSet {
+            laizi: true,
+            ..RuleSet::default()
+        };
+        let state = GameState::with_rules(&[1, 2, 3], 23, rules);
+        let wild = state.wild_rank.unwrap();
+        assert!(!wild.is_joker());
+    }
+
+    #[test]
+    fn play_not_allowed_during_bidding() {
+        let mut state = GameState::new(&[1, 2, 3], 9);
+        let idx = state.turn;
+        let card_play = state.players[idx].hand[0];
+        let result = state.apply_play(idx, vec![card_play]);
+        assert_eq!(result.err(), Some(GameError::WrongPhase));
+    }
+
+    #[test]
+    fn bid_not_allowed_after_landlord_decided() {
+        let mut state = started_game(9);
+        let idx = state.turn;
+        assert_eq!(state.bid(idx, 1).err(), Some(GameError::WrongPhase));
     }
 
     #[test]
     fn apply_play_removes_cards() {
-        let mut state = GameState::new([1, 2, 3], 9);
+        let mut state = started_game(9);
         let idx = state.turn;
         let card_play = state.players[idx].hand[0];
         let outcome = state.apply_play(idx, vec![card_play]).unwrap();
@@ -843,7 +1647,7 @@ mod tests {
 
     #[test]
     fn apply_play_checks_turn() {
-        let mut state = GameState::new([1, 2, 3], 9);
+        let mut state = started_game(9);
         let idx = (state.turn + 1) % 3;
         let card_play = state.players[idx].hand[0];
         let result = state.apply_play(idx, vec![card_play]);
@@ -852,7 +1656,7 @@ mod tests {
 
     #[test]
     fn apply_play_rejects_missing_cards() {
-        let mut state = GameState::new([1, 2, 3], 9);
+        let mut state = started_game(9);
         let idx = state.turn;
         let card_play = standard_deck()
             .into_iter()
@@ -864,7 +1668,7 @@ mod tests {
 
     #[test]
     fn apply_play_requires_beating_previous() {
-        let mut state = GameState::new([1, 2, 3], 9);
+        let mut state = started_game(9);
         let idx = state.turn;
         let next_idx = (idx + 1) % 3;
         let first = state.players[idx].hand[0];
@@ -904,14 +1708,385 @@ mod tests {
     }
     #[test]
     fn pass_not_allowed_without_previous_play() {
-        let mut state = GameState::new([1, 2, 3], 9);
+        let mut state = started_game(9);
         let result = state.pass(state.turn);
         assert_eq!(result.err(), Some(GameError::CannotPass));
     }
 
+    #[test]
+    fn four_player_game_deals_two_decks() {
+        let rules = RuleSet {
+            variant: Variant::FourPlayer,
+            ..RuleSet::default()
+        };
+        let mut state = GameState::with_first_bidder(&[1, 2, 3, 4], 5, rules, 3);
+        assert!(state.players.iter().all(|p| p.hand.len() == 25));
+        assert_eq!(state.bottom.len(), 8);
+        state.bid(3, 1).unwrap();
+        state.bid(0, 0).unwrap();
+        state.bid(1, 2).unwrap();
+        let outcome = state.bid(2, 0).unwrap();
+        assert_eq!(
+            outcome,
+            BidOutcome::Landlord {
+                landlord: 1,
+                bid: 2
+            }
+        );
+        assert_eq!(state.players[1].hand.len(), 33);
+
+        let first_card = state.players[1].hand[0];
+        state.apply_play(1, vec![first_card]).unwrap();
+        for seat in [2, 3, 0] {
+            assert!(state.last_play.is_some());
+            state.pass(seat).unwrap();
+        }
+        assert!(state.last_play.is_none());
+        assert_eq!(state.turn, 1);
+    }
+
+    #[test]
+    fn two_player_game_resets_lead_after_one_pass() {
+        let rules = RuleSet {
+            variant: Variant::TwoPlayer,
+            ..RuleSet::default()
+        };
+        let mut state = GameState::with_first_bidder(&[1, 2], 8, rules, 0);
+        assert_eq!(state.leftover.len(), 9);
+        state.bid(0, 1).unwrap();
+        let outcome = state.bid(1, 0).unwrap();
+        assert_eq!(
+            outcome,
+            BidOutcome::Landlord {
+                landlord: 0,
+                bid: 1
+            }
+        );
+        assert_eq!(state.players[0].hand.len(), 20);
+        assert_eq!(state.players[1].hand.len(), 17);
+
+        let first_card = state.players[0].hand[0];
+        state.apply_play(0, vec![first_card]).unwrap();
+        state.pass(1).unwrap();
+        assert!(state.last_play.is_none());
+        assert_eq!(state.turn, 0);
+        assert_eq!(state.pass(0).err(), Some(GameError::CannotPass));
+    }
+
+    #[test]
+    #[should_panic(expected = "player count must match the variant")]
+    fn variant_rejects_wrong_player_count() {
+        GameState::new(&[1, 2, 3, 4], 5);
+    }
+
+    #[test]
+    fn two_deck_bombs_grow_with_card_count() {
+        let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+        let two_decks = RulePreset::FourPlayer.rules();
+        let bomb = |rank: Rank, len: usize| {
+            let cards: Vec<Card> = (0..len).map(|i| card(rank, suits[i % 4])).collect();
+            classify_plain(&cards, &two_decks).unwrap()
+        };
+        let four_twos = bomb(Rank::Two, 4);
+        let five_threes = bomb(Rank::Three, 5);
+        let eight_threes = bomb(Rank::Three, 8);
+        assert_eq!(five_threes.kind, PlayKind::Bomb);
+        assert!(can_beat(&four_twos, &five_threes));
+        assert!(!can_beat(&five_threes, &four_twos));
+        assert!(can_beat(&five_threes, &eight_threes));
+        assert!(can_beat(&bomb(Rank::Four, 5), &bomb(Rank::Five, 5)));
+        assert!(classify_play(&[card(Rank::Three, Suit::Clubs); 5]).is_none());
+    }
+
+    #[test]
+    fn two_deck_rocket_needs_all_four_jokers() {
+        let black = card(Rank::BlackJoker, Suit::Joker);
+        let red = card(Rank::RedJoker, Suit::Joker);
+        let two_decks = RulePreset::FourPlayer.rules();
+        assert_eq!(classify_plain(&[black, red], &two_decks), None);
+        let rocket = classify_plain(&[black, black, red, red], &two_decks).unwrap();
+        assert_eq!(rocket.kind, PlayKind::Rocket);
+        let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+        let eights: Vec<Card> = (0..8).map(|i| card(Rank::Two, suits[i % 4])).collect();
+        assert!(can_beat(
+            &classify_plain(&eights, &two_decks).unwrap(),
+            &rocket
+        ));
+    }
+
+    #[test]
+    fn rule_set_controls_chain_lengths_and_kinds() {
+        let straight = [
+            card(Rank::Three, Suit::Clubs),
+            card(Rank::Four, Suit::Clubs),
+            card(Rank::Five, Suit::Clubs),
+            card(Rank::Six, Suit::Clubs),
+            card(Rank::Seven, Suit::Clubs),
+        ];
+        let long_straights = RuleSet {
+            min_straight: 6,
+            ..RuleSet::default()
+        };
+        assert!(classify_play(&straight).is_some());
+        assert!(classify_play_with(&straight, &long_straights, None).is_none());
+
+        let two_pairs = [
+            card(Rank::Three, Suit::Clubs),
+            card(Rank::Three, Suit::Hearts),
+            card(Rank::Four, Suit::Clubs),
+            card(Rank::Four, Suit::Hearts),
+        ];
+        let short_pairs = RuleSet {
+            min_double_straight: 2,
+            ..RuleSet::default()
+        };
+        assert!(classify_play(&two_pairs).is_none());
+        let play = classify_play_with(&two_pairs, &short_pairs, None).unwrap();
+        assert_eq!(play.kind, PlayKind::DoubleStraight);
+
+        let strict = RulePreset::Strict.rules();
+        let triple = [
+            card(Rank::Nine, Suit::Clubs),
+            card(Rank::Nine, Suit::Hearts),
+            card(Rank::Nine, Suit::Spades),
+        ];
+        assert!(classify_play_with(&triple, &strict, None).is_none());
+        let four_two = [
+            card(Rank::Nine, Suit::Clubs),
+            card(Rank::Nine, Suit::Hearts),
+            card(Rank::Nine, Suit::Spades),
+            card(Rank::Nine, Suit::Diamonds),
+            card(Rank::Three, Suit::Clubs),
+            card(Rank::Five, Suit::Clubs),
+        ];
+        assert!(classify_play_with(&four_two, &strict, None).is_none());
+    }
+
+    #[test]
+    fn disabled_bombs_cannot_beat() {
+        let no_bombs = RuleSet {
+            enabled_kinds: KindSet::ALL.without(PlayKind::Bomb),
+            ..RuleSet::default()
+        };
+        let pair = Play::new(PlayKind::Pair, Rank::Ace, 2);
+        let bomb = Play::new(PlayKind::Bomb, Rank::Three, 4);
+        assert!(can_beat(&pair, &bomb));
+        assert!(!can_beat_with(&pair, &bomb, &no_bombs));
+        let fours: Vec<Card> = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades]
+            .into_iter()
+            .map(|suit| card(Rank::Four, suit))
+            .collect();
+        assert!(classify_play_with(&fours, &no_bombs, None).is_none());
+    }
+
+    #[test]
+    fn doubling_round_runs_before_first_play() {
+        let rules = RuleSet {
+            doubling: true,
+            ..RuleSet::default()
+        };
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 12, rules, 0);
+        state.bid(0, MAX_BID).unwrap();
+        assert_eq!(state.phase, GamePhase::Doubling);
+        assert_eq!(state.turn, 1);
+        let card = state.players[0].hand[0];
+        assert_eq!(
+            state.apply_play(0, vec![card]).err(),
+            Some(GameError::WrongPhase)
+        );
+        assert_eq!(state.double(1, 3).err(), Some(GameError::InvalidDouble));
+        assert_eq!(state.double(1, 2), Ok(2));
+        assert_eq!(state.double(2, 1), Ok(0));
+        assert_eq!(state.player_multiplier(1), 2);
+        assert_eq!(state.double(0, 4), Ok(0));
+        assert_eq!(state.phase, GamePhase::Playing);
+        assert_eq!(state.doubles(), vec![4, 2, 1]);
+        assert_eq!(state.player_multiplier(1), 8);
+        assert_eq!(state.player_multiplier(2), 4);
+        assert_eq!(state.player_multiplier(0), 12);
+        assert_eq!(state.double(1, 2).err(), Some(GameError::WrongPhase));
+        state.apply_play(0, vec![card]).unwrap();
+    }
+
+    #[test]
+    fn show_hand_raises_multiplier_once_per_seat() {
+        let rules = RuleSet {
+            show_hand: true,
+            ..RuleSet::default()
+        };
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 12, rules, 0);
+        assert_eq!(state.show_hand(2), Ok(SHOW_HAND_AT_DEAL));
+        assert_eq!(state.show_hand(2).err(), Some(GameError::CannotShowHand));
+        state.bid(0, 1).unwrap();
+        state.bid(1, 2).unwrap();
+        state.bid(2, MAX_BID).unwrap();
+        assert_eq!(state.landlord, Some(2));
+        assert_eq!(state.current_multiplier(), 4);
+        assert_eq!(state.show_hand(0).err(), Some(GameError::CannotShowHand));
+
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 12, rules, 0);
+        state.bid(0, MAX_BID).unwrap();
+        assert_eq!(state.show_hand(0), Ok(SHOW_HAND_AT_PLAY));
+        assert_eq!(state.player_multiplier(0), 4);
+        let card = state.players[0].hand[0];
+        state.apply_play(0, vec![card]).unwrap();
+        assert_eq!(state.show_hand(0).err(), Some(GameError::CannotShowHand));
+        assert_eq!(state.show_hand(1).err(), Some(GameError::CannotShowHand));
+    }
+
+    #[test]
+    fn actions_for_a_seat_not_at_the_table_are_rejected() {
+        let mut state = GameState::with_rules(&[1, 2, 3], 12, RulePreset::Happy.rules());
+        let card = state.players[0].hand[0];
+        for action in [
+            Action::ShowHand,
+            Action::Rob(true),
+            Action::Bid(1),
+            Action::Double(2),
+            Action::Pass,
+            Action::Play {
+                cards: vec![card],
+                kind: None,
+            },
+        ] {
+            assert!(state.apply(99, action).is_err());
+        }
+        assert!(state.events.is_empty());
+    }
+
+    #[test]
+    fn show_hand_needs_rule() {
+        let mut state = started_game(3);
+        let landlord = state.landlord.unwrap();
+        assert_eq!(
+            state.show_hand(landlord).err(),
+            Some(GameError::CannotShowHand)
+        );
+    }
+
+    #[test]
+    fn classify_all_lists_every_reading() {
+        let cards = cards_of(&[
+            Rank::Three,
+            Rank::Three,
+            Rank::Three,
+            Rank::Four,
+            Rank::Four,
+            Rank::Four,
+            Rank::Five,
+            Rank::Five,
+            Rank::Five,
+            Rank::Six,
+            Rank::Six,
+            Rank::Six,
+        ]);
+        let readings: Vec<(PlayKind, Rank)> = classify_all(&cards)
+            .into_iter()
+            .map(|play| (play.kind, play.main_rank))
+            .collect();
+        assert_eq!(
+            readings,
+            vec![
+                (PlayKind::Airplane, Rank::Six),
+                (PlayKind::AirplaneSingles, Rank::Six),
+                (PlayKind::AirplaneSingles, Rank::Five),
+            ]
+        );
+        assert_eq!(classify_play(&cards).unwrap().kind, PlayKind::Airplane);
+
+        let quads = cards_of(&[
+            Rank::Seven,
+            Rank::Seven,
+            Rank::Seven,
+            Rank::Seven,
+            Rank::Nine,
+            Rank::Nine,
+            Rank::Nine,
+            Rank::Nine,
+        ]);
+        let readings: Vec<(PlayKind, Rank)> = classify_all(&quads)
+            .into_iter()
+            .map(|play| (play.kind, play.main_rank))
+            .collect();
+        assert_eq!(
+            readings,
+            vec![
+                (PlayKind::FourTwoPair, Rank::Nine),
+                (PlayKind::FourTwoPair, Rank::Seven),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_play_picks_reading_that_beats_previous() {
+        let mut state = started_game(9);
+        let leader = state.turn;
+        let next = (leader + 1) % 3;
+        let lead = cards_of(&[
+            Rank::Three,
+            Rank::Three,
+            Rank::Three,
+            Rank::Four,
+            Rank::Four,
+            Rank::Four,
+            Rank::Five,
+            Rank::Five,
+            Rank::Five,
+            Rank::Jack,
+            Rank::Queen,
+            Rank::King,
+        ]);
+        let reply = cards_of(&[
+            Rank::Six,
+            Rank::Six,
+            Rank::Six,
+            Rank::Seven,
+            Rank::Seven,
+            Rank::Seven,
+            Rank::Eight,
+            Rank::Eight,
+            Rank::Eight,
+            Rank::Nine,
+            Rank::Nine,
+            Rank::Nine,
+        ]);
+        state.players[leader].hand = lead.clone();
+        state.players[leader]
+            .hand
+            .push(card(Rank::Two, Suit::Spades));
+        state.players[next].hand = reply.clone();
+        state.players[next].hand.push(card(Rank::Two, Suit::Hearts));
+
+        let outcome = state
+            .apply_play_as(leader, lead, Some(PlayKind::AirplaneSingles))
+            .unwrap();
+        assert_eq!(outcome.play.main_rank, Rank::Five);
+
+        assert_eq!(
+            classify_play(&reply).unwrap().kind,
+            PlayKind::Airplane,
+            "the plain airplane is the preferred reading"
+        );
+        assert_eq!(
+            state
+                .apply_play_as(next, reply.clone(), Some(PlayKind::Airplane))
+                .err(),
+            Some(GameError::MustBeatPrevious)
+        );
+        assert_eq!(
+            state
+                .apply_play_as(next, reply.clone(), Some(PlayKind::Bomb))
+                .err(),
+            Some(GameError::InvalidPlay)
+        );
+        let outcome = state.apply_play(next, reply).unwrap();
+        assert_eq!(outcome.play.kind, PlayKind::AirplaneSingles);
+        assert_eq!(outcome.play.main_rank, Rank::Nine);
+    }
+
     #[test]
     fn two_passes_reset_last_play() {
-        let mut state = GameState::new([1, 2, 3], 9);
+        let mut state = started_game(9);
         let leader = state.turn;
         let first_card = state.players[leader].hand[0];
         state.apply_play(leader, vec![first_card]).unwrap();
@@ -0,0 +1,574 @@
+use crate::{
+    bomb_strength, can_beat_with, classify_all_with, sort_hand, Card, Play, PlayKind, Rank,
+    RuleSet, SUITED_RANKS,
+};
+use std::collections::BTreeSet;
+
+/// A legal play together with the cards from the hand that make it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub cards: Vec<Card>,
+    pub play: Play,
+}
+
+/// Cards taken per rank, indexed like `RANKS`.
+pub(crate) type Counts = [u8; 15];
+
+pub(crate) const RANKS: [Rank; 15] = [
+    SUITED_RANKS[0],
+    SUITED_RANKS[1],
+    SUITED_RANKS[2],
+    SUITED_RANKS[3],
+    SUITED_RANKS[4],
+    SUITED_RANKS[5],
+    SUITED_RANKS[6],
+    SUITED_RANKS[7],
+    SUITED_RANKS[8],
+    SUITED_RANKS[9],
+    SUITED_RANKS[10],
+    SUITED_RANKS[11],
+    SUITED_RANKS[12],
+    Rank::BlackJoker,
+    Rank::RedJoker,
+];
+
+/// Ranks from three to ace, the ones chains may use.
+pub(crate) const CHAIN_RANKS: usize = 12;
+pub(crate) const BLACK_JOKER: usize = 13;
+pub(crate) const RED_JOKER: usize = 14;
+
+pub(crate) fn rank_index(rank: Rank) -> usize {
+    // Discriminants run three to ace as 3..=14, then skip 15 before the two.
+    match rank as usize {
+        face @ 3..=14 => face - 3,
+        high => high - 4,
+    }
+}
+
+/// Every distinct play `hand` can make against `last_play` under the classic
+/// deck, with the cards it uses. `None` means the player leads.
+pub fn legal_moves(hand: &[Card], last_play: Option<&Play>, rules: &RuleSet) -> Vec<Move> {
+    legal_moves_wild(hand, last_play, rules, None)
+}
+
+/// `legal_moves` with `wild` as the 癞子 rank. Plays that use the same cards
+/// but read differently are listed once per reading. Moves come ordered by
+/// kind, length and rank, with bombs last from weakest to strongest; within a
+/// rank the cards are picked lowest suit first.
+pub fn legal_moves_wild(
+    hand: &[Card],
+    last_play: Option<&Play>,
+    rules: &RuleSet,
+    wild: Option<Rank>,
+) -> Vec<Move> {
+    let mut sorted = hand.to_vec();
+    sort_hand(&mut sorted);
+    let mut generator = Generator::new(&sorted, rules, wild);
+    generator.run(last_play.map(|play| play.kind));
+
+    let mut moves = Vec::new();
+    for counts in &generator.out {
+        let cards = take_cards(&sorted, counts);
+        for play in classify_all_with(&cards, rules, wild) {
+            if last_play.is_none_or(|prev| can_beat_with(prev, &play, rules)) {
+                moves.push(Move {
+                    cards: cards.clone(),
+                    play,
+                });
+            }
+        }
+    }
+    moves.sort_by_cached_key(|candidate| {
+        let play = &candidate.play;
+        let ranks: Vec<Rank> = candidate.cards.iter().map(|card| card.rank).collect();
+        (
+            bomb_strength(play),
+            kind_order(play.kind),
+            play.size,
+            play.main_rank,
+            ranks,
+        )
+    });
+    moves
+}
+
+pub(crate) fn kind_order(kind: PlayKind) -> usize {
+    PlayKind::ALL
+        .iter()
+        .position(|candidate| *candidate == kind)
+        .unwrap_or_default()
+}
+
+/// The first `counts[i]` cards of each rank from a sorted hand.
+pub(crate) fn take_cards(sorted: &[Card], counts: &Counts) -> Vec<Card> {
+    let mut left = *counts;
+    sorted
+        .iter()
+        .copied()
+        .filter(|card| {
+            let slot = &mut left[rank_index(card.rank)];
+            let take = *slot > 0;
+            *slot = slot.saturating_sub(1);
+            take
+        })
+        .collect()
+}
+
+/// Enumerates candidate card sets shaped like each play kind. Candidates only
+/// need to cover every legal play; classification decides which are legal.
+/// Wild cards are kept apart from the natural counts and may top up any
+/// non-joker rank.
+struct Generator<'a> {
+    rules: &'a RuleSet,
+    decks: u8,
+    naturals: Counts,
+    wilds: u8,
+    wild: Option<usize>,
+    out: BTreeSet<Counts>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(hand: &[Card], rules: &'a RuleSet, wild: Option<Rank>) -> Self {
+        let mut naturals = [0u8; 15];
+        let mut wilds = 0;
+        for card in hand {
+            if Some(card.rank) == wild {
+                wilds += 1;
+            } else {
+                naturals[rank_index(card.rank)] += 1;
+            }
+        }
+        Generator {
+            rules,
+            decks: rules.variant.decks() as u8,
+            naturals,
+            wilds,
+            wild: wild.map(rank_index),
+            out: BTreeSet::new(),
+        }
+    }
+
+    fn run(&mut self, lead: Option<PlayKind>) {
+        let rules = self.rules;
+        let wants = |kind: PlayKind| rules.allows(kind) && lead.is_none_or(|lead| lead == kind);
+        let sets = [
+            (PlayKind::Single, 1),
+            (PlayKind::Pair, 2),
+            (PlayKind::Triple, 3),
+        ];
+        for (kind, size) in sets {
+            if wants(kind) {
+                for rank in 0..RANKS.len() {
+                    self.emit_filled(&[(rank, size)]);
+                }
+            }
+        }
+        if wants(PlayKind::TripleSingle) {
+            self.with_singles(&[3], 1, 1);
+        }
+        if wants(PlayKind::TriplePair) {
+            self.with_pairs(&[3], 1, false);
+        }
+        if wants(PlayKind::FourTwoSingle) {
+            self.with_singles(&[4], 2, 1);
+        }
+        if wants(PlayKind::FourTwoPair) {
+            self.with_pairs(&[4], 2, true);
+        }
+        let chains = [
+            (PlayKind::Straight, 1, rules.min_straight),
+            (PlayKind::DoubleStraight, 2, rules.min_double_straight),
+            (PlayKind::Airplane, 3, rules.min_airplane),
+        ];
+        for (kind, width, min_len) in chains {
+            if wants(kind) {
+                for chain in chains_of(min_len) {
+                    let reqs: Vec<(usize, u8)> = chain.map(|rank| (rank, width)).collect();
+                    self.emit_filled(&reqs);
+                }
+            }
+        }
+        if wants(PlayKind::AirplaneSingles) {
+            for chain in chains_of(rules.min_airplane) {
+                let len = chain.len() as u8;
+                let reqs: Vec<(usize, u8)> = chain.map(|rank| (rank, 3)).collect();
+                self.with_wings(&reqs, len, len);
+            }
+        }
+        if wants(PlayKind::AirplanePairs) {
+            for chain in chains_of(rules.min_airplane) {
+                let len = chain.len();
+                let reqs: Vec<(usize, u8)> = chain.map(|rank| (rank, 3)).collect();
+                self.with_pair_wings(&reqs, len, false);
+            }
+        }
+        self.bombs();
+    }
+
+    /// Bombs and rockets beat everything else, so they are always generated.
+    fn bombs(&mut self) {
+        let kinds = [PlayKind::Bomb, PlayKind::SoftBomb, PlayKind::LaiziBomb];
+        if kinds.iter().any(|kind| self.rules.allows(*kind)) {
+            for rank in 0..CHAIN_RANKS + 1 {
+                for size in 4..=4 * self.decks {
+                    self.emit_filled(&[(rank, size)]);
+                }
+            }
+        }
+        let decks = self.decks;
+        if self.rules.allows(PlayKind::Rocket)
+            && self.naturals[BLACK_JOKER] >= decks
+            && self.naturals[RED_JOKER] >= decks
+        {
+            let mut rocket = [0u8; 15];
+            rocket[BLACK_JOKER] = decks;
+            rocket[RED_JOKER] = decks;
+            self.out.insert(rocket);
+        }
+    }
+
+    /// A set of `core` cards of one rank plus `count` single kickers of other
+    /// ranks, at most `limit` natural kickers per rank.
+    fn with_singles(&mut self, core: &[u8], count: u8, limit: u8) {
+        for rank in 0..RANKS.len() {
+            let reqs: Vec<(usize, u8)> = core.iter().map(|size| (rank, *size)).collect();
+            self.with_wings(&reqs, count, limit);
+        }
+    }
+
+    /// A set of `core` cards of one rank plus `count` pairs of other ranks;
+    /// with `repeat` two pairs may share a rank.
+    fn with_pairs(&mut self, core: &[u8], count: usize, repeat: bool) {
+        for rank in 0..RANKS.len() {
+            let reqs: Vec<(usize, u8)> = core.iter().map(|size| (rank, *size)).collect();
+            self.with_pair_wings(&reqs, count, repeat);
+        }
+    }
+
+    /// `core` plus `count` kicker cards drawn from whatever is left, skipping
+    /// natural cards of the core ranks.
+    fn with_wings(&mut self, core: &[(usize, u8)], count: u8, limit: u8) {
+        for (taken, used) in self.fill(core, &self.naturals, self.wilds) {
+            let mut pool = [0u8; 15];
+            for (rank, slot) in pool.iter_mut().enumerate() {
+                if core.iter().all(|(core_rank, _)| *core_rank != rank) {
+                    *slot = self.naturals[rank].min(limit);
+                }
+            }
+            if let Some(wild) = self.wild {
+                pool[wild] = self.wilds - used;
+            }
+            let mut picks = Vec::new();
+            pick(&pool, count, 0, [0u8; 15], &mut picks);
+            for wings in picks {
+                self.out.insert(add(&taken, &wings));
+            }
+        }
+    }
+
+    /// `core` plus `count` pairs of distinct ranks outside the core, or with
+    /// `repeat` a rank may give two pairs.
+    fn with_pair_wings(&mut self, core: &[(usize, u8)], count: usize, repeat: bool) {
+        let mut ranks: Vec<usize> = (0..RANKS.len())
+            .filter(|rank| core.iter().all(|(core_rank, _)| core_rank != rank))
+            .filter(|rank| self.available(*rank) >= 2)
+            .collect();
+        if repeat {
+            let doubled: Vec<usize> = ranks.iter().flat_map(|rank| [*rank, *rank]).collect();
+            ranks = doubled;
+        }
+        let mut combos = BTreeSet::new();
+        combinations(&ranks, count, 0, &mut Vec::new(), &mut combos);
+        for combo in combos {
+            let mut reqs = core.to_vec();
+            for rank in combo {
+                match reqs.iter_mut().find(|(req_rank, _)| *req_rank == rank) {
+                    Some((_, size)) => *size += 2,
+                    None => reqs.push((rank, 2)),
+                }
+            }
+            self.emit_filled(&reqs);
+        }
+    }
+
+    fn available(&self, rank: usize) -> u8 {
+        if rank >= BLACK_JOKER {
+            self.naturals[rank]
+        } else {
+            self.naturals[rank] + self.wilds
+        }
+    }
+
+    fn emit_filled(&mut self, reqs: &[(usize, u8)]) {
+        for (taken, _) in self.fill(reqs, &self.naturals, self.wilds) {
+            self.out.insert(taken);
+        }
+    }
+
+    /// Ways to take `size` cards of each requested rank from `naturals`,
+    /// topping non-joker ranks up with at most `wilds` wild cards. Each way is
+    /// the cards taken, wild cards counted on the wild rank, and the number of
+    /// wild cards used.
+    fn fill(&self, reqs: &[(usize, u8)], naturals: &Counts, wilds: u8) -> Vec<(Counts, u8)> {
+        let mut ways = vec![([0u8; 15], 0u8)];
+        for &(rank, size) in reqs {
+            let mut next = Vec::new();
+            for (taken, used) in ways {
+                let spare = match self.wild {
+                    Some(_) if rank < BLACK_JOKER => wilds - used,
+                    _ => 0,
+                };
+                let lowest = size.saturating_sub(spare);
+                for natural in lowest..=size.min(naturals[rank]) {
+                    let mut taken = taken;
+                    taken[rank] += natural;
+                    let substituted = size - natural;
+                    if let Some(wild) = self.wild {
+                        taken[wild] += substituted;
+                    }
+                    next.push((taken, used + substituted));
+                }
+            }
+            ways = next;
+        }
+        ways
+    }
+}
+
+/// Every run of consecutive chain ranks at least `min_len` long.
+fn chains_of(min_len: usize) -> impl Iterator<Item = std::ops::Range<usize>> {
+    let min_len = min_len.max(2);
+    (min_len..=CHAIN_RANKS)
+        .flat_map(move |len| (0..=CHAIN_RANKS - len).map(move |start| start..start + len))
+}
+
+/// Multisets of `size` cards from `pool`, built rank by rank from `from`.
+fn pick(pool: &Counts, size: u8, from: usize, picked: Counts, out: &mut Vec<Counts>) {
+    if size == 0 {
+        out.push(picked);
+        return;
+    }
+    for rank in from..pool.len() {
+        for count in 1..=pool[rank].min(size) {
+            let mut next = picked;
+            next[rank] = count;
+            pick(pool, size - count, rank + 1, next, out);
+        }
+    }
+}
+
+/// Sorted `count`-element selections from `items`, which may hold repeats.
+fn combinations(
+    items: &[usize],
+    count: usize,
+    from: usize,
+    chosen: &mut Vec<usize>,
+    out: &mut BTreeSet<Vec<usize>>,
+) {
+    if chosen.len() == count {
+        out.insert(chosen.clone());
+        return;
+    }
+    for idx in from..items.len() {
+        chosen.push(items[idx]);
+        combinations(items, count, idx + 1, chosen, out);
+        chosen.pop();
+    }
+}
+
+fn add(a: &Counts, b: &Counts) -> Counts {
+    let mut sum = *a;
+    for (slot, extra) in sum.iter_mut().zip(b) {
+        *slot += extra;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{classify_play, shuffled_deck, Suit, Variant};
+    use std::collections::BTreeMap;
+
+    fn card(rank: Rank, suit: Suit) -> Card {
+        Card { rank, suit }
+    }
+
+    fn key(candidate: &Move) -> (Vec<Rank>, usize, Rank, usize) {
+        let mut ranks: Vec<Rank> = candidate.cards.iter().map(|card| card.rank).collect();
+        ranks.sort();
+        let play = &candidate.play;
+        (ranks, kind_order(play.kind), play.main_rank, play.size)
+    }
+
+    /// Classifies every sub-multiset of the hand.
+    fn brute_force(
+        hand: &[Card],
+        last_play: Option<&Play>,
+        rules: &RuleSet,
+        wild: Option<Rank>,
+    ) -> BTreeSet<(Vec<Rank>, usize, Rank, usize)> {
+        let mut sorted = hand.to_vec();
+        sort_hand(&mut sorted);
+        let mut counts = BTreeMap::new();
+        for card in &sorted {
+            *counts.entry(rank_index(card.rank)).or_insert(0u8) += 1;
+        }
+        let ranks: Vec<(usize, u8)> = counts.into_iter().collect();
+        let mut subsets = vec![[0u8; 15]];
+        for (rank, count) in ranks {
+            subsets = subsets
+                .into_iter()
+                .flat_map(|subset| {
+                    (0..=count).map(move |taken| {
+                        let mut next = subset;
+                        next[rank] = taken;
+                        next
+                    })
+                })
+                .collect();
+        }
+        let mut found = BTreeSet::new();
+        for subset in subsets {
+            let cards = take_cards(&sorted, &subset);
+            for play in classify_all_with(&cards, rules, wild) {
+                if last_play.is_none_or(|prev| can_beat_with(prev, &play, rules)) {
+                    found.insert(key(&Move {
+                        cards: cards.clone(),
+                        play,
+                    }));
+                }
+            }
+        }
+        found
+    }
+
+    fn check(hand: &[Card], last_play: Option<&Play>, rules: &RuleSet, wild: Option<Rank>) {
+        let moves = legal_moves_wild(hand, last_play, rules, wild);
+        let keys: Vec<_> = moves.iter().map(key).collect();
+        let unique: BTreeSet<_> = keys.iter().cloned().collect();
+        assert_eq!(unique.len(), keys.len(), "duplicate moves for {hand:?}");
+        assert_eq!(
+            unique,
+            brute_force(hand, last_play, rules, wild),
+            "hand {hand:?} against {last_play:?}"
+        );
+        for candidate in &moves {
+            assert!(candidate.cards.iter().all(|card| hand.contains(card)));
+        }
+    }
+
+    /// Leads taken from other hands of the same deal, so every kind shows up.
+    fn leads(deck: &[Card], rules: &RuleSet) -> Vec<Option<Play>> {
+        let mut leads = vec![None];
+        let others = &deck[12..24];
+        for candidate in legal_moves(others, None, rules).into_iter().step_by(7) {
+            leads.push(Some(candidate.play));
+        }
+        leads
+    }
+
+    #[test]
+    fn matches_brute_force_on_random_hands() {
+        let rules = RuleSet::default();
+        for seed in 0..40 {
+            let deck = shuffled_deck(seed);
+            let hand = &deck[..12];
+            for lead in leads(&deck, &rules) {
+                check(hand, lead.as_ref(), &rules, None);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_with_two_decks() {
+        let rules = RuleSet {
+            variant: Variant::FourPlayer,
+            ..RuleSet::default()
+        };
+        let mut sorted = Variant::FourPlayer.deck().cards();
+        sort_hand(&mut sorted);
+        for seed in 0..12 {
+            let start = seed as usize * 8;
+            let mut hand = sorted[start..start + 10].to_vec();
+            hand.extend_from_slice(&Variant::FourPlayer.shuffled_deck(seed)[..4]);
+            check(&hand, None, &rules, None);
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_with_wild_cards() {
+        let rules = RuleSet {
+            laizi: true,
+            ..RuleSet::default()
+        };
+        for seed in 0..6 {
+            let deck = shuffled_deck(seed);
+            let mut hand = deck[..9].to_vec();
+            let wild = hand[0].rank;
+            let wild = if wild >= Rank::BlackJoker {
+                Rank::Seven
+            } else {
+                wild
+            };
+            hand.push(card(wild, Suit::Spades));
+            for lead in leads(&deck, &rules).into_iter().take(3) {
+                check(&hand, lead.as_ref(), &rules, Some(wild));
+            }
+        }
+    }
+
+    #[test]
+    fn respects_rule_set() {
+        let rules = RuleSet {
+            min_straight: 6,
+            four_with_two: false,
+            ..RuleSet::default()
+        };
+        for seed in 40..50 {
+            let deck = shuffled_deck(seed);
+            check(&deck[..12], None, &rules, None);
+        }
+    }
+
+    #[test]
+    fn includes_kickers_and_bomb_overrides() {
+        let hand = vec![
+            card(Rank::Five, Suit::Clubs),
+            card(Rank::Five, Suit::Diamonds),
+            card(Rank::Five, Suit::Hearts),
+            card(Rank::Five, Suit::Spades),
+            card(Rank::Nine, Suit::Clubs),
+            card(Rank::King, Suit::Hearts),
+            card(Rank::BlackJoker, Suit::Joker),
+            card(Rank::RedJoker, Suit::Joker),
+        ];
+        let lead = classify_play(&[card(Rank::Two, Suit::Clubs)]).unwrap();
+        let moves = legal_moves(&hand, Some(&lead), &RuleSet::default());
+        let kinds: Vec<(PlayKind, Rank)> = moves
+            .iter()
+            .map(|candidate| (candidate.play.kind, candidate.play.main_rank))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (PlayKind::Single, Rank::BlackJoker),
+                (PlayKind::Single, Rank::RedJoker),
+                (PlayKind::Bomb, Rank::Five),
+                (PlayKind::Rocket, Rank::RedJoker),
+            ]
+        );
+
+        let moves = legal_moves(&hand, None, &RuleSet::default());
+        let kickers = moves
+            .iter()
+            .filter(|candidate| candidate.play.kind == PlayKind::TripleSingle)
+            .count();
+        assert_eq!(kickers, 4, "555 with 9, K, BJ or RJ");
+        assert!(moves
+            .iter()
+            .any(|candidate| candidate.play.kind == PlayKind::FourTwoSingle
+                && candidate.cards.contains(&card(Rank::RedJoker, Suit::Joker))));
+        assert_eq!(moves, legal_moves(&hand, None, &RuleSet::default()));
+    }
+}
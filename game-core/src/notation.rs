@@ -0,0 +1,530 @@
+//! Compact text for cards, hands and plays, for tests, logs and puzzle files.
+//!
+//! - Ranks: `3`-`9`, ten as `10`, `T` or `0`, `J Q K A 2`, jokers `BJ`/`RJ`.
+//! - Cards: suit first (`SA`, `H10`) or rank first (`AS`, `0H`, as the card
+//!   assets are named); `X1`/`X2` are the jokers.
+//! - Hands: whitespace or comma separated tokens, each a card or a run of
+//!   suitless ranks, e.g. `"3344TTJQKA2 BJ RJ"` or `"S3 H3 4 4"`.
+//! - Plays: `Kind(body)+kicker+kicker`, e.g. `"Airplane(3-4)+5+6"`,
+//!   `"Triple(K)+99"`, `"Bomb(5x6)"` for six fives, `"Rocket"`.
+
+use crate::movegen::{rank_index, CHAIN_RANKS, RANKS};
+use crate::{sort_hand, Card, Play, PlayKind, Rank, Suit};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotationError {
+    /// A token that is neither a card nor a run of ranks.
+    BadToken(String),
+    /// A play description that does not describe a play.
+    BadPlay(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::BadToken(token) => write!(f, "cannot read {token:?} as cards"),
+            NotationError::BadPlay(text) => write!(f, "cannot read {text:?} as a play"),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+const SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+/// Shortest symbol for a rank: ten is `T`, jokers `BJ`/`RJ`.
+pub fn rank_symbol(rank: Rank) -> &'static str {
+    match rank {
+        Rank::Ten => "T",
+        _ => rank.to_str(),
+    }
+}
+
+pub fn parse_rank(token: &str) -> Option<Rank> {
+    match token.to_ascii_uppercase().as_str() {
+        "T" | "0" | "10" => Some(Rank::Ten),
+        other => Rank::from_str(other),
+    }
+}
+
+/// A single suit-qualified card or joker; `None` for suitless ranks.
+pub fn parse_card(token: &str) -> Option<Card> {
+    let token = token.to_ascii_uppercase();
+    let joker = |rank| Card {
+        rank,
+        suit: Suit::Joker,
+    };
+    match token.as_str() {
+        "BJ" | "X1" => return Some(joker(Rank::BlackJoker)),
+        "RJ" | "X2" => return Some(joker(Rank::RedJoker)),
+        _ => {}
+    }
+    let suited = |suit: &str, rank: &str| {
+        let mut chars = suit.chars();
+        let suit = Suit::from_char(chars.next()?).filter(|_| chars.next().is_none())?;
+        let rank = parse_rank(rank).filter(|rank| !rank.is_joker())?;
+        Some(Card { rank, suit })
+    };
+    let first = token.chars().next()?.len_utf8();
+    let last = token.char_indices().last()?.0;
+    suited(&token[..first], &token[first..]).or_else(|| suited(&token[last..], &token[..last]))
+}
+
+/// Ranks written back to back, e.g. `"3344T10BJ"`.
+pub fn parse_ranks(run: &str) -> Result<Vec<Rank>, NotationError> {
+    let upper = run.to_ascii_uppercase();
+    let mut rest = upper.as_str();
+    let mut ranks = Vec::new();
+    while !rest.is_empty() {
+        let width = if ["10", "BJ", "RJ"].iter().any(|two| rest.starts_with(two)) {
+            2
+        } else {
+            rest.chars().next().map_or(1, char::len_utf8)
+        };
+        let rank =
+            parse_rank(&rest[..width]).ok_or_else(|| NotationError::BadToken(run.to_string()))?;
+        ranks.push(rank);
+        rest = &rest[width..];
+    }
+    Ok(ranks)
+}
+
+/// Reads a hand. Suitless ranks get the first suits not already taken by
+/// cards written out in full, so the result never repeats a card needlessly.
+pub fn parse_hand(text: &str) -> Result<Vec<Card>, NotationError> {
+    let mut tokens = Vec::new();
+    for token in text
+        .split(|ch: char| ch.is_whitespace() || ch == ',')
+        .filter(|token| !token.is_empty())
+    {
+        match parse_card(token) {
+            Some(card) => tokens.push(Ok(card)),
+            None => tokens.push(Err(parse_ranks(token)?)),
+        }
+    }
+    let mut dealer = SuitDealer::default();
+    for card in tokens.iter().filter_map(|token| token.as_ref().ok()) {
+        dealer.take(*card);
+    }
+    let mut cards = Vec::new();
+    for token in tokens {
+        match token {
+            Ok(card) => cards.push(card),
+            Err(ranks) => cards.extend(ranks.into_iter().map(|rank| dealer.deal(rank))),
+        }
+    }
+    Ok(cards)
+}
+
+/// Suitless form, lowest rank first: `"3344TTJQKA2 BJ RJ"`.
+pub fn format_hand(cards: &[Card]) -> String {
+    let mut sorted = cards.to_vec();
+    sort_hand(&mut sorted);
+    let mut tokens = Vec::new();
+    let run: String = sorted
+        .iter()
+        .filter(|card| !card.rank.is_joker())
+        .map(|card| rank_symbol(card.rank))
+        .collect();
+    if !run.is_empty() {
+        tokens.push(run);
+    }
+    tokens.extend(
+        sorted
+            .iter()
+            .filter(|card| card.rank.is_joker())
+            .map(|card| rank_symbol(card.rank).to_string()),
+    );
+    tokens.join(" ")
+}
+
+/// Suit-qualified form using card codes, in the given order: `"S3 H10 BJ"`.
+pub fn format_cards(cards: &[Card]) -> String {
+    cards.iter().map(Card::code).collect::<Vec<_>>().join(" ")
+}
+
+/// Describes a play by the ranks its cards stand for, so wild cards are
+/// written as the rank they replace.
+pub fn format_play(play: &Play, cards: &[Card]) -> String {
+    let shape = Shape::of(play.kind);
+    let mut counts: HashMap<Rank, usize> = HashMap::new();
+    for card in cards {
+        let rank = play
+            .substitutions
+            .iter()
+            .find(|sub| sub.card == *card)
+            .map_or(card.rank, |sub| sub.rank);
+        *counts.entry(rank).or_default() += 1;
+    }
+    let copies = match play.kind {
+        PlayKind::Bomb | PlayKind::SoftBomb | PlayKind::LaiziBomb => play.size,
+        PlayKind::Rocket => play.size / 2,
+        _ => shape.copies,
+    };
+    let width = kicker_width(play.kind);
+    let top = rank_index(play.main_rank);
+    let len = match play.kind {
+        PlayKind::Rocket => 2,
+        // Chains count their links rather than their cards.
+        _ if shape.chain => play.size,
+        _ => 1,
+    };
+    let body: Vec<Rank> = RANKS[top + 1 - len..=top].to_vec();
+    for rank in &body {
+        if let Some(count) = counts.get_mut(rank) {
+            *count = count.saturating_sub(copies);
+        }
+    }
+
+    let mut text = shape.name.to_string();
+    let extra = (copies != shape.copies).then(|| format!("x{copies}"));
+    match play.kind {
+        PlayKind::Rocket => {
+            if let Some(extra) = extra {
+                text.push_str(&format!("({extra})"));
+            }
+        }
+        _ => {
+            let range = if len > 1 {
+                format!("{}-{}", rank_symbol(body[0]), rank_symbol(play.main_rank))
+            } else {
+                rank_symbol(play.main_rank).to_string()
+            };
+            text.push_str(&format!("({range}{})", extra.unwrap_or_default()));
+        }
+    }
+
+    let mut kickers: Vec<(Rank, usize)> = counts.into_iter().filter(|(_, n)| *n > 0).collect();
+    kickers.sort();
+    for (rank, count) in kickers {
+        for _ in 0..count.checked_div(width).unwrap_or(0) {
+            text.push('+');
+            text.push_str(&rank_symbol(rank).repeat(width));
+        }
+    }
+    text
+}
+
+/// Reads a play description back into natural cards and the play they make.
+pub fn parse_play(text: &str) -> Result<(Play, Vec<Card>), NotationError> {
+    let bad = || NotationError::BadPlay(text.to_string());
+    let mut parts = text.trim().split('+');
+    let head = parts.next().ok_or_else(bad)?;
+    let (name, body) = match head.split_once('(') {
+        Some((name, rest)) => (name, Some(rest.strip_suffix(')').ok_or_else(bad)?)),
+        None => (head, None),
+    };
+    let shape = SHAPES
+        .iter()
+        .find(|shape| shape.name == name)
+        .ok_or_else(bad)?;
+
+    let (range, copies) = match body.map(|body| body.split_once('x')) {
+        Some(Some((range, copies))) => (range, copies.parse::<usize>().map_err(|_| bad())?),
+        Some(None) => (body.unwrap_or_default(), shape.copies),
+        None => ("", shape.copies),
+    };
+    let body: Vec<Rank> = if shape.kind == PlayKind::Rocket {
+        if !range.is_empty() {
+            return Err(bad());
+        }
+        vec![Rank::BlackJoker, Rank::RedJoker]
+    } else {
+        let (low, high) = range.split_once('-').unwrap_or((range, range));
+        let low = rank_index(parse_rank(low).ok_or_else(bad)?);
+        let high = rank_index(parse_rank(high).ok_or_else(bad)?);
+        let chain_ok = high > low && high < CHAIN_RANKS;
+        if low > high || (low != high && !shape.chain) || (shape.chain && !chain_ok) {
+            return Err(bad());
+        }
+        RANKS[low..=high].to_vec()
+    };
+    let bomb_like = shape.copies == 4 || shape.kind == PlayKind::Rocket;
+    if copies == 0 || (copies != shape.copies && !bomb_like) {
+        return Err(bad());
+    }
+
+    let mut kickers = Vec::new();
+    for part in parts {
+        let ranks = parse_ranks(part).map_err(|_| bad())?;
+        if ranks.is_empty() || ranks.len() > 2 || ranks.iter().any(|rank| *rank != ranks[0]) {
+            return Err(bad());
+        }
+        kickers.push(ranks);
+    }
+    let kind = match kickers.first().map(Vec::len) {
+        None => shape.kind,
+        Some(width) => {
+            let same_width = kickers.iter().all(|kicker| kicker.len() == width);
+            let expected = match shape.kind {
+                PlayKind::Triple => 1,
+                PlayKind::Airplane => body.len(),
+                PlayKind::Bomb if copies == 4 => 2,
+                _ => 0,
+            };
+            let overlaps = kickers.iter().any(|kicker| body.contains(&kicker[0]));
+            if !same_width || kickers.len() != expected || overlaps {
+                return Err(bad());
+            }
+            match (shape.kind, width) {
+                (PlayKind::Triple, 1) => PlayKind::TripleSingle,
+                (PlayKind::Triple, _) => PlayKind::TriplePair,
+                (PlayKind::Airplane, 1) => PlayKind::AirplaneSingles,
+                (PlayKind::Airplane, _) => PlayKind::AirplanePairs,
+                (_, 1) => PlayKind::FourTwoSingle,
+                _ => PlayKind::FourTwoPair,
+            }
+        }
+    };
+
+    let mut dealer = SuitDealer::default();
+    let mut cards = Vec::new();
+    for rank in &body {
+        cards.extend((0..copies).map(|_| dealer.deal(*rank)));
+    }
+    for rank in kickers.iter().flatten() {
+        cards.push(dealer.deal(*rank));
+    }
+    let main_rank = *body.last().ok_or_else(bad)?;
+    let size = if shape.chain { body.len() } else { cards.len() };
+    Ok((Play::new(kind, main_rank, size), cards))
+}
+
+/// Hands out suits for suitless ranks, least used suit first.
+#[derive(Default)]
+struct SuitDealer {
+    used: HashMap<(Rank, Suit), usize>,
+}
+
+impl SuitDealer {
+    fn take(&mut self, card: Card) {
+        *self.used.entry((card.rank, card.suit)).or_default() += 1;
+    }
+
+    fn deal(&mut self, rank: Rank) -> Card {
+        let suit = if rank.is_joker() {
+            Suit::Joker
+        } else {
+            SUITS
+                .into_iter()
+                .min_by_key(|suit| self.used.get(&(rank, *suit)).copied().unwrap_or(0))
+                .unwrap_or(Suit::Clubs)
+        };
+        let card = Card { rank, suit };
+        self.take(card);
+        card
+    }
+}
+
+/// How a family of play kinds is written.
+struct Shape {
+    name: &'static str,
+    /// The kind written without kickers.
+    kind: PlayKind,
+    /// Cards of each body rank.
+    copies: usize,
+    /// Whether the body may span several ranks.
+    chain: bool,
+}
+
+const SHAPES: [Shape; 10] = [
+    Shape::new("Single", PlayKind::Single, 1, false),
+    Shape::new("Pair", PlayKind::Pair, 2, false),
+    Shape::new("Triple", PlayKind::Triple, 3, false),
+    Shape::new("Straight", PlayKind::Straight, 1, true),
+    Shape::new("DoubleStraight", PlayKind::DoubleStraight, 2, true),
+    Shape::new("Airplane", PlayKind::Airplane, 3, true),
+    Shape::new("Bomb", PlayKind::Bomb, 4, false),
+    Shape::new("SoftBomb", PlayKind::SoftBomb, 4, false),
+    Shape::new("LaiziBomb", PlayKind::LaiziBomb, 4, false),
+    Shape::new("Rocket", PlayKind::Rocket, 1, false),
+];
+
+impl Shape {
+    const fn new(name: &'static str, kind: PlayKind, copies: usize, chain: bool) -> Self {
+        Shape {
+            name,
+            kind,
+            copies,
+            chain,
+        }
+    }
+
+    /// Kinds with kickers are written as their body's kind plus kickers.
+    fn of(kind: PlayKind) -> &'static Shape {
+        let base = match kind {
+            PlayKind::TripleSingle | PlayKind::TriplePair => PlayKind::Triple,
+            PlayKind::AirplaneSingles | PlayKind::AirplanePairs => PlayKind::Airplane,
+            PlayKind::FourTwoSingle | PlayKind::FourTwoPair => PlayKind::Bomb,
+            other => other,
+        };
+        SHAPES
+            .iter()
+            .find(|shape| shape.kind == base)
+            .unwrap_or(&SHAPES[0])
+    }
+}
+
+/// Cards in each kicker of `kind`.
+fn kicker_width(kind: PlayKind) -> usize {
+    match kind {
+        PlayKind::TripleSingle | PlayKind::AirplaneSingles | PlayKind::FourTwoSingle => 1,
+        PlayKind::TriplePair | PlayKind::AirplanePairs | PlayKind::FourTwoPair => 2,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{classify_all, classify_all_with, legal_moves, shuffled_deck, RuleSet};
+
+    fn ranks(cards: &[Card]) -> Vec<Rank> {
+        cards.iter().map(|card| card.rank).collect()
+    }
+
+    #[test]
+    fn reads_suitless_hands() {
+        let hand = parse_hand("3344TTJQKA2 BJ RJ").unwrap();
+        assert_eq!(
+            ranks(&hand),
+            vec![
+                Rank::Three,
+                Rank::Three,
+                Rank::Four,
+                Rank::Four,
+                Rank::Ten,
+                Rank::Ten,
+                Rank::Jack,
+                Rank::Queen,
+                Rank::King,
+                Rank::Ace,
+                Rank::Two,
+                Rank::BlackJoker,
+                Rank::RedJoker,
+            ]
+        );
+        assert_ne!(hand[0], hand[1], "repeated ranks get different suits");
+        assert_eq!(format_hand(&hand), "3344TTJQKA2 BJ RJ");
+        assert_eq!(parse_hand("10 0 t").unwrap(), parse_hand("TTT").unwrap());
+        assert_eq!(
+            ranks(&parse_hand("Q7").unwrap()),
+            vec![Rank::Queen, Rank::Seven],
+            "text order is kept"
+        );
+        assert!(parse_hand("3 1").is_err());
+        assert!(parse_hand("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reads_suited_cards_either_way_round() {
+        let spade_ace = Card {
+            rank: Rank::Ace,
+            suit: Suit::Spades,
+        };
+        let heart_ten = Card {
+            rank: Rank::Ten,
+            suit: Suit::Hearts,
+        };
+        for code in ["SA", "AS", "sa"] {
+            assert_eq!(parse_card(code), Some(spade_ace), "{code}");
+        }
+        for code in ["H10", "HT", "H0", "10H", "TH", "0H"] {
+            assert_eq!(parse_card(code), Some(heart_ten), "{code}");
+        }
+        assert_eq!(parse_card("X2").map(|card| card.rank), Some(Rank::RedJoker));
+        assert_eq!(parse_card("A"), None);
+        assert_eq!(parse_card("SBJ"), None);
+        assert_eq!(Card::from_code("0H"), Some(heart_ten));
+        for code in ["♠A", "é3", "A♠", "é", "3é3"] {
+            assert_eq!(Card::from_code(code), None, "{code}");
+        }
+        assert!(parse_hand("♠A é3").is_err());
+    }
+
+    #[test]
+    fn suitless_ranks_avoid_written_cards() {
+        let hand = parse_hand("3, C3 S3").unwrap();
+        assert_eq!(hand[0].suit, Suit::Diamonds);
+        assert_eq!(format_cards(&hand), "D3 C3 S3");
+        let deck = shuffled_deck(3);
+        assert_eq!(parse_hand(&format_cards(&deck)).unwrap(), deck);
+    }
+
+    #[test]
+    fn play_descriptions_round_trip() {
+        let cases = [
+            ("Single(T)", PlayKind::Single),
+            ("Pair(2)", PlayKind::Pair),
+            ("Triple(K)", PlayKind::Triple),
+            ("Triple(K)+3", PlayKind::TripleSingle),
+            ("Triple(K)+99", PlayKind::TriplePair),
+            ("Straight(9-A)", PlayKind::Straight),
+            ("DoubleStraight(3-5)", PlayKind::DoubleStraight),
+            ("Airplane(3-4)", PlayKind::Airplane),
+            ("Airplane(3-4)+5+6", PlayKind::AirplaneSingles),
+            ("Airplane(3-4)+5+5", PlayKind::AirplaneSingles),
+            ("Airplane(J-K)+55+66+RJ", PlayKind::AirplanePairs),
+            ("Bomb(7)", PlayKind::Bomb),
+            ("Bomb(7x6)", PlayKind::Bomb),
+            ("Bomb(7)+3+BJ", PlayKind::FourTwoSingle),
+            ("Bomb(7)+33+33", PlayKind::FourTwoPair),
+            ("Rocket", PlayKind::Rocket),
+            ("Rocket(x2)", PlayKind::Rocket),
+        ];
+        for (text, kind) in cases {
+            let parsed = parse_play(text);
+            if text.ends_with("+RJ") {
+                assert!(parsed.is_err(), "{text}: wings must be the same width");
+                continue;
+            }
+            let (play, cards) = parsed.unwrap_or_else(|err| panic!("{text}: {err}"));
+            assert_eq!(play.kind, kind, "{text}");
+            assert_eq!(format_play(&play, &cards), text);
+        }
+        assert_eq!(
+            parse_play("Airplane(3-4)+5+6").unwrap().0,
+            Play::new(PlayKind::AirplaneSingles, Rank::Four, 2)
+        );
+        for text in [
+            "Straight(K)",
+            "Straight(J-2)",
+            "Pair(3-4)",
+            "Airplane(3-4)+5",
+            "Triple(K)+K",
+            "Triple(3)+4+5",
+            "Pair(3x3)",
+            "Plane(3-4)",
+            "Single(3",
+        ] {
+            assert!(parse_play(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn wild_cards_are_written_as_what_they_replace() {
+        let cards = parse_hand("777 5").unwrap();
+        let play = classify_all_with(&cards, &RuleSet::default(), Some(Rank::Five))
+            .into_iter()
+            .find(|play| play.kind == PlayKind::SoftBomb)
+            .unwrap();
+        assert_eq!(format_play(&play, &cards), "SoftBomb(7)");
+    }
+
+    #[test]
+    fn describes_every_legal_lead() {
+        let rules = RuleSet::default();
+        for seed in 0..5 {
+            let deck = shuffled_deck(seed);
+            for candidate in legal_moves(&deck[..20], None, &rules) {
+                let text = format_play(&candidate.play, &candidate.cards);
+                let (play, cards) = parse_play(&text).unwrap_or_else(|err| panic!("{err}"));
+                assert_eq!(format_play(&play, &cards), text);
+                assert_eq!(format_hand(&cards), format_hand(&candidate.cards), "{text}");
+                assert!(classify_all(&cards).contains(&play), "{text}");
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{Card, Settlement};
+use serde::{Deserialize, Serialize};
+
+/// The two teams at the table: the landlord alone against every farmer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Landlord,
+    Farmers,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerResult {
+    pub id: u64,
+    pub side: Side,
+    pub won: bool,
+    /// Cards left in hand when the game ended, revealed to everyone.
+    pub remaining: Vec<Card>,
+    pub plays: u32,
+    pub delta: i64,
+}
+
+/// Outcome of a finished game for the whole table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub winning_side: Side,
+    /// Seat that played its last card.
+    pub first_out: usize,
+    pub players: Vec<PlayerResult>,
+    pub settlement: Settlement,
+}
+
+impl GameResult {
+    /// Seats on the winning side.
+    pub fn winners(&self) -> Vec<usize> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| player.won)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Number of plays made by everyone on `side`.
+    pub fn plays_by(&self, side: Side) -> u32 {
+        self.players
+            .iter()
+            .filter(|player| player.side == side)
+            .map(|player| player.plays)
+            .sum()
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{BiddingMode, PlayKind, ScoringRules, Variant};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Set of play kinds, stored as one bit per `PlayKind::ALL` entry. Serialized
+/// as a list of kind names.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Vec<PlayKind>", into = "Vec<PlayKind>")]
+pub struct KindSet(u32);
+
+impl KindSet {
+    pub const ALL: KindSet = KindSet((1 << PlayKind::ALL.len()) - 1);
+
+    pub fn contains(self, kind: PlayKind) -> bool {
+        self.0 & kind_bit(kind) != 0
+    }
+
+    pub fn with(self, kind: PlayKind) -> Self {
+        KindSet(self.0 | kind_bit(kind))
+    }
+
+    pub fn without(self, kind: PlayKind) -> Self {
+        KindSet(self.0 & !kind_bit(kind))
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = PlayKind> {
+        PlayKind::ALL
+            .into_iter()
+            .filter(move |kind| self.contains(*kind))
+    }
+}
+
+fn kind_bit(kind: PlayKind) -> u32 {
+    let idx = PlayKind::ALL
+        .iter()
+        .position(|candidate| *candidate == kind)
+        .unwrap_or_default();
+    1 << idx
+}
+
+impl Default for KindSet {
+    fn default() -> Self {
+        KindSet::ALL
+    }
+}
+
+impl fmt::Debug for KindSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl From<Vec<PlayKind>> for KindSet {
+    fn from(kinds: Vec<PlayKind>) -> Self {
+        kinds
+            .into_iter()
+            .fold(KindSet(0), |set, kind| set.with(kind))
+    }
+}
+
+impl From<KindSet> for Vec<PlayKind> {
+    fn from(set: KindSet) -> Self {
+        set.iter().collect()
+    }
+}
+
+/// Highest `base_score` a table may use.
+pub const MAX_BASE_SCORE: u32 = 10_000;
+
+/// Why a `RuleSet` can't be played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    /// Without singles a leader can be left with no legal play.
+    SingleDisabled,
+    /// A chain minimum outside 2 to 12 ranks.
+    ChainLength { kind: PlayKind, min: usize },
+    /// `base_score` outside 1 to `MAX_BASE_SCORE`.
+    BaseScore(u32),
+    /// A multiplier cap of zero.
+    MultiplierCap(u32),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::SingleDisabled => write!(f, "singles must stay enabled"),
+            RuleError::ChainLength { kind, min } => {
+                write!(f, "{kind:?} minimum of {min} is not between 2 and 12")
+            }
+            RuleError::BaseScore(score) => {
+                write!(
+                    f,
+                    "base score {score} is not between 1 and {MAX_BASE_SCORE}"
+                )
+            }
+            RuleError::MultiplierCap(cap) => write!(f, "multiplier cap {cap} is below 1"),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// Everything that can differ between tables: seats and deck, bidding,
+/// scoring and which plays are legal. Missing fields deserialize to the
+/// classic rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    pub variant: Variant,
+    pub bidding: BiddingMode,
+    /// Base score and multiplier cap.
+    pub scoring: ScoringRules,
+    /// 癞子 mode: a rank drawn after the deal is wild.
+    pub laizi: bool,
+    pub enabled_kinds: KindSet,
+    /// Fewest cards in a `Straight`.
+    pub min_straight: usize,
+    /// Fewest pairs in a `DoubleStraight`.
+    pub min_double_straight: usize,
+    /// Fewest triples in an airplane, with or without wings.
+    pub min_airplane: usize,
+    /// Whether three of a kind may be played without a kicker.
+    pub triple_alone: bool,
+    /// Whether four of a kind may carry two singles or two pairs.
+    pub four_with_two: bool,
+    /// Whether a 加倍 round follows the bidding.
+    pub doubling: bool,
+    /// Whether players may play with their hand face up (明牌).
+    pub show_hand: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            variant: Variant::Classic,
+            bidding: BiddingMode::Points,
+            scoring: ScoringRules::default(),
+            laizi: false,
+            enabled_kinds: KindSet::ALL,
+            min_straight: 5,
+            min_double_straight: 3,
+            min_airplane: 2,
+            triple_alone: true,
+            four_with_two: true,
+            doubling: false,
+            show_hand: false,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Whether a classified play of `kind` may be played under these rules.
+    pub fn allows(&self, kind: PlayKind) -> bool {
+        if !self.enabled_kinds.contains(kind) {
+            return false;
+        }
+        match kind {
+            PlayKind::Triple => self.triple_alone,
+            PlayKind::FourTwoSingle | PlayKind::FourTwoPair => self.four_with_two,
+            _ => true,
+        }
+    }
+
+    /// Checks that a game under these rules can always be played out:
+    /// every hand has a lead, chains fit between 3 and A, and stakes stay
+    /// in range.
+    pub fn validate(&self) -> Result<(), RuleError> {
+        if !self.allows(PlayKind::Single) {
+            return Err(RuleError::SingleDisabled);
+        }
+        for (kind, min) in [
+            (PlayKind::Straight, self.min_straight),
+            (PlayKind::DoubleStraight, self.min_double_straight),
+            (PlayKind::Airplane, self.min_airplane),
+        ] {
+            if !(2..=12).contains(&min) {
+                return Err(RuleError::ChainLength { kind, min });
+            }
+        }
+        if !(1..=MAX_BASE_SCORE).contains(&self.scoring.base_score) {
+            return Err(RuleError::BaseScore(self.scoring.base_score));
+        }
+        match self.scoring.multiplier_cap {
+            Some(0) => Err(RuleError::MultiplierCap(0)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Named rule sets a room creator can pick instead of spelling out rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RulePreset {
+    /// Three players, 叫分, every play kind.
+    #[default]
+    Classic,
+    /// 欢乐斗地主: 抢地主, 加倍 and 明牌, with the multiplier capped at 64.
+    Happy,
+    /// Classic rules with a wild rank.
+    Laizi,
+    /// No four-with-two and no bare triples.
+    Strict,
+    FourPlayer,
+    TwoPlayer,
+}
+
+impl RulePreset {
+    pub const ALL: [RulePreset; 6] = [
+        RulePreset::Classic,
+        RulePreset::Happy,
+        RulePreset::Laizi,
+        RulePreset::Strict,
+        RulePreset::FourPlayer,
+        RulePreset::TwoPlayer,
+    ];
+
+    pub fn rules(self) -> RuleSet {
+        let classic = RuleSet::default();
+        match self {
+            RulePreset::Classic => classic,
+            RulePreset::Happy => RuleSet {
+                bidding: BiddingMode::Rob,
+                doubling: true,
+                show_hand: true,
+                scoring: ScoringRules {
+                    multiplier_cap: Some(64),
+                    ..ScoringRules::default()
+                },
+                ..classic
+            },
+            RulePreset::Laizi => RuleSet {
+                laizi: true,
+                ..classic
+            },
+            RulePreset::Strict => RuleSet {
+                triple_alone: false,
+                four_with_two: false,
+                ..classic
+            },
+            RulePreset::FourPlayer => RuleSet {
+                variant: Variant::FourPlayer,
+                ..classic
+            },
+            RulePreset::TwoPlayer => RuleSet {
+                variant: Variant::TwoPlayer,
+                ..classic
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_set_round_trips_through_names() {
+        let set = KindSet::ALL
+            .without(PlayKind::FourTwoPair)
+            .without(PlayKind::Rocket);
+        assert!(!set.contains(PlayKind::Rocket));
+        assert!(set.contains(PlayKind::Bomb));
+        let json = serde_json::to_string(&set).unwrap();
+        assert!(!json.contains("Rocket"));
+        let back: KindSet = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, set);
+    }
+
+    #[test]
+    fn missing_fields_default_to_classic() {
+        let rules: RuleSet = serde_json::from_str(r#"{"min_straight":6}"#).unwrap();
+        assert_eq!(
+            rules,
+            RuleSet {
+                min_straight: 6,
+                ..RuleSet::default()
+            }
+        );
+    }
+
+    #[test]
+    fn presets_validate_and_broken_rules_do_not() {
+        for preset in RulePreset::ALL {
+            assert_eq!(preset.rules().validate(), Ok(()));
+        }
+        let rules: RuleSet = serde_json::from_str(r#"{"enabled_kinds":[]}"#).unwrap();
+        assert_eq!(rules.validate(), Err(RuleError::SingleDisabled));
+        let rules = RuleSet {
+            min_airplane: 0,
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            rules.validate(),
+            Err(RuleError::ChainLength {
+                kind: PlayKind::Airplane,
+                min: 0
+            })
+        );
+        let rules = RuleSet {
+            scoring: ScoringRules {
+                base_score: 0,
+                multiplier_cap: None,
+            },
+            ..RuleSet::default()
+        };
+        assert_eq!(rules.validate(), Err(RuleError::BaseScore(0)));
+    }
+
+    #[test]
+    fn strict_preset_rejects_four_with_two_and_bare_triples() {
+        let rules = RulePreset::Strict.rules();
+        assert!(!rules.allows(PlayKind::Triple));
+        assert!(!rules.allows(PlayKind::FourTwoSingle));
+        assert!(rules.allows(PlayKind::TripleSingle));
+        assert!(RulePreset::Classic.rules().allows(PlayKind::FourTwoPair));
+    }
+}
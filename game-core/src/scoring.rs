@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringRules {
+    pub base_score: u32,
+    /// Upper bound for the combined multiplier (bidding, bombs, spring).
+    pub multiplier_cap: Option<u32>,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            base_score: 1,
+            multiplier_cap: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Spring {
+    None,
+    /// 春天: the landlord won and neither farmer played a card.
+    Spring,
+    /// 反春: the farmers won and the landlord only made the opening play.
+    AntiSpring,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settlement {
+    pub landlord: usize,
+    pub landlord_won: bool,
+    pub base_score: u32,
+    pub bid: u8,
+    pub bombs: u32,
+    pub spring: Spring,
+    /// Combined multiplier (bidding, 明牌, bombs, spring) after the cap has
+    /// been applied, before 加倍.
+    pub multiplier: u32,
+    /// 加倍 factor per seat.
+    pub doubles: Vec<u32>,
+    /// Score change per seat; the landlord settles against each farmer.
+    pub deltas: Vec<i64>,
+}
+
+/// Everything about a finished game that affects the score.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScoreInput {
+    pub landlord: usize,
+    pub winner: usize,
+    pub bid: u8,
+    pub bid_multiplier: u32,
+    pub bombs: u32,
+    /// 明牌 factor; 0 and 1 both mean nobody showed.
+    pub show_hand: u32,
+    pub plays: Vec<u32>,
+    /// 加倍 factor per seat; missing seats count as 1.
+    pub doubles: Vec<u32>,
+}
+
+pub fn spring_for(input: &ScoreInput) -> Spring {
+    let landlord_won = input.winner == input.landlord;
+    let farmer_plays: u32 = input
+        .plays
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != input.landlord)
+        .map(|(_, plays)| *plays)
+        .sum();
+    if landlord_won && farmer_plays == 0 {
+        Spring::Spring
+    } else if !landlord_won && input.plays[input.landlord] == 1 {
+        Spring::AntiSpring
+    } else {
+        Spring::None
+    }
+}
+
+pub fn capped_multiplier(raw: u64, rules: &ScoringRules) -> u32 {
+    let capped = match rules.multiplier_cap {
+        Some(cap) => raw.min(u64::from(cap)),
+        None => raw,
+    };
+    u32::try_from(capped).unwrap_or(u32::MAX)
+}
+
+pub fn settle(input: &ScoreInput, rules: &ScoringRules) -> Settlement {
+    let spring = spring_for(input);
+    let spring_factor = if spring == Spring::None { 1 } else { 2 };
+    let raw = u64::from(input.bid_multiplier.max(1))
+        .saturating_mul(u64::from(input.show_hand.max(1)))
+        .saturating_mul(1u64.checked_shl(input.bombs).unwrap_or(u64::MAX))
+        .saturating_mul(spring_factor);
+    let multiplier = capped_multiplier(raw, rules);
+    let landlord_won = input.winner == input.landlord;
+    let doubles: Vec<u32> = (0..input.plays.len())
+        .map(|idx| input.doubles.get(idx).copied().unwrap_or(1).max(1))
+        .collect();
+    let mut deltas = vec![0i64; input.plays.len()];
+    for (idx, delta) in deltas.iter_mut().enumerate() {
+        if idx == input.landlord {
+            continue;
+        }
+        let doubled = raw
+            .saturating_mul(u64::from(doubles[idx]))
+            .saturating_mul(u64::from(doubles[input.landlord]));
+        let stake = i64::from(rules.base_score)
+            * i64::from(input.bid.max(1))
+            * i64::from(capped_multiplier(doubled, rules));
+        *delta = if landlord_won { -stake } else { stake };
+    }
+    deltas[input.landlord] = -deltas.iter().sum::<i64>();
+    Settlement {
+        landlord: input.landlord,
+        landlord_won,
+        base_score: rules.base_score,
+        bid: input.bid,
+        bombs: input.bombs,
+        spring,
+        multiplier,
+        doubles,
+        deltas,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(winner: usize, bombs: u32, plays: [u32; 3]) -> ScoreInput {
+        ScoreInput {
+            landlord: 0,
+            winner,
+            bid: 2,
+            bid_multiplier: 1,
+            bombs,
+            show_hand: 1,
+            plays: plays.to_vec(),
+            doubles: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn landlord_win_pays_double_stake() {
+        let settlement = settle(&input(0, 0, [8, 3, 2]), &ScoringRules::default());
+        assert_eq!(settlement.spring, Spring::None);
+        assert_eq!(settlement.multiplier, 1);
+        assert_eq!(settlement.deltas, [4, -2, -2]);
+    }
+
+    #[test]
+    fn farmer_win_is_shared_by_both_farmers() {
+        let settlement = settle(&input(2, 1, [5, 4, 6]), &ScoringRules::default());
+        assert!(!settlement.landlord_won);
+        assert_eq!(settlement.multiplier, 2);
+        assert_eq!(settlement.deltas, [-8, 4, 4]);
+    }
+
+    #[test]
+    fn spring_and_anti_spring_double() {
+        let spring = settle(&input(0, 0, [6, 0, 0]), &ScoringRules::default());
+        assert_eq!(spring.spring, Spring::Spring);
+        assert_eq!(spring.deltas, [8, -4, -4]);
+
+        let anti = settle(&input(1, 0, [1, 5, 3]), &ScoringRules::default());
+        assert_eq!(anti.spring, Spring::AntiSpring);
+        assert_eq!(anti.deltas, [-8, 4, 4]);
+    }
+
+    #[test]
+    fn multiplier_cap_limits_stake() {
+        let rules = ScoringRules {
+            base_score: 10,
+            multiplier_cap: Some(4),
+        };
+        let mut bombs = input(0, 3, [7, 0, 0]);
+        bombs.bid_multiplier = 2;
+        let settlement = settle(&bombs, &rules);
+        assert_eq!(settlement.multiplier, 4);
+        assert_eq!(settlement.deltas, [160, -80, -80]);
+    }
+
+    #[test]
+    fn landlord_settles_with_every_farmer() {
+        let four = ScoreInput {
+            plays: vec![4, 2, 3, 1],
+            ..input(0, 0, [0; 3])
+        };
+        let settlement = settle(&four, &ScoringRules::default());
+        assert_eq!(settlement.deltas, [6, -2, -2, -2]);
+
+        let two = ScoreInput {
+            winner: 1,
+            plays: vec![3, 4],
+            ..input(0, 0, [0; 3])
+        };
+        assert_eq!(settle(&two, &ScoringRules::default()).deltas, [-2, 2]);
+    }
+
+    #[test]
+    fn show_hand_multiplies_table_stake() {
+        let shown = ScoreInput {
+            show_hand: 4,
+            ..input(0, 1, [8, 3, 2])
+        };
+        let settlement = settle(&shown, &ScoringRules::default());
+        assert_eq!(settlement.multiplier, 8);
+        assert_eq!(settlement.deltas, [32, -16, -16]);
+    }
+
+    #[test]
+    fn doubling_scales_each_farmer_stake() {
+        let doubled = ScoreInput {
+            doubles: vec![2, 4, 1],
+            ..input(0, 0, [8, 3, 2])
+        };
+        let settlement = settle(&doubled, &ScoringRules::default());
+        assert_eq!(settlement.multiplier, 1);
+        assert_eq!(settlement.doubles, [2, 4, 1]);
+        assert_eq!(settlement.deltas, [20, -16, -4]);
+
+        let capped = settle(
+            &doubled,
+            &ScoringRules {
+                base_score: 1,
+                multiplier_cap: Some(4),
+            },
+        );
+        assert_eq!(capped.deltas, [12, -8, -4]);
+    }
+}
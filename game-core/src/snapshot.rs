@@ -0,0 +1,256 @@
+use crate::GameState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Layout written by `GameState::to_snapshot`. Bump it when a change to the
+/// state needs more than a defaulted field, and add the upgrade step to
+/// `MIGRATIONS`.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Entry `n` turns a version `n` snapshot into version `n + 1`.
+const MIGRATIONS: [fn(Value) -> Result<Value, SnapshotError>; SNAPSHOT_VERSION as usize] =
+    [wrap_bare_state, add_opening];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// Not JSON, or not shaped like the version it claims to be.
+    Malformed(String),
+    /// Written by a newer build than this one.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Malformed(reason) => write!(f, "malformed snapshot: {reason}"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "snapshot version {version} is newer than {SNAPSHOT_VERSION}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Stored form of a game: cards as codes (`"H10"`, `"BJ"`), play kinds and
+/// ranks by name, and the layout version alongside.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    state: GameState,
+}
+
+impl GameState {
+    /// The state in the current snapshot layout.
+    pub fn to_snapshot(&self) -> Value {
+        serde_json::json!({
+            "version": SNAPSHOT_VERSION,
+            "state": self,
+        })
+    }
+
+    pub fn to_snapshot_json(&self) -> String {
+        self.to_snapshot().to_string()
+    }
+
+    /// Loads a snapshot of any version this build knows, migrating it first.
+    pub fn from_snapshot(snapshot: Value) -> Result<GameState, SnapshotError> {
+        let snapshot: Snapshot = serde_json::from_value(migrate(snapshot)?)
+            .map_err(|err| SnapshotError::Malformed(err.to_string()))?;
+        Ok(snapshot.state)
+    }
+
+    pub fn from_snapshot_json(json: &str) -> Result<GameState, SnapshotError> {
+        let snapshot =
+            serde_json::from_str(json).map_err(|err| SnapshotError::Malformed(err.to_string()))?;
+        Self::from_snapshot(snapshot)
+    }
+}
+
+/// Upgrades a stored snapshot to `SNAPSHOT_VERSION` without loading it.
+pub fn migrate(mut snapshot: Value) -> Result<Value, SnapshotError> {
+    let mut version = version_of(&snapshot)?;
+    if version > SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    while version < SNAPSHOT_VERSION {
+        snapshot = MIGRATIONS[version as usize](snapshot)?;
+        version += 1;
+    }
+    Ok(snapshot)
+}
+
+/// Version 0 is a `GameState` serialized directly, with no envelope.
+fn version_of(snapshot: &Value) -> Result<u32, SnapshotError> {
+    let Some(object) = snapshot.as_object() else {
+        return Err(SnapshotError::Malformed("expected an object".to_string()));
+    };
+    match object.get("version") {
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| SnapshotError::Malformed(format!("bad version {version}"))),
+        None if object.contains_key("players") => Ok(0),
+        None => Err(SnapshotError::Malformed("missing version".to_string())),
+    }
+}
+
+fn wrap_bare_state(state: Value) -> Result<Value, SnapshotError> {
+    Ok(serde_json::json!({ "version": 1, "state": state }))
+}
+
+/// Version 1 had no event log. The opening deal is marked unknown and the
+/// log starts empty, so replaying or undoing the game fails with
+/// `IncompleteLog` instead of rebuilding some other deal.
+fn add_opening(mut snapshot: Value) -> Result<Value, SnapshotError> {
+    let state = snapshot
+        .get_mut("state")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| SnapshotError::Malformed("missing state".to_string()))?;
+    state.entry("opening").or_insert(Value::Null);
+    state
+        .entry("events")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    snapshot["version"] = 2.into();
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Card, GameError, PlayKind, PlayOutcome, RuleSet, MAX_BID};
+
+    fn game_in_play(rules: RuleSet) -> GameState {
+        let mut state = GameState::with_rules(&[1, 2, 3], 11, rules);
+        let bidder = state.turn;
+        state.bid(bidder, MAX_BID).unwrap();
+        for _ in 0..4 {
+            let Some(candidate) = state.legal_moves(state.turn).into_iter().next() else {
+                break;
+            };
+            let seat = state.turn;
+            if state.last_play.is_some() && state.last_player != Some(seat) {
+                state.pass(seat).unwrap();
+            } else {
+                state
+                    .apply_play_as(seat, candidate.cards, Some(candidate.play.kind))
+                    .unwrap();
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn game_round_trips_through_snapshot() {
+        for rules in [
+            RuleSet::default(),
+            RuleSet {
+                laizi: true,
+                ..RuleSet::default()
+            },
+        ] {
+            let state = game_in_play(rules);
+            assert!(state.last_play.is_some());
+            let json = state.to_snapshot_json();
+            assert_eq!(GameState::from_snapshot_json(&json), Ok(state));
+        }
+    }
+
+    #[test]
+    fn play_outcome_round_trips() {
+        let mut state = game_in_play(RuleSet::default());
+        let seat = state.last_player.unwrap();
+        while state.turn != seat {
+            state.pass(state.turn).unwrap();
+        }
+        let candidate = state.legal_moves(seat).pop().unwrap();
+        let outcome = state.apply_play(seat, candidate.cards).unwrap();
+        let json = serde_json::to_string(&outcome).unwrap();
+        assert_eq!(serde_json::from_str::<PlayOutcome>(&json).unwrap(), outcome);
+    }
+
+    #[test]
+    fn layout_uses_card_codes_and_kind_names() {
+        let state = game_in_play(RuleSet::default());
+        let snapshot = state.to_snapshot();
+        assert_eq!(snapshot["version"], SNAPSHOT_VERSION);
+        let hand = &snapshot["state"]["players"][0]["hand"];
+        let first = state.players[0].hand[0];
+        assert_eq!(hand[0], first.code());
+        assert_eq!(
+            snapshot["state"]["last_play"]["kind"],
+            serde_json::to_value(state.last_play.as_ref().unwrap().kind).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(PlayKind::AirplanePairs).unwrap(),
+            "AirplanePairs"
+        );
+        assert_eq!(
+            serde_json::from_str::<Card>("\"D10\"").unwrap(),
+            Card::from_code("D10").unwrap()
+        );
+        assert!(serde_json::from_str::<Card>("\"X3\"").is_err());
+    }
+
+    #[test]
+    fn bare_state_migrates_to_current_version() {
+        let state = game_in_play(RuleSet::default());
+        let bare = serde_json::to_value(&state).unwrap();
+        let migrated = migrate(bare.clone()).unwrap();
+        assert_eq!(migrated["version"], SNAPSHOT_VERSION);
+        assert_eq!(GameState::from_snapshot(bare), Ok(state));
+    }
+
+    /// `state` as version 1 wrote it, before the event log.
+    fn version_one(state: &GameState) -> Value {
+        let mut snapshot = state.to_snapshot();
+        snapshot["version"] = 1.into();
+        let fields = snapshot["state"].as_object_mut().unwrap();
+        fields.remove("opening");
+        fields.remove("events");
+        snapshot
+    }
+
+    #[test]
+    fn version_one_games_load_without_a_log() {
+        let state = game_in_play(RuleSet::default());
+        let mut loaded = GameState::from_snapshot(version_one(&state)).unwrap();
+        assert!(loaded.events.is_empty());
+        assert_eq!(loaded.opening, None);
+        assert_eq!(loaded.players, state.players);
+        assert_eq!(
+            loaded.replayed().map_err(|err| err.error),
+            Err(GameError::IncompleteLog)
+        );
+        assert_eq!(loaded.undo(), Err(GameError::NothingToUndo));
+        assert_eq!(loaded.rewind_to(0), Err(GameError::IncompleteLog));
+        assert_eq!(loaded.players, state.players);
+    }
+
+    #[test]
+    fn rejects_newer_and_malformed_snapshots() {
+        let state = game_in_play(RuleSet::default());
+        let mut snapshot = state.to_snapshot();
+        snapshot["version"] = (SNAPSHOT_VERSION + 1).into();
+        assert_eq!(
+            GameState::from_snapshot(snapshot),
+            Err(SnapshotError::UnsupportedVersion(SNAPSHOT_VERSION + 1))
+        );
+        assert!(matches!(
+            GameState::from_snapshot_json("[1, 2]"),
+            Err(SnapshotError::Malformed(_))
+        ));
+        for code in ["Z9", "♠A", "é3"] {
+            let mut snapshot = state.to_snapshot();
+            snapshot["state"]["players"][0]["hand"][0] = code.into();
+            assert!(matches!(
+                GameState::from_snapshot(snapshot),
+                Err(SnapshotError::Malformed(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Double-dummy endgame solver: with every hand face up, decides whether the
+//! side to move can force one of its own seats to go out first.
+//!
+//! The search runs over rank counts rather than cards, with win/loss
+//! alpha-beta (a side stops at its first winning move) and a transposition
+//! table keyed on the hands, the play to beat and the seat to move. Most
+//! endgames of ten cards a hand settle within a few thousand positions; the
+//! hardest take a few million.
+
+use crate::hand::RankCounts;
+use crate::movegen::kind_order;
+use crate::{
+    can_beat_with, classify_all_with, legal_moves_wild, Action, GamePhase, GameState, Play, Rank,
+    RuleSet, Side,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Positions `solve` searches before giving up.
+pub const DEFAULT_NODE_LIMIT: u64 = 5_000_000;
+
+/// Most seats any variant has.
+const MAX_SEATS: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /// Only games in the playing phase can be solved.
+    NotPlaying,
+    /// The search gave up after this many positions.
+    NodeLimit(u64),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NotPlaying => write!(f, "the game is not in its playing phase"),
+            SolveError::NodeLimit(nodes) => write!(f, "gave up after {nodes} positions"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    /// Side of the seat to move.
+    pub side: Side,
+    /// Whether that side wins against any defence.
+    pub wins: bool,
+    /// Seat and action for every move to the end of the game with both sides
+    /// playing their best. A side that cannot win shows one of its defences.
+    pub line: Vec<(usize, Action)>,
+    /// Positions searched.
+    pub nodes: u64,
+}
+
+/// Solves the position with `DEFAULT_NODE_LIMIT`.
+pub fn solve(state: &GameState) -> Result<Solution, SolveError> {
+    solve_within(state, DEFAULT_NODE_LIMIT)
+}
+
+/// Solves the position, giving up after `node_limit` positions.
+pub fn solve_within(state: &GameState, node_limit: u64) -> Result<Solution, SolveError> {
+    let landlord = state.landlord.ok_or(SolveError::NotPlaying)?;
+    if state.phase != GamePhase::Playing || state.players.len() > MAX_SEATS {
+        return Err(SolveError::NotPlaying);
+    }
+    let mut search = Search {
+        rules: &state.rules,
+        wild: state.wild_rank,
+        landlord,
+        table: HashMap::default(),
+        nodes: 0,
+        node_limit,
+    };
+    let root = Position::of(state);
+    let landlord_wins = search.landlord_wins(&root)?;
+
+    let mut line = Vec::new();
+    let mut game = state.clone();
+    let mut position = root;
+    while position.winner.is_none() {
+        let seat = position.turn;
+        let (step, next) = search.best_step(&position, landlord_wins)?;
+        let action = match step {
+            Step::Pass => Action::Pass,
+            Step::Play(taken, play) => Action::Play {
+                cards: taken
+                    .take_from(&game.players[seat].hand)
+                    .expect("solver plays come from the hand"),
+                kind: Some(play.kind),
+            },
+        };
+        game.apply(seat, action.clone())
+            .expect("solver moves are legal");
+        line.push((seat, action));
+        position = next;
+    }
+
+    let side = if state.turn == landlord {
+        Side::Landlord
+    } else {
+        Side::Farmers
+    };
+    Ok(Solution {
+        side,
+        wins: landlord_wins == (side == Side::Landlord),
+        line,
+        nodes: search.nodes,
+    })
+}
+
+#[derive(Clone, Debug)]
+enum Step {
+    Play(RankCounts, Play),
+    Pass,
+}
+
+#[derive(Clone, Debug)]
+struct Position {
+    hands: [RankCounts; MAX_SEATS],
+    seats: usize,
+    /// Play to beat and the seat that made it.
+    last: Option<(Play, usize)>,
+    turn: usize,
+    passes: u8,
+    winner: Option<usize>,
+}
+
+/// `Position` without the parts that cannot change the outcome.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    hands: [RankCounts; MAX_SEATS],
+    last: Option<(usize, Rank, usize, usize)>,
+    turn: usize,
+    passes: u8,
+}
+
+impl Position {
+    fn of(state: &GameState) -> Self {
+        let mut hands = [RankCounts::EMPTY; MAX_SEATS];
+        for (hand, player) in hands.iter_mut().zip(&state.players) {
+            *hand = RankCounts::from_cards(&player.hand);
+        }
+        Position {
+            hands,
+            seats: state.players.len(),
+            last: state.last_play.clone().zip(state.last_player),
+            turn: state.turn,
+            passes: state.pass_count,
+            winner: state.winner,
+        }
+    }
+
+    fn key(&self) -> Key {
+        Key {
+            hands: self.hands,
+            last: self
+                .last
+                .as_ref()
+                .map(|(play, seat)| (kind_order(play.kind), play.main_rank, play.size, *seat)),
+            turn: self.turn,
+            passes: self.passes,
+        }
+    }
+
+    /// The play the seat to move has to beat, if any.
+    fn to_beat(&self) -> Option<&Play> {
+        self.last
+            .as_ref()
+            .filter(|(_, seat)| *seat != self.turn)
+            .map(|(play, _)| play)
+    }
+
+    /// Mirrors `GameState::apply_play_as` and `GameState::pass`.
+    fn after(&self, step: &Step) -> Position {
+        let mut next = self.clone();
+        match step {
+            Step::Play(taken, play) => {
+                let hand = &mut next.hands[self.turn];
+                *hand = hand
+                    .remove(*taken)
+                    .expect("solver plays come from the hand");
+                if hand.is_empty() {
+                    next.winner = Some(self.turn);
+                }
+                next.last = Some((play.clone(), self.turn));
+                next.passes = 0;
+            }
+            Step::Pass => {
+                next.passes += 1;
+                if usize::from(next.passes) + 1 >= self.seats {
+                    next.last = None;
+                    next.passes = 0;
+                }
+            }
+        }
+        next.turn = (self.turn + 1) % self.seats;
+        next
+    }
+}
+
+/// Multiply-rotate hashing; the keys are plain integers and never come from
+/// outside, so SipHash's protection buys nothing here.
+#[derive(Default)]
+struct KeyHasher(u64);
+
+impl KeyHasher {
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+}
+
+impl Hasher for KeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.add(u64::from(*byte));
+        }
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.add(u64::from(value));
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.add(value);
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.add(value as u64);
+    }
+
+    fn write_isize(&mut self, value: isize) {
+        self.add(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+struct Search<'a> {
+    rules: &'a RuleSet,
+    wild: Option<Rank>,
+    landlord: usize,
+    /// Whether the landlord wins each position searched so far.
+    table: HashMap<Key, bool, BuildHasherDefault<KeyHasher>>,
+    nodes: u64,
+    node_limit: u64,
+}
+
+impl Search<'_> {
+    fn landlord_wins(&mut self, position: &Position) -> Result<bool, SolveError> {
+        if let Some(winner) = position.winner {
+            return Ok(winner == self.landlord);
+        }
+        let key = position.key();
+        if let Some(known) = self.table.get(&key) {
+            return Ok(*known);
+        }
+        self.nodes += 1;
+        if self.nodes > self.node_limit {
+            return Err(SolveError::NodeLimit(self.node_limit));
+        }
+        let landlord_to_move = position.turn == self.landlord;
+        let steps = self.steps(position);
+        let mut result = !landlord_to_move;
+        if steps.iter().any(|step| self.wins_outright(position, step)) {
+            result = landlord_to_move;
+        } else {
+            for step in steps {
+                if self.landlord_wins(&position.after(&step))? == landlord_to_move {
+                    result = landlord_to_move;
+                    break;
+                }
+            }
+        }
+        self.table.insert(key, result);
+        Ok(result)
+    }
+
+    /// Whether `step` goes out, or leaves one play behind after a play the
+    /// other side cannot beat: teammates then pass and the seat leads out.
+    fn wins_outright(&self, position: &Position, step: &Step) -> bool {
+        let Step::Play(taken, play) = step else {
+            return false;
+        };
+        let mover = position.turn;
+        let left = position.hands[mover].remove(*taken).unwrap_or_default();
+        if left.is_empty() {
+            return true;
+        }
+        let one_play = match self.wild {
+            None => left.classify(self.rules).is_some(),
+            Some(_) => !self.readings(left, None).is_empty(),
+        };
+        if !one_play {
+            return false;
+        }
+        let mover_is_landlord = mover == self.landlord;
+        (0..position.seats)
+            .filter(|seat| (*seat == self.landlord) != mover_is_landlord)
+            .all(|seat| self.plays(position.hands[seat], Some(play)).is_empty())
+    }
+
+    /// First step, in search order, that keeps the result at `landlord_wins`.
+    fn best_step(
+        &mut self,
+        position: &Position,
+        landlord_wins: bool,
+    ) -> Result<(Step, Position), SolveError> {
+        let mut fallback = None;
+        for step in self.steps(position) {
+            let next = position.after(&step);
+            if self.landlord_wins(&next)? == landlord_wins {
+                return Ok((step, next));
+            }
+            fallback.get_or_insert((step, next));
+        }
+        Ok(fallback.expect("an unfinished game always has a move"))
+    }
+
+    /// Legal steps for the seat to move, going out first, then bigger plays,
+    /// then passing. Each card set appears once per kind, read the way
+    /// `GameState::apply_play_as` reads it.
+    fn steps(&self, position: &Position) -> Vec<Step> {
+        let hand = position.hands[position.turn];
+        let prev = position.to_beat();
+        let mut plays = self.plays(hand, prev);
+        plays.sort_by_key(|(taken, play)| (*taken, kind_order(play.kind)));
+        plays.dedup_by(|later, earlier| later.0 == earlier.0 && later.1.kind == earlier.1.kind);
+        // Plays that leave the fewest ranks behind tend to win soonest.
+        plays.sort_by_cached_key(|(taken, _)| {
+            let left = hand.remove(*taken).unwrap_or_default();
+            (left.iter().count(), std::cmp::Reverse(taken.len()))
+        });
+
+        let mut steps: Vec<Step> = plays
+            .into_iter()
+            .map(|(taken, play)| Step::Play(taken, play))
+            .collect();
+        if let Some((_, last_seat)) = position.last.as_ref().filter(|_| prev.is_some()) {
+            // Letting a teammate's play stand is usually right.
+            let partner_led = *last_seat != self.landlord && position.turn != self.landlord;
+            if partner_led {
+                steps.insert(0, Step::Pass);
+            } else {
+                steps.push(Step::Pass);
+            }
+        }
+        steps
+    }
+
+    /// Every play `hand` can make against `prev`.
+    fn plays(&self, hand: RankCounts, prev: Option<&Play>) -> Vec<(RankCounts, Play)> {
+        match self.wild {
+            // Readings of one card set come together, best first.
+            None => hand.legal_moves(prev, self.rules),
+            Some(_) => legal_moves_wild(&hand.to_cards(), prev, self.rules, self.wild)
+                .into_iter()
+                .map(|candidate| {
+                    let taken = RankCounts::from_cards(&candidate.cards);
+                    let play = self.reading(taken, &candidate.play, prev);
+                    (taken, play)
+                })
+                .collect(),
+        }
+    }
+
+    /// Ways to read `cards` as a play against `prev`.
+    fn readings(&self, cards: RankCounts, prev: Option<&Play>) -> Vec<Play> {
+        let readings = match self.wild {
+            None => cards.classify_all(self.rules),
+            Some(_) => classify_all_with(&cards.to_cards(), self.rules, self.wild),
+        };
+        readings
+            .into_iter()
+            .filter(|reading| prev.is_none_or(|prev| can_beat_with(prev, reading, self.rules)))
+            .collect()
+    }
+
+    /// The reading `GameState::apply_play_as` picks for `taken` as `play.kind`.
+    fn reading(&self, taken: RankCounts, play: &Play, prev: Option<&Play>) -> Play {
+        self.readings(taken, prev)
+            .into_iter()
+            .find(|reading| reading.kind == play.kind)
+            .unwrap_or_else(|| play.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_hand;
+    use crate::{sort_hand, RulePreset, MAX_BID};
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    fn endgame(rules: RuleSet, hands: [&str; 3], landlord: usize, turn: usize) -> GameState {
+        let mut state = GameState::with_first_bidder(&[1, 2, 3], 1, rules, landlord);
+        state.bid(landlord, MAX_BID).unwrap();
+        for (player, text) in state.players.iter_mut().zip(hands) {
+            player.hand = parse_hand(text).unwrap();
+            sort_hand(&mut player.hand);
+        }
+        state.turn = turn;
+        state
+    }
+
+    /// Plain minimax over `GameState` itself.
+    fn landlord_wins_naive(state: &GameState) -> bool {
+        if let Some(winner) = state.winner {
+            return state.landlord == Some(winner);
+        }
+        let landlord_to_move = state.landlord == Some(state.turn);
+        let mut outcomes = state.legal_actions().into_iter().map(|action| {
+            let mut next = state.clone();
+            next.apply(state.turn, action).unwrap();
+            landlord_wins_naive(&next)
+        });
+        if landlord_to_move {
+            outcomes.any(|wins| wins)
+        } else {
+            outcomes.all(|wins| wins)
+        }
+    }
+
+    fn check_line(state: &GameState, solution: &Solution) {
+        let mut game = state.clone();
+        for (seat, action) in &solution.line {
+            assert_eq!(*seat, game.turn);
+            game.apply(*seat, action.clone()).unwrap();
+        }
+        let winner = game.winner.expect("the line plays the game out");
+        assert_eq!(game.side_of(winner) == Some(solution.side), solution.wins);
+    }
+
+    #[test]
+    fn matches_minimax_on_small_endgames() {
+        for (seed, preset) in (0..40).zip([RulePreset::Classic, RulePreset::Laizi].iter().cycle()) {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut state = GameState::with_rules(&[1, 2, 3], seed, preset.rules());
+            let bidder = state.turn;
+            state.bid(bidder, MAX_BID).unwrap();
+            for (idx, player) in state.players.iter_mut().enumerate() {
+                player.hand.shuffle(&mut rng);
+                player.hand.truncate(3 + (seed as usize + idx) % 3);
+                sort_hand(&mut player.hand);
+            }
+            // Start some positions mid-trick.
+            for _ in 0..seed % 3 {
+                let action = state.legal_actions().choose(&mut rng).unwrap().clone();
+                state.apply(state.turn, action).unwrap();
+                if state.winner.is_some() {
+                    break;
+                }
+            }
+            if state.winner.is_some() {
+                continue;
+            }
+            let solution = solve(&state).unwrap();
+            let landlord_wins = solution.wins == (solution.side == Side::Landlord);
+            assert_eq!(landlord_wins, landlord_wins_naive(&state), "seed {seed}");
+            check_line(&state, &solution);
+        }
+    }
+
+    #[test]
+    fn finds_the_winning_order() {
+        // Leading the three first lets the farmer in the middle out with a
+        // four; the rocket has to come first.
+        let state = endgame(RuleSet::default(), ["BJ RJ 3", "4", "5 6"], 0, 0);
+        let solution = solve(&state).unwrap();
+        assert_eq!(solution.side, Side::Landlord);
+        assert!(solution.wins);
+        assert_eq!(solution.line.len(), 4);
+        assert!(matches!(
+            &solution.line[0],
+            (
+                0,
+                Action::Play {
+                    kind: Some(crate::PlayKind::Rocket),
+                    ..
+                }
+            )
+        ));
+        check_line(&state, &solution);
+
+        // The farmers cannot stop a landlord holding two pairs over theirs.
+        let state = endgame(RuleSet::default(), ["AA KK", "3 QQ", "4 JJ"], 0, 1);
+        let solution = solve(&state).unwrap();
+        assert_eq!(solution.side, Side::Farmers);
+        assert!(!solution.wins);
+        check_line(&state, &solution);
+    }
+
+    #[test]
+    fn solves_ten_card_endgames() {
+        for seed in [0, 1, 4, 5, 7, 8, 9] {
+            let mut state = GameState::with_rules(&[1, 2, 3], 100 + seed, RuleSet::default());
+            let bidder = state.turn;
+            state.bid(bidder, MAX_BID).unwrap();
+            let mut rng = StdRng::seed_from_u64(seed);
+            for player in state.players.iter_mut() {
+                player.hand.shuffle(&mut rng);
+                player.hand.truncate(10);
+                sort_hand(&mut player.hand);
+            }
+            let solution = solve(&state).unwrap();
+            assert!(solution.nodes < 100_000, "seed {seed}: {}", solution.nodes);
+            check_line(&state, &solution);
+        }
+    }
+
+    #[test]
+    fn reports_limits_and_phase() {
+        let state = GameState::new(&[1, 2, 3], 3);
+        assert_eq!(solve(&state), Err(SolveError::NotPlaying));
+        let state = endgame(RuleSet::default(), ["3 5 7 9 J", "4 6 8", "T Q K"], 0, 0);
+        assert_eq!(solve_within(&state, 2), Err(SolveError::NodeLimit(2)));
+    }
+}
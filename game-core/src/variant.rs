@@ -0,0 +1,178 @@
+use crate::{Card, Rank, Suit, SUITED_RANKS};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+/// Table layout: how many seats there are and which cards are dealt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Variant {
+    /// Three players, one 54-card deck, 17 cards each and 3 bottom cards.
+    #[default]
+    Classic,
+    /// Four players, two decks (108 cards), 25 cards each and 8 bottom cards.
+    FourPlayer,
+    /// 二人斗地主: the 3s and 4s are removed, each player gets 17 cards, 3 go
+    /// to the bottom and the remaining 9 stay face down.
+    TwoPlayer,
+}
+
+/// Which cards make up the deck before shuffling.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeckSpec {
+    pub decks: usize,
+    pub removed_ranks: Vec<Rank>,
+}
+
+impl DeckSpec {
+    pub fn cards(&self) -> Vec<Card> {
+        let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+        let mut cards = Vec::with_capacity(54 * self.decks);
+        for _ in 0..self.decks {
+            for suit in suits.iter() {
+                for rank in SUITED_RANKS.iter() {
+                    cards.push(Card {
+                        rank: *rank,
+                        suit: *suit,
+                    });
+                }
+            }
+            for rank in [Rank::BlackJoker, Rank::RedJoker] {
+                cards.push(Card {
+                    rank,
+                    suit: Suit::Joker,
+                });
+            }
+        }
+        cards.retain(|card| !self.removed_ranks.contains(&card.rank));
+        cards
+    }
+}
+
+/// Cards dealt for one game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deal {
+    pub hands: Vec<Vec<Card>>,
+    pub bottom: Vec<Card>,
+    /// Cards nobody receives, kept hidden.
+    pub leftover: Vec<Card>,
+}
+
+impl Variant {
+    pub fn player_count(self) -> usize {
+        match self {
+            Variant::Classic => 3,
+            Variant::FourPlayer => 4,
+            Variant::TwoPlayer => 2,
+        }
+    }
+
+    /// Number of full decks shuffled together.
+    pub fn decks(self) -> usize {
+        match self {
+            Variant::Classic | Variant::TwoPlayer => 1,
+            Variant::FourPlayer => 2,
+        }
+    }
+
+    pub fn deck(self) -> DeckSpec {
+        match self {
+            Variant::Classic | Variant::FourPlayer => DeckSpec {
+                decks: self.decks(),
+                removed_ranks: Vec::new(),
+            },
+            Variant::TwoPlayer => DeckSpec {
+                decks: self.decks(),
+                removed_ranks: vec![Rank::Three, Rank::Four],
+            },
+        }
+    }
+
+    pub fn bottom_size(self) -> usize {
+        match self {
+            Variant::Classic | Variant::TwoPlayer => 3,
+            Variant::FourPlayer => 8,
+        }
+    }
+
+    pub fn hand_size(self) -> usize {
+        match self {
+            Variant::Classic | Variant::TwoPlayer => 17,
+            Variant::FourPlayer => 25,
+        }
+    }
+
+    pub fn shuffled_deck(self, seed: u64) -> Vec<Card> {
+        let mut deck = self.deck().cards();
+        let mut rng = StdRng::seed_from_u64(seed);
+        deck.shuffle(&mut rng);
+        deck
+    }
+
+    /// Deals round-robin from a seeded shuffle; the bottom follows the hands
+    /// and anything after it is left over.
+    pub fn deal(self, seed: u64) -> Deal {
+        let deck = self.shuffled_deck(seed);
+        let players = self.player_count();
+        let dealt = self.hand_size() * players;
+        let mut hands = vec![Vec::with_capacity(self.hand_size()); players];
+        for (i, card) in deck.iter().take(dealt).enumerate() {
+            hands[i % players].push(*card);
+        }
+        let bottom = deck[dealt..dealt + self.bottom_size()].to_vec();
+        let leftover = deck[dealt + self.bottom_size()..].to_vec();
+        Deal {
+            hands,
+            bottom,
+            leftover,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_deal_matches_single_deck() {
+        let deal = Variant::Classic.deal(42);
+        assert_eq!(deal.hands.len(), 3);
+        assert!(deal.hands.iter().all(|hand| hand.len() == 17));
+        assert_eq!(deal.bottom.len(), 3);
+        assert!(deal.leftover.is_empty());
+    }
+
+    #[test]
+    fn two_player_deal_leaves_hidden_pile() {
+        let deal = Variant::TwoPlayer.deal(42);
+        assert_eq!(deal.hands.len(), 2);
+        assert!(deal.hands.iter().all(|hand| hand.len() == 17));
+        assert_eq!(deal.bottom.len(), 3);
+        assert_eq!(deal.leftover.len(), 9);
+        assert!(deal
+            .hands
+            .iter()
+            .flatten()
+            .chain(deal.bottom.iter())
+            .chain(deal.leftover.iter())
+            .all(|card| card.rank != Rank::Three && card.rank != Rank::Four));
+    }
+
+    #[test]
+    fn four_player_deal_uses_two_decks() {
+        let variant = Variant::FourPlayer;
+        assert_eq!(variant.deck().cards().len(), 108);
+        let deal = variant.deal(42);
+        assert_eq!(deal.hands.len(), 4);
+        assert!(deal.hands.iter().all(|hand| hand.len() == 25));
+        assert_eq!(deal.bottom.len(), 8);
+        let jokers = deal
+            .hands
+            .iter()
+            .flatten()
+            .chain(deal.bottom.iter())
+            .filter(|card| card.suit == Suit::Joker)
+            .count();
+        assert_eq!(jokers, 4);
+    }
+}
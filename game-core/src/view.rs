@@ -0,0 +1,188 @@
+use crate::{
+    legal_moves_wild, Action, BiddingMode, Card, GamePhase, GameState, Play, Rank, RuleSet, Side,
+    DOUBLE_FACTORS, MAX_BID,
+};
+use serde::{Deserialize, Serialize};
+
+/// What one seat can see of the table: its own hand, every card played so
+/// far and how many cards each seat holds. Bots decide from this alone, so
+/// they cannot peek at other hands.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub seat: usize,
+    pub hand: Vec<Card>,
+    pub rules: RuleSet,
+    pub phase: GamePhase,
+    pub turn: usize,
+    pub landlord: Option<usize>,
+    pub wild_rank: Option<Rank>,
+    /// Bottom cards, once the landlord has taken them.
+    pub bottom: Vec<Card>,
+    pub hand_counts: Vec<usize>,
+    /// Cards each seat has played, oldest first.
+    pub played: Vec<Vec<Card>>,
+    /// Hands played face up (明牌).
+    pub shown: Vec<Option<Vec<Card>>>,
+    pub bidding_mode: BiddingMode,
+    /// Highest point bid so far, or whether someone called in rob bidding.
+    pub current_bid: u8,
+    pub last_play: Option<Play>,
+    pub last_player: Option<usize>,
+    pub pass_count: u8,
+}
+
+impl GameState {
+    /// The table as `seat` sees it, or `None` for a seat not at the table.
+    pub fn view(&self, seat: usize) -> Option<PlayerView> {
+        let player = self.players.get(seat)?;
+        let mut played = vec![Vec::new(); self.players.len()];
+        for event in &self.events {
+            if let Action::Play { cards, .. } = &event.action {
+                played[event.seat].extend_from_slice(cards);
+            }
+        }
+        Some(PlayerView {
+            seat,
+            hand: player.hand.clone(),
+            rules: self.rules,
+            phase: self.phase,
+            turn: self.turn,
+            landlord: self.landlord,
+            wild_rank: self.wild_rank,
+            bottom: if self.landlord.is_some() {
+                self.bottom.clone()
+            } else {
+                Vec::new()
+            },
+            hand_counts: self
+                .players
+                .iter()
+                .map(|player| player.hand.len())
+                .collect(),
+            played,
+            shown: self
+                .players
+                .iter()
+                .zip(&self.shown)
+                .map(|(player, shown)| shown.map(|_| player.hand.clone()))
+                .collect(),
+            bidding_mode: self.bidding.mode(),
+            current_bid: self.bidding.current_bid(),
+            last_play: self.last_play.clone(),
+            last_player: self.last_player,
+            pass_count: self.pass_count,
+        })
+    }
+}
+
+impl PlayerView {
+    pub fn player_count(&self) -> usize {
+        self.hand_counts.len()
+    }
+
+    pub fn side_of(&self, seat: usize) -> Option<Side> {
+        let landlord = self.landlord?;
+        Some(if seat == landlord {
+            Side::Landlord
+        } else {
+            Side::Farmers
+        })
+    }
+
+    /// Whether `seat` plays on the same side as this one.
+    pub fn is_teammate(&self, seat: usize) -> bool {
+        seat != self.seat
+            && self.landlord.is_some()
+            && self.side_of(seat) == self.side_of(self.seat)
+    }
+
+    /// Seats on the other side.
+    pub fn opponents(&self) -> Vec<usize> {
+        (0..self.player_count())
+            .filter(|seat| *seat != self.seat && !self.is_teammate(*seat))
+            .collect()
+    }
+
+    /// The play this seat has to beat, if any.
+    pub fn to_beat(&self) -> Option<&Play> {
+        self.last_play
+            .as_ref()
+            .filter(|_| self.last_player.is_some_and(|last| last != self.seat))
+    }
+
+    /// `GameState::legal_actions` for this seat, worked out from the view.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        match self.phase {
+            GamePhase::Bidding => match self.bidding_mode {
+                BiddingMode::Points => std::iter::once(0)
+                    .chain(self.current_bid + 1..=MAX_BID)
+                    .map(Action::Bid)
+                    .collect(),
+                BiddingMode::Rob => vec![Action::Rob(true), Action::Rob(false)],
+            },
+            GamePhase::Doubling => DOUBLE_FACTORS.iter().copied().map(Action::Double).collect(),
+            GamePhase::Playing => {
+                let prev = self.to_beat();
+                let mut actions: Vec<Action> =
+                    legal_moves_wild(&self.hand, prev, &self.rules, self.wild_rank)
+                        .into_iter()
+                        .map(|candidate| Action::Play {
+                            cards: candidate.cards,
+                            kind: Some(candidate.play.kind),
+                        })
+                        .collect();
+                if prev.is_some() {
+                    actions.push(Action::Pass);
+                }
+                actions
+            }
+            GamePhase::Finished => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RulePreset;
+
+    #[test]
+    fn view_shows_counts_and_played_cards_only() {
+        let mut state = GameState::with_rules(&[1, 2, 3], 8, RulePreset::Happy.rules());
+        let mut turns = 0;
+        while state.phase != GamePhase::Finished && turns < 12 {
+            let seat = state.turn;
+            let view = state.view(seat).unwrap();
+            assert_eq!(view.legal_actions(), state.legal_actions());
+            let action = view.legal_actions().remove(0);
+            state.apply(seat, action).unwrap();
+            turns += 1;
+        }
+
+        let landlord = state.landlord.unwrap();
+        let farmer = (landlord + 1) % 3;
+        let view = state.view(farmer).unwrap();
+        assert_eq!(view.hand, state.players[farmer].hand);
+        assert_eq!(view.bottom, state.bottom);
+        assert_eq!(view.opponents(), vec![landlord]);
+        assert!(view.is_teammate((landlord + 2) % 3));
+        let played: usize = view.played.iter().map(Vec::len).sum();
+        let held: usize = view.hand_counts.iter().sum();
+        assert_eq!(played + held, 54);
+        for (seat, player) in state.players.iter().enumerate() {
+            assert_eq!(view.hand_counts[seat], player.hand.len());
+            assert_eq!(view.shown[seat].is_some(), state.shown[seat].is_some());
+        }
+    }
+
+    #[test]
+    fn bottom_stays_hidden_during_bidding() {
+        let state = GameState::new(&[1, 2, 3], 4);
+        let view = state.view(0).unwrap();
+        assert!(view.bottom.is_empty());
+        assert_eq!(state.view(3), None);
+        assert!(view.played.iter().all(Vec::is_empty));
+        assert_eq!(view.opponents(), vec![1, 2]);
+        assert_eq!(view.legal_actions(), state.legal_actions());
+    }
+}
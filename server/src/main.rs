@@ -10,13 +10,10 @@ use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
-use tracing::info;
+use tracing::{debug, info, warn};
 
-mod protocol;
-mod rooms;
-
-use protocol::{ClientMessage, ServerMessage};
-use rooms::{display_name_for_user, PlayerConn, RoomError, RoomManager};
+use server::protocol::{ClientMessage, ServerMessage};
+use server::rooms::{display_name_for_user, PlayerConn, RoomError, RoomManager, UndoVote};
 
 #[derive(Clone)]
 struct AppState {
@@ -126,12 +123,19 @@ async fn handle_client_message(
         ClientMessage::ListRooms => {
             send_room_list(state, tx).await;
         }
-        ClientMessage::CreateRoom => {
+        ClientMessage::CreateRoom(config) => {
             leave_room_if_needed(state, current_room, user_id).await;
+            let config = config.unwrap_or_default();
 
             let (room_id, player_count, started) = {
                 let mut rooms = state.rooms.lock().await;
-                let room_id = rooms.create_room();
+                let room_id = match config.preset {
+                    Some(preset) => rooms.create_room_with_preset(preset),
+                    None => rooms.create_room_with(config.rules)?,
+                };
+                if config.practice {
+                    rooms.make_practice(&room_id)?;
+                }
                 rooms.join_room(
                     &room_id,
                     PlayerConn {
@@ -194,8 +198,72 @@ async fn handle_client_message(
 
             broadcast_room_state(state, &normalized_room).await;
             send_room_list(state, tx).await;
+            spawn_bots(state, &normalized_room);
+        }
+        ClientMessage::Bid { value } => {
+            let room_id = current_room
+                .as_ref()
+                .map(|binding| binding.room_id.clone())
+                .ok_or(RoomError::NotFound)?;
+            {
+                let mut rooms = state.rooms.lock().await;
+                rooms.bid(&room_id, user_id, value)?;
+            }
+            broadcast_room_state(state, &room_id).await;
+            spawn_bots(state, &room_id);
+        }
+        ClientMessage::Rob { take } => {
+            let room_id = current_room
+                .as_ref()
+                .map(|binding| binding.room_id.clone())
+                .ok_or(RoomError::NotFound)?;
+            {
+                let mut rooms = state.rooms.lock().await;
+                rooms.rob(&room_id, user_id, take)?;
+            }
+            broadcast_room_state(state, &room_id).await;
+            spawn_bots(state, &room_id);
+        }
+        ClientMessage::Double { factor } => {
+            let room_id = current_room
+                .as_ref()
+                .map(|binding| binding.room_id.clone())
+                .ok_or(RoomError::NotFound)?;
+            {
+                let mut rooms = state.rooms.lock().await;
+                rooms.double(&room_id, user_id, factor)?;
+            }
+            broadcast_room_state(state, &room_id).await;
+            spawn_bots(state, &room_id);
         }
-        ClientMessage::Play { cards } => {
+        ClientMessage::RequestHint { cursor } => {
+            let room_id = current_room
+                .as_ref()
+                .map(|binding| binding.room_id.clone())
+                .ok_or(RoomError::NotFound)?;
+            let hints = {
+                let rooms = state.rooms.lock().await;
+                rooms.hints_for(&room_id, user_id)?
+            };
+            let cursor = if hints.is_empty() {
+                0
+            } else {
+                cursor % hints.len()
+            };
+            let _ = tx.send(ServerMessage::Hints { cursor, hints });
+        }
+        ClientMessage::ShowHand => {
+            let room_id = current_room
+                .as_ref()
+                .map(|binding| binding.room_id.clone())
+                .ok_or(RoomError::NotFound)?;
+            {
+                let mut rooms = state.rooms.lock().await;
+                rooms.show_hand(&room_id, user_id)?;
+            }
+            broadcast_room_state(state, &room_id).await;
+        }
+        ClientMessage::Play { cards, kind } => {
             let room_id = current_room
                 .as_ref()
                 .map(|binding| binding.room_id.clone())
@@ -210,14 +278,17 @@ async fn handle_client_message(
                 });
                 return Ok(());
             }
+            let played = game_core::notation::format_hand(&card_objs);
             let winner = {
                 let mut rooms = state.rooms.lock().await;
-                rooms.apply_play(&room_id, user_id, card_objs)?
+                rooms.apply_play_as(&room_id, user_id, card_objs, kind)?
             };
+            debug!("room {room_id}: player {user_id} played {played}");
             broadcast_room_state(state, &room_id).await;
             if let Some(winner_id) = winner {
                 broadcast_game_over(state, &room_id, winner_id).await;
             }
+            spawn_bots(state, &room_id);
         }
         ClientMessage::Pass => {
             let room_id = current_room
@@ -229,6 +300,7 @@ async fn handle_client_message(
                 rooms.pass_turn(&room_id, user_id)?;
             }
             broadcast_room_state(state, &room_id).await;
+            spawn_bots(state, &room_id);
         }
         ClientMessage::RestartGame => {
             let room_id = current_room
@@ -241,11 +313,115 @@ async fn handle_client_message(
             }
             broadcast_room_state(state, &room_id).await;
             broadcast_game_restarted(state, &room_id).await;
+            spawn_bots(state, &room_id);
+        }
+        ClientMessage::Undo => {
+            let room_id = current_room
+                .as_ref()
+                .map(|binding| binding.room_id.clone())
+                .ok_or(RoomError::NotFound)?;
+            let vote = {
+                let mut rooms = state.rooms.lock().await;
+                rooms.request_undo(&room_id, user_id)?
+            };
+            match vote {
+                UndoVote::Pending { agreed, needed } => {
+                    broadcast_undo_requested(state, &room_id, user_id, agreed, needed).await;
+                }
+                UndoVote::Undone => {
+                    debug!("room {room_id}: undid the last action");
+                    broadcast_room_state(state, &room_id).await;
+                    spawn_bots(state, &room_id);
+                }
+            }
+        }
+        ClientMessage::AddBot { strategy } => {
+            let room_id = current_room
+                .as_ref()
+                .map(|binding| binding.room_id.clone())
+                .ok_or(RoomError::NotFound)?;
+            {
+                let mut rooms = state.rooms.lock().await;
+                let bot_id = rooms.add_bot(&room_id, &strategy)?;
+                debug!("room {room_id}: seated {strategy} bot {bot_id}");
+                let _ = rooms.start_if_ready(&room_id, rand::random());
+            }
+            broadcast_room_state(state, &room_id).await;
+            send_room_list(state, tx).await;
+            spawn_bots(state, &room_id);
         }
     }
     Ok(())
 }
 
+/// Lets the room's bots play in a task of their own, so the player who
+/// moved last can keep sending messages while they think.
+fn spawn_bots(state: &AppState, room_id: &str) {
+    let state = state.clone();
+    let room_id = room_id.to_string();
+    tokio::spawn(async move { run_bots(&state, &room_id).await });
+}
+
+/// Plays for the room's bots until a human is to move or the game ends,
+/// broadcasting after each action. Bots think on the blocking pool with the
+/// rooms unlocked.
+async fn run_bots(state: &AppState, room_id: &str) {
+    loop {
+        let bot_move = {
+            let rooms = state.rooms.lock().await;
+            rooms.bot_move(room_id)
+        };
+        let Ok(Some(bot_move)) = bot_move else {
+            return;
+        };
+        let decided = tokio::task::spawn_blocking(move || {
+            let action = bot_move.decide();
+            (bot_move, action)
+        })
+        .await;
+        let (bot_move, action) = match decided {
+            Ok((bot_move, Some(action))) => (bot_move, action),
+            Ok((_, None)) => return,
+            Err(err) => {
+                warn!("room {room_id}: bot failed to move: {err}");
+                return;
+            }
+        };
+        let turn = {
+            let mut rooms = state.rooms.lock().await;
+            match rooms.apply_bot_move(room_id, &bot_move, action) {
+                Ok(turn) => turn,
+                Err(err) => {
+                    warn!(
+                        "room {room_id}: bot {} was refused: {err:?}",
+                        bot_move.bot_id
+                    );
+                    // Play something legal rather than leave the seat stuck.
+                    let fallback = bot_move
+                        .fallback()
+                        .map(|action| rooms.apply_bot_move(room_id, &bot_move, action));
+                    match fallback {
+                        Some(Ok(turn)) => turn,
+                        _ => return,
+                    }
+                }
+            }
+        };
+        // Someone else moved the game on meanwhile; look again.
+        let Some(turn) = turn else {
+            continue;
+        };
+        debug!(
+            "room {room_id}: bot {} chose {:?}",
+            turn.bot_id, turn.action
+        );
+        broadcast_room_state(state, room_id).await;
+        if let Some(winner_id) = turn.winner_id {
+            broadcast_game_over(state, room_id, winner_id).await;
+        }
+    }
+}
+
 async fn leave_room_if_needed(
     state: &AppState,
     current_room: &mut Option<SessionBinding>,
@@ -283,7 +459,7 @@ async fn broadcast_room_state(state: &AppState, room_id: &str) {
         for connection in connections {
             if let Some(snapshot) = rooms.snapshot_for(room_id, connection.id) {
                 if let Some(tx) = connection.tx {
-                    let _ = tx.send(ServerMessage::RoomState(snapshot));
+                    let _ = tx.send(ServerMessage::RoomState(Box::new(snapshot)));
                 }
             }
         }
@@ -292,12 +468,16 @@ async fn broadcast_room_state(state: &AppState, room_id: &str) {
 
 async fn broadcast_game_over(state: &AppState, room_id: &str, winner_id: u64) {
     let rooms = state.rooms.lock().await;
+    let settlement = rooms.settlement_for(room_id);
+    let result = rooms.result_for(room_id);
     if let Some(connections) = rooms.room_connections(room_id) {
         for connection in connections {
             if let Some(tx) = connection.tx {
                 let _ = tx.send(ServerMessage::GameOver {
                     room_id: room_id.to_string(),
                     winner_id,
+                    settlement: settlement.clone(),
+                    result: result.clone(),
                 });
             }
         }
@@ -336,3 +516,25 @@ async fn broadcast_game_restarted(state: &AppState, room_id: &str) {
         }
     }
 }
+
+async fn broadcast_undo_requested(
+    state: &AppState,
+    room_id: &str,
+    requester_id: u64,
+    agreed: Vec<u64>,
+    needed: usize,
+) {
+    let rooms = state.rooms.lock().await;
+    if let Some(connections) = rooms.room_connections(room_id) {
+        for connection in connections {
+            if let Some(tx) = connection.tx {
+                let _ = tx.send(ServerMessage::UndoRequested {
+                    room_id: room_id.to_string(),
+                    requester_id,
+                    agreed: agreed.clone(),
+                    needed,
+                });
+            }
+        }
+    }
+}
@@ -1,17 +1,65 @@
+use game_core::{PlayKind, RulePreset, RuleSet, Side};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ClientMessage {
-    CreateRoom,
-    JoinRoom { room_id: String },
+    CreateRoom(#[serde(default)] Option<RoomConfig>),
+    JoinRoom {
+        room_id: String,
+    },
     ListRooms,
-    Play { cards: Vec<String> },
+    Bid {
+        value: u8,
+    },
+    Rob {
+        take: bool,
+    },
+    /// 加倍 factor: 1 (no), 2 (加倍) or 4 (超级加倍).
+    Double {
+        factor: u32,
+    },
+    /// 明牌: play with the hand face up.
+    ShowHand,
+    Play {
+        cards: Vec<String>,
+        /// Reading to use when the cards can be classified more than one way.
+        #[serde(default)]
+        kind: Option<PlayKind>,
+    },
     Pass,
+    /// Asks for legal plays ranked as hints; `cursor` picks the one to
+    /// suggest and wraps around, so pressing "提示" again sends `cursor + 1`.
+    RequestHint {
+        #[serde(default)]
+        cursor: usize,
+    },
     RestartGame,
+    /// Asks to take back the last action; only practice rooms accept it, once
+    /// every human player has asked.
+    Undo,
+    /// Fills a free seat of the current room with a bot playing a registered
+    /// strategy: "random", "easy", "normal" or "hard".
+    AddBot {
+        strategy: String,
+    },
     Ping,
 }
 
+/// Rules for a new room: either a named preset or custom rule fields, which
+/// fall back to the classic rules when left out.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RoomConfig {
+    /// Takes precedence over the custom fields.
+    #[serde(default)]
+    pub preset: Option<RulePreset>,
+    #[serde(flatten)]
+    pub rules: RuleSet,
+    /// Practice rooms allow taking moves back.
+    #[serde(default)]
+    pub practice: bool,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ServerMessage {
@@ -32,13 +80,20 @@ pub enum ServerMessage {
     RoomsList {
         rooms: Vec<RoomSummary>,
     },
-    RoomState(RoomSnapshot),
+    RoomState(Box<RoomSnapshot>),
     PlayRejected {
         reason: String,
     },
+    /// Ranked hints for the requester's hand; `cursor` is the suggested one.
+    Hints {
+        cursor: usize,
+        hints: Vec<HintView>,
+    },
     GameOver {
         room_id: String,
         winner_id: u64,
+        settlement: Option<SettlementView>,
+        result: Option<GameResultView>,
     },
     RoomInterrupted {
         room_id: String,
@@ -48,6 +103,14 @@ pub enum ServerMessage {
     GameRestarted {
         room_id: String,
     },
+    /// Someone asked to undo; the action is taken back once `agreed` holds
+    /// `needed` players.
+    UndoRequested {
+        room_id: String,
+        requester_id: u64,
+        agreed: Vec<u64>,
+        needed: usize,
+    },
     Error {
         message: String,
     },
@@ -60,6 +123,17 @@ pub struct PlayerInfo {
     pub name: String,
     pub hand_count: usize,
     pub is_landlord: bool,
+    pub bid: Option<u8>,
+    pub rob: Option<bool>,
+    /// 加倍 decision, once made.
+    pub double: Option<u32>,
+    /// Multiplier this seat is playing for, including 加倍.
+    pub multiplier: u32,
+    /// Cards still in hand, for seats that showed their hand (明牌).
+    pub shown_hand: Option<Vec<String>>,
+    /// Strategy name for seats played by a bot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -67,7 +141,11 @@ pub struct RoomSummary {
     pub room_id: String,
     pub player_count: usize,
     pub started: bool,
+    pub capacity: usize,
     pub can_join: bool,
+    pub bidding_mode: String,
+    pub variant: String,
+    pub preset: Option<RulePreset>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -75,14 +153,76 @@ pub struct PlayView {
     pub kind: String,
     pub main_rank: String,
     pub size: usize,
+    pub substitutions: Vec<SubstitutionView>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HintView {
+    pub cards: Vec<String>,
+    pub play: PlayView,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubstitutionView {
+    pub card: String,
+    pub as_rank: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RoomSnapshot {
     pub room_id: String,
+    pub phase: String,
+    pub bidding_mode: String,
+    pub variant: String,
+    pub preset: Option<RulePreset>,
+    pub rules: RuleSet,
+    pub practice: bool,
+    pub current_bid: u8,
+    pub multiplier: u32,
+    pub wild_rank: Option<String>,
+    pub bottom: Vec<String>,
     pub players: Vec<PlayerInfo>,
     pub turn: u64,
     pub last_player: Option<u64>,
     pub last_play: Option<PlayView>,
     pub your_hand: Vec<String>,
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScoreView {
+    pub player_id: u64,
+    pub double: u32,
+    pub delta: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SettlementView {
+    pub landlord_id: u64,
+    pub landlord_won: bool,
+    pub base_score: u32,
+    pub bid: u8,
+    pub bombs: u32,
+    pub spring: String,
+    pub multiplier: u32,
+    pub scores: Vec<ScoreView>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerResultView {
+    pub player_id: u64,
+    pub side: Side,
+    pub won: bool,
+    pub remaining: Vec<String>,
+    pub plays: u32,
+    pub delta: i64,
+}
+
+/// Outcome for the whole table; every seat on `winning_side` has won.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameResultView {
+    pub winning_side: Side,
+    pub first_out_id: u64,
+    pub landlord_plays: u32,
+    pub farmer_plays: u32,
+    pub players: Vec<PlayerResultView>,
+}
@@ -1,5 +1,11 @@
-use crate::protocol::{PlayView, PlayerInfo, RoomSnapshot, RoomSummary};
-use game_core::{GameError, GameState, Play};
+use crate::protocol::{
+    GameResultView, HintView, PlayView, PlayerInfo, PlayerResultView, RoomSnapshot, RoomSummary,
+    ScoreView, SettlementView, SubstitutionView,
+};
+use game_core::{
+    Action, Bidding, GameError, GamePhase, GameState, Opening, Play, PlayKind, PlayerView,
+    RuleError, RulePreset, RuleSet, Side,
+};
 use rand::distributions::Alphanumeric;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -15,7 +21,88 @@ pub struct PlayerConn {
 #[derive(Clone, Debug)]
 pub struct Room {
     pub players: Vec<PlayerConn>,
+    pub rules: RuleSet,
+    /// Preset the rules came from, if the creator picked one.
+    pub preset: Option<RulePreset>,
     pub state: Option<GameState>,
+    /// Practice rooms let the table take moves back.
+    pub practice: bool,
+    pub undo_request: Option<UndoRequest>,
+    /// Seats the server plays, by player id, with their strategy name.
+    pub bots: HashMap<u64, String>,
+}
+
+/// Players asking to undo the action that brought the game to `events`
+/// logged actions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UndoRequest {
+    pub events: usize,
+    pub agreed: Vec<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UndoVote {
+    /// Still waiting on other players.
+    Pending {
+        agreed: Vec<u64>,
+        needed: usize,
+    },
+    Undone,
+}
+
+/// An action a bot took for its seat.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BotTurn {
+    pub bot_id: u64,
+    pub action: Action,
+    /// Set when the action ended the game.
+    pub winner_id: Option<u64>,
+}
+
+/// A bot's turn, taken out of the room so the bot can think without the
+/// room being borrowed.
+#[derive(Clone, Debug)]
+pub struct BotMove {
+    pub bot_id: u64,
+    seat: usize,
+    strategy: String,
+    view: PlayerView,
+    /// Which game, and how far into it, the move was taken out.
+    opening: Option<Opening>,
+    events: usize,
+}
+
+impl BotMove {
+    /// Runs the bot's strategy. The search bots can take a while.
+    pub fn decide(&self) -> Option<Action> {
+        bot::create(&self.strategy, self.bot_id)?.act(&self.view)
+    }
+
+    /// The seat's first legal action, for when the table refuses what the
+    /// bot decided.
+    pub fn fallback(&self) -> Option<Action> {
+        self.view.legal_actions().into_iter().next()
+    }
+}
+
+impl Room {
+    /// Seats needed before a game can start.
+    pub fn capacity(&self) -> usize {
+        self.rules.variant.player_count()
+    }
+
+    fn player_ids(&self) -> Vec<u64> {
+        self.players.iter().map(|player| player.id).collect()
+    }
+
+    /// Players whose agreement an undo needs.
+    fn human_ids(&self) -> Vec<u64> {
+        self.players
+            .iter()
+            .map(|player| player.id)
+            .filter(|id| !self.bots.contains_key(id))
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -44,6 +131,21 @@ pub enum RoomError {
     NotReady,
     CannotPass,
     RestartNotAllowed,
+    InvalidBid,
+    WrongPhase,
+    WrongBiddingMode,
+    InvalidDouble,
+    CannotShowHand,
+    UndoNotAllowed,
+    NothingToUndo,
+    UnknownBot,
+    InvalidRules(RuleError),
+}
+
+impl Default for RoomManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RoomManager {
@@ -59,27 +161,140 @@ impl RoomManager {
     }
 
     pub fn create_room(&mut self) -> String {
+        self.insert_room(RuleSet::default(), None)
+    }
+
+    /// A room with custom rules, which must pass `RuleSet::validate`.
+    pub fn create_room_with(&mut self, rules: RuleSet) -> Result<String, RoomError> {
+        rules.validate().map_err(RoomError::InvalidRules)?;
+        Ok(self.insert_room(rules, None))
+    }
+
+    pub fn create_room_with_preset(&mut self, preset: RulePreset) -> String {
+        self.insert_room(preset.rules(), Some(preset))
+    }
+
+    fn insert_room(&mut self, rules: RuleSet, preset: Option<RulePreset>) -> String {
         let id = self.new_room_id();
         let room = Room {
             players: Vec::new(),
+            rules,
+            preset,
             state: None,
+            practice: false,
+            undo_request: None,
+            bots: HashMap::new(),
         };
         self.rooms.insert(id.clone(), room);
         id
     }
 
+    /// Turns undo on for a room.
+    pub fn make_practice(&mut self, room_id: &str) -> Result<(), RoomError> {
+        let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
+        room.practice = true;
+        Ok(())
+    }
+
     pub fn join_room(&mut self, room_id: &str, player: PlayerConn) -> Result<(), RoomError> {
         let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
         if room.players.iter().any(|p| p.id == player.id) {
             return Err(RoomError::AlreadyJoined);
         }
-        if room.players.len() >= 3 {
+        if room.players.len() >= room.capacity() {
             return Err(RoomError::Full);
         }
         room.players.push(player);
         Ok(())
     }
 
+    /// Seats a bot playing the registered strategy `strategy` (see
+    /// `bot::strategy_names`) and returns its player id.
+    pub fn add_bot(&mut self, room_id: &str, strategy: &str) -> Result<u64, RoomError> {
+        if bot::create(strategy, 0).is_none() {
+            return Err(RoomError::UnknownBot);
+        }
+        let bot_id = self.rng.gen::<u64>();
+        self.join_room(
+            room_id,
+            PlayerConn {
+                id: bot_id,
+                tx: None,
+            },
+        )?;
+        if let Some(room) = self.rooms.get_mut(room_id) {
+            room.bots.insert(bot_id, strategy.to_string());
+        }
+        Ok(bot_id)
+    }
+
+    /// Lets the bot whose turn it is act. `Ok(None)` when no game is running
+    /// or a human is to move.
+    pub fn play_bot_turn(&mut self, room_id: &str) -> Result<Option<BotTurn>, RoomError> {
+        let Some(bot_move) = self.bot_move(room_id)? else {
+            return Ok(None);
+        };
+        let Some(action) = bot_move.decide() else {
+            return Ok(None);
+        };
+        self.apply_bot_move(room_id, &bot_move, action)
+    }
+
+    /// The turn of the bot to move, to `decide` away from the room. `Ok(None)`
+    /// when no game is running or a human is to move.
+    pub fn bot_move(&self, room_id: &str) -> Result<Option<BotMove>, RoomError> {
+        let room = self.rooms.get(room_id).ok_or(RoomError::NotFound)?;
+        let Some(state) = room.state.as_ref() else {
+            return Ok(None);
+        };
+        let seat = state.turn;
+        let bot_id = state.players[seat].id;
+        let Some(strategy) = room.bots.get(&bot_id) else {
+            return Ok(None);
+        };
+        Ok(state.view(seat).map(|view| BotMove {
+            bot_id,
+            seat,
+            strategy: strategy.clone(),
+            view,
+            opening: state.opening,
+            events: state.events.len(),
+        }))
+    }
+
+    /// Applies what the bot decided, unless the game moved on while it was
+    /// thinking, in which case `Ok(None)`.
+    pub fn apply_bot_move(
+        &mut self,
+        room_id: &str,
+        bot_move: &BotMove,
+        action: Action,
+    ) -> Result<Option<BotTurn>, RoomError> {
+        let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
+        let Some(state) = room.state.as_mut() else {
+            return Ok(None);
+        };
+        let seat = bot_move.seat;
+        let bot_id = bot_move.bot_id;
+        if state.opening != bot_move.opening
+            || state.events.len() != bot_move.events
+            || state.turn != seat
+            || state.players[seat].id != bot_id
+        {
+            return Ok(None);
+        }
+        state.apply(seat, action.clone()).map_err(map_game_error)?;
+        let winner_id = state
+            .winner
+            .filter(|_| state.phase == GamePhase::Finished)
+            .map(|idx| state.players[idx].id);
+        Ok(Some(BotTurn {
+            bot_id,
+            action,
+            winner_id,
+        }))
+    }
+
     pub fn remove_connection(
         &mut self,
         room_id: &str,
@@ -89,20 +304,23 @@ impl RoomManager {
         let had_state = room.state.is_some();
         let before_len = room.players.len();
         room.players.retain(|p| p.id != user_id);
+        room.bots.remove(&user_id);
         let player_count = room.players.len();
+        let humans_left = room.players.iter().any(|p| !room.bots.contains_key(&p.id));
         let removed = player_count < before_len;
-        let game_interrupted = had_state && removed && player_count < 3;
+        let game_interrupted = had_state && removed && player_count < room.capacity();
 
         if game_interrupted {
             room.state = None;
+            room.undo_request = None;
         }
 
-        if player_count == 0 {
+        if !humans_left {
             self.rooms.remove(room_id);
             return Some(RemoveConnectionResult {
                 room_deleted: true,
                 game_interrupted,
-                player_count: 0,
+                player_count,
             });
         }
 
@@ -115,28 +333,89 @@ impl RoomManager {
 
     pub fn start_if_ready(&mut self, room_id: &str, seed: u64) -> Result<(), RoomError> {
         let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
-        if room.players.len() < 3 {
+        if room.players.len() < room.capacity() {
             return Err(RoomError::NotReady);
         }
         if room.state.is_some() {
             return Ok(());
         }
-        let player_ids = [room.players[0].id, room.players[1].id, room.players[2].id];
-        room.state = Some(GameState::new(player_ids, seed));
+        room.state = Some(GameState::with_rules(&room.player_ids(), seed, room.rules));
         Ok(())
     }
 
+    pub fn bid(&mut self, room_id: &str, player_id: u64, value: u8) -> Result<(), RoomError> {
+        let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
+        let state = room.state.as_mut().ok_or(RoomError::NotReady)?;
+        let player_idx = state.player_index(player_id).ok_or(RoomError::NotFound)?;
+        state.bid(player_idx, value).map_err(map_game_error)?;
+        Ok(())
+    }
+
+    pub fn rob(&mut self, room_id: &str, player_id: u64, take: bool) -> Result<(), RoomError> {
+        let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
+        let state = room.state.as_mut().ok_or(RoomError::NotReady)?;
+        let player_idx = state.player_index(player_id).ok_or(RoomError::NotFound)?;
+        state.rob(player_idx, take).map_err(map_game_error)?;
+        Ok(())
+    }
+
+    pub fn double(&mut self, room_id: &str, player_id: u64, factor: u32) -> Result<(), RoomError> {
+        let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
+        let state = room.state.as_mut().ok_or(RoomError::NotReady)?;
+        let player_idx = state.player_index(player_id).ok_or(RoomError::NotFound)?;
+        state.double(player_idx, factor).map_err(map_game_error)?;
+        Ok(())
+    }
+
+    pub fn show_hand(&mut self, room_id: &str, player_id: u64) -> Result<u32, RoomError> {
+        let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
+        let state = room.state.as_mut().ok_or(RoomError::NotReady)?;
+        let player_idx = state.player_index(player_id).ok_or(RoomError::NotFound)?;
+        state.show_hand(player_idx).map_err(map_game_error)
+    }
+
+    /// Ranked hints for a player's hand against the current lead.
+    pub fn hints_for(&self, room_id: &str, player_id: u64) -> Result<Vec<HintView>, RoomError> {
+        let room = self.rooms.get(room_id).ok_or(RoomError::NotFound)?;
+        let state = room.state.as_ref().ok_or(RoomError::NotReady)?;
+        let player_idx = state.player_index(player_id).ok_or(RoomError::NotFound)?;
+        match state.phase {
+            GamePhase::Playing => {}
+            GamePhase::Finished => return Err(RoomError::GameOver),
+            _ => return Err(RoomError::WrongPhase),
+        }
+        Ok(state
+            .hints(player_idx)
+            .iter()
+            .map(|hint| HintView {
+                cards: hint.cards.iter().map(|c| c.code()).collect(),
+                play: play_to_view(&hint.play),
+            })
+            .collect())
+    }
+
     pub fn apply_play(
         &mut self,
         room_id: &str,
         player_id: u64,
         cards: Vec<game_core::Card>,
+    ) -> Result<Option<u64>, RoomError> {
+        self.apply_play_as(room_id, player_id, cards, None)
+    }
+
+    /// Like `apply_play`, reading the cards as `kind` when one is given.
+    pub fn apply_play_as(
+        &mut self,
+        room_id: &str,
+        player_id: u64,
+        cards: Vec<game_core::Card>,
+        kind: Option<PlayKind>,
     ) -> Result<Option<u64>, RoomError> {
         let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
         let state = room.state.as_mut().ok_or(RoomError::NotReady)?;
         let player_idx = state.player_index(player_id).ok_or(RoomError::NotFound)?;
         let outcome = state
-            .apply_play(player_idx, cards)
+            .apply_play_as(player_idx, cards, kind)
             .map_err(map_game_error)?;
         let winner_id = outcome.winner.map(|idx| state.players[idx].id);
         Ok(winner_id)
@@ -157,32 +436,95 @@ impl RoomManager {
         seed: u64,
     ) -> Result<(), RoomError> {
         let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
-        if room.players.len() < 3 {
+        if room.players.len() < room.capacity() {
             return Err(RoomError::NotReady);
         }
         if !room.players.iter().any(|player| player.id == requester_id) {
             return Err(RoomError::RestartNotAllowed);
         }
         let previous_state = room.state.as_ref().ok_or(RoomError::NotReady)?;
-        if !previous_state.players.iter().any(|player| player.out) {
+        if previous_state.phase != GamePhase::Finished {
             return Err(RoomError::RestartNotAllowed);
         }
-        let player_ids = [room.players[0].id, room.players[1].id, room.players[2].id];
-        room.state = Some(GameState::new(player_ids, seed));
+        let first_bidder = (previous_state.bidding.first_bidder() + 1) % room.capacity();
+        room.state = Some(GameState::with_first_bidder(
+            &room.player_ids(),
+            seed,
+            room.rules,
+            first_bidder,
+        ));
+        room.undo_request = None;
         Ok(())
     }
 
+    /// Records the player's wish to take back the last action in a practice
+    /// room, and takes it back once every human player has asked.
+    pub fn request_undo(&mut self, room_id: &str, player_id: u64) -> Result<UndoVote, RoomError> {
+        let room = self.rooms.get_mut(room_id).ok_or(RoomError::NotFound)?;
+        if !room.practice {
+            return Err(RoomError::UndoNotAllowed);
+        }
+        let humans = room.human_ids();
+        let state = room.state.as_mut().ok_or(RoomError::NotReady)?;
+        if state.player_index(player_id).is_none() {
+            return Err(RoomError::NotFound);
+        }
+        if state.events.is_empty() {
+            return Err(RoomError::NothingToUndo);
+        }
+        let events = state.events.len();
+        let request = match &mut room.undo_request {
+            Some(request) if request.events == events => request,
+            slot => slot.insert(UndoRequest {
+                events,
+                agreed: Vec::new(),
+            }),
+        };
+        if !request.agreed.contains(&player_id) {
+            request.agreed.push(player_id);
+        }
+        if humans.iter().any(|id| !request.agreed.contains(id)) {
+            return Ok(UndoVote::Pending {
+                agreed: request.agreed.clone(),
+                needed: humans.len(),
+            });
+        }
+        room.undo_request = None;
+        // Bots' replies go too, so the action taken back is a human's.
+        loop {
+            let undone = state.undo().map_err(map_game_error)?;
+            let by_bot = room.bots.contains_key(&state.players[undone.seat].id);
+            if !by_bot || state.events.is_empty() {
+                break;
+            }
+        }
+        Ok(UndoVote::Undone)
+    }
+
     pub fn snapshot_for(&self, room_id: &str, player_id: u64) -> Option<RoomSnapshot> {
         let room = self.rooms.get(room_id)?;
         let state = room.state.as_ref()?;
         let players = state
             .players
             .iter()
-            .map(|p| PlayerInfo {
+            .enumerate()
+            .map(|(idx, p)| PlayerInfo {
                 id: p.id,
                 name: display_name_for_user(p.id),
                 hand_count: p.hand.len(),
-                is_landlord: state.player_index(p.id) == Some(state.landlord),
+                is_landlord: state.landlord == Some(idx),
+                bid: match &state.bidding {
+                    Bidding::Points(bidding) => bidding.bids[idx],
+                    Bidding::Rob(_) => None,
+                },
+                rob: match &state.bidding {
+                    Bidding::Points(_) => None,
+                    Bidding::Rob(bidding) => bidding.decision(idx),
+                },
+                double: state.doubling.factors.get(idx).copied().flatten(),
+                multiplier: state.player_multiplier(idx),
+                shown_hand: state.shown[idx].map(|_| p.hand.iter().map(|c| c.code()).collect()),
+                bot: room.bots.get(&p.id).cloned(),
             })
             .collect();
         let your_hand = state
@@ -192,8 +534,23 @@ impl RoomManager {
             .map(|p| p.hand.iter().map(|c| c.code()).collect())
             .unwrap_or_default();
         let last_play = state.last_play.as_ref().map(play_to_view);
+        let bottom = if state.landlord.is_some() {
+            state.bottom.iter().map(|c| c.code()).collect()
+        } else {
+            Vec::new()
+        };
         Some(RoomSnapshot {
             room_id: room_id.to_string(),
+            phase: format!("{:?}", state.phase),
+            bidding_mode: format!("{:?}", state.rules.bidding),
+            variant: format!("{:?}", state.rules.variant),
+            preset: room.preset,
+            rules: state.rules,
+            practice: room.practice,
+            current_bid: state.bidding.current_bid(),
+            multiplier: state.current_multiplier(),
+            wild_rank: state.wild_rank.map(|rank| format!("{:?}", rank)),
+            bottom,
             players,
             turn: state.players[state.turn].id,
             last_player: state.last_player.map(|idx| state.players[idx].id),
@@ -202,6 +559,53 @@ impl RoomManager {
         })
     }
 
+    pub fn settlement_for(&self, room_id: &str) -> Option<SettlementView> {
+        let state = self.rooms.get(room_id)?.state.as_ref()?;
+        let settlement = state.settlement()?;
+        Some(SettlementView {
+            landlord_id: state.players[settlement.landlord].id,
+            landlord_won: settlement.landlord_won,
+            base_score: settlement.base_score,
+            bid: settlement.bid,
+            bombs: settlement.bombs,
+            spring: format!("{:?}", settlement.spring),
+            multiplier: settlement.multiplier,
+            scores: state
+                .players
+                .iter()
+                .zip(settlement.deltas.iter().zip(settlement.doubles.iter()))
+                .map(|(player, (delta, double))| ScoreView {
+                    player_id: player.id,
+                    double: *double,
+                    delta: *delta,
+                })
+                .collect(),
+        })
+    }
+
+    pub fn result_for(&self, room_id: &str) -> Option<GameResultView> {
+        let state = self.rooms.get(room_id)?.state.as_ref()?;
+        let result = state.result()?;
+        Some(GameResultView {
+            winning_side: result.winning_side,
+            first_out_id: state.players[result.first_out].id,
+            landlord_plays: result.plays_by(Side::Landlord),
+            farmer_plays: result.plays_by(Side::Farmers),
+            players: result
+                .players
+                .iter()
+                .map(|player| PlayerResultView {
+                    player_id: player.id,
+                    side: player.side,
+                    won: player.won,
+                    remaining: player.remaining.iter().map(|c| c.code()).collect(),
+                    plays: player.plays,
+                    delta: player.delta,
+                })
+                .collect(),
+        })
+    }
+
     pub fn room_connections(&self, room_id: &str) -> Option<Vec<PlayerConn>> {
         self.rooms.get(room_id).map(|room| room.players.clone())
     }
@@ -233,7 +637,11 @@ impl RoomManager {
                 room_id: room_id.clone(),
                 player_count: room.players.len(),
                 started: room.state.is_some(),
-                can_join: room.players.len() < 3,
+                capacity: room.capacity(),
+                can_join: room.players.len() < room.capacity(),
+                bidding_mode: format!("{:?}", room.rules.bidding),
+                variant: format!("{:?}", room.rules.variant),
+                preset: room.preset,
             })
             .collect::<Vec<_>>();
         rooms.sort_by(|a, b| a.room_id.cmp(&b.room_id));
@@ -253,6 +661,14 @@ fn play_to_view(play: &Play) -> PlayView {
         kind: format!("{:?}", play.kind),
         main_rank: format!("{:?}", play.main_rank),
         size: play.size,
+        substitutions: play
+            .substitutions
+            .iter()
+            .map(|sub| SubstitutionView {
+                card: sub.card.code(),
+                as_rank: format!("{:?}", sub.rank),
+            })
+            .collect(),
     }
 }
 
@@ -264,6 +680,12 @@ fn map_game_error(err: GameError) -> RoomError {
         GameError::CardsNotOwned => RoomError::CardsNotOwned,
         GameError::GameOver => RoomError::GameOver,
         GameError::CannotPass => RoomError::CannotPass,
+        GameError::InvalidBid => RoomError::InvalidBid,
+        GameError::WrongPhase => RoomError::WrongPhase,
+        GameError::WrongBiddingMode => RoomError::WrongBiddingMode,
+        GameError::InvalidDouble => RoomError::InvalidDouble,
+        GameError::CannotShowHand => RoomError::CannotShowHand,
+        GameError::NothingToUndo | GameError::IncompleteLog => RoomError::NothingToUndo,
     }
 }
 
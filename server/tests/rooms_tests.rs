@@ -1,4 +1,6 @@
-use server::rooms::{PlayerConn, RoomError, RoomManager};
+use game_core::{Action, BiddingMode, PlayKind, RuleError, RulePreset, RuleSet, Side, Variant};
+use server::protocol::ClientMessage;
+use server::rooms::{BotTurn, PlayerConn, RoomError, RoomManager, UndoVote};
 use std::collections::HashSet;
 
 fn join_three(manager: &mut RoomManager, room_id: &str) -> [u64; 3] {
@@ -18,6 +20,12 @@ fn current_turn(manager: &RoomManager, room_id: &str, player_id: u64) -> u64 {
         .unwrap()
 }
 
+fn finish_bidding(manager: &mut RoomManager, room_id: &str, observer: u64) -> u64 {
+    let bidder = current_turn(manager, room_id, observer);
+    manager.bid(room_id, bidder, 3).unwrap();
+    bidder
+}
+
 fn hand_for(manager: &RoomManager, room_id: &str, player_id: u64) -> Vec<String> {
     manager
         .snapshot_for(room_id, player_id)
@@ -162,6 +170,7 @@ fn apply_play_updates_state() {
     let room_id = manager.create_room();
     let ids = join_three(&mut manager, &room_id);
     manager.start_if_ready(&room_id, 77).unwrap();
+    finish_bidding(&mut manager, &room_id, ids[0]);
     let turn = current_turn(&manager, &room_id, ids[0]);
     let hand = hand_for(&manager, &room_id, turn);
     let card_code = hand[0].clone();
@@ -176,6 +185,7 @@ fn pass_requires_previous_play() {
     let room_id = manager.create_room();
     let ids = join_three(&mut manager, &room_id);
     manager.start_if_ready(&room_id, 88).unwrap();
+    finish_bidding(&mut manager, &room_id, ids[0]);
     let turn = current_turn(&manager, &room_id, ids[0]);
     let result = manager.pass_turn(&room_id, turn);
     assert_eq!(result.err(), Some(RoomError::CannotPass));
@@ -219,6 +229,7 @@ fn apply_play_rejects_invalid_cards() {
     let room_id = manager.create_room();
     let ids = join_three(&mut manager, &room_id);
     manager.start_if_ready(&room_id, 99).unwrap();
+    finish_bidding(&mut manager, &room_id, ids[0]);
     let turn = current_turn(&manager, &room_id, ids[0]);
     let hand = hand_for(&manager, &room_id, turn);
     let hand_set: HashSet<String> = hand.into_iter().collect();
@@ -238,6 +249,7 @@ fn full_game_simulation_reaches_game_over() {
     let room_id = manager.create_room();
     let ids = join_three(&mut manager, &room_id);
     manager.start_if_ready(&room_id, 2026).unwrap();
+    finish_bidding(&mut manager, &room_id, ids[0]);
 
     let winner_id = simulate_until_game_over(&mut manager, &room_id, ids[0]);
     let winner_snapshot = manager.snapshot_for(&room_id, winner_id).unwrap();
@@ -245,18 +257,790 @@ fn full_game_simulation_reaches_game_over() {
     assert_eq!(winner_snapshot.your_hand.len(), 0);
 }
 
+#[test]
+fn settlement_reports_zero_sum_scores_after_game_over() {
+    let mut manager = RoomManager::with_seed(20);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 2029).unwrap();
+    let landlord = finish_bidding(&mut manager, &room_id, ids[0]);
+    assert!(manager.settlement_for(&room_id).is_none());
+
+    let winner_id = simulate_until_game_over(&mut manager, &room_id, ids[0]);
+    let settlement = manager.settlement_for(&room_id).unwrap();
+    assert_eq!(settlement.landlord_id, landlord);
+    assert_eq!(settlement.landlord_won, winner_id == landlord);
+    assert_eq!(settlement.bid, 3);
+    assert_eq!(settlement.scores.len(), 3);
+    assert_eq!(settlement.scores.iter().map(|s| s.delta).sum::<i64>(), 0);
+    let landlord_delta = settlement
+        .scores
+        .iter()
+        .find(|s| s.player_id == landlord)
+        .map(|s| s.delta)
+        .unwrap();
+    let expected = 2 * 3 * i64::from(settlement.multiplier);
+    if settlement.landlord_won {
+        assert_eq!(landlord_delta, expected);
+    } else {
+        assert_eq!(landlord_delta, -expected);
+    }
+}
+
 #[test]
 fn restart_game_resets_round_after_game_over() {
     let mut manager = RoomManager::with_seed(13);
     let room_id = manager.create_room();
     let ids = join_three(&mut manager, &room_id);
     manager.start_if_ready(&room_id, 2027).unwrap();
+    finish_bidding(&mut manager, &room_id, ids[0]);
 
     let _winner = simulate_until_game_over(&mut manager, &room_id, ids[0]);
     manager.restart_game(&room_id, ids[0], 2028).unwrap();
+    let snapshot = manager.snapshot_for(&room_id, ids[0]).unwrap();
+    assert_eq!(snapshot.phase, "Bidding");
 
     for id in ids {
         let snapshot = manager.snapshot_for(&room_id, id).unwrap();
         assert!(!snapshot.your_hand.is_empty());
     }
 }
+
+#[test]
+fn snapshot_reports_bidding_phase() {
+    let mut manager = RoomManager::with_seed(14);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 303).unwrap();
+    let snapshot = manager.snapshot_for(&room_id, ids[0]).unwrap();
+    assert_eq!(snapshot.phase, "Bidding");
+    assert_eq!(snapshot.current_bid, 0);
+    assert!(snapshot.bottom.is_empty());
+    assert!(snapshot.players.iter().all(|p| !p.is_landlord));
+}
+
+#[test]
+fn bidding_assigns_landlord_and_reveals_bottom() {
+    let mut manager = RoomManager::with_seed(15);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 304).unwrap();
+    let landlord = finish_bidding(&mut manager, &room_id, ids[0]);
+
+    let snapshot = manager.snapshot_for(&room_id, landlord).unwrap();
+    assert_eq!(snapshot.phase, "Playing");
+    assert_eq!(snapshot.current_bid, 3);
+    assert_eq!(snapshot.turn, landlord);
+    assert_eq!(snapshot.bottom.len(), 3);
+    assert_eq!(snapshot.your_hand.len(), 20);
+    let info = snapshot.players.iter().find(|p| p.id == landlord).unwrap();
+    assert!(info.is_landlord);
+    assert_eq!(info.bid, Some(3));
+}
+
+#[test]
+fn bid_rejects_out_of_turn_and_low_bids() {
+    let mut manager = RoomManager::with_seed(16);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 305).unwrap();
+    let bidder = current_turn(&manager, &room_id, ids[0]);
+    let other = ids.iter().copied().find(|id| *id != bidder).unwrap();
+    assert_eq!(
+        manager.bid(&room_id, other, 1).err(),
+        Some(RoomError::NotYourTurn)
+    );
+    manager.bid(&room_id, bidder, 2).unwrap();
+    let next = current_turn(&manager, &room_id, ids[0]);
+    assert_eq!(
+        manager.bid(&room_id, next, 1).err(),
+        Some(RoomError::InvalidBid)
+    );
+}
+
+#[test]
+fn play_rejected_while_bidding() {
+    let mut manager = RoomManager::with_seed(17);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 306).unwrap();
+    let turn = current_turn(&manager, &room_id, ids[0]);
+    let hand = hand_for(&manager, &room_id, turn);
+    let card = game_core::Card::from_code(&hand[0]).unwrap();
+    let result = manager.apply_play(&room_id, turn, vec![card]);
+    assert_eq!(result.err(), Some(RoomError::WrongPhase));
+}
+
+#[test]
+fn custom_rules_that_cannot_be_played_are_rejected() {
+    let mut manager = RoomManager::with_seed(17);
+    let rules: RuleSet = serde_json::from_str(r#"{"enabled_kinds":[]}"#).unwrap();
+    assert_eq!(
+        manager.create_room_with(rules),
+        Err(RoomError::InvalidRules(RuleError::SingleDisabled))
+    );
+    let rules = RuleSet {
+        min_straight: 0,
+        ..RuleSet::default()
+    };
+    assert!(manager.create_room_with(rules).is_err());
+    assert!(manager.room_ids().is_empty());
+}
+
+fn rob_room(manager: &mut RoomManager) -> String {
+    manager
+        .create_room_with(RuleSet {
+            bidding: BiddingMode::Rob,
+            ..RuleSet::default()
+        })
+        .unwrap()
+}
+
+#[test]
+fn rob_room_doubles_multiplier_per_rob() {
+    let mut manager = RoomManager::with_seed(18);
+    let room_id = rob_room(&mut manager);
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 307).unwrap();
+
+    let caller = current_turn(&manager, &room_id, ids[0]);
+    manager.rob(&room_id, caller, true).unwrap();
+    let robber = current_turn(&manager, &room_id, ids[0]);
+    manager.rob(&room_id, robber, true).unwrap();
+    let third = current_turn(&manager, &room_id, ids[0]);
+    manager.rob(&room_id, third, false).unwrap();
+    assert_eq!(current_turn(&manager, &room_id, ids[0]), caller);
+    manager.rob(&room_id, caller, false).unwrap();
+
+    let snapshot = manager.snapshot_for(&room_id, robber).unwrap();
+    assert_eq!(snapshot.bidding_mode, "Rob");
+    assert_eq!(snapshot.phase, "Playing");
+    assert_eq!(snapshot.multiplier, 2);
+    assert_eq!(snapshot.turn, robber);
+    assert_eq!(snapshot.your_hand.len(), 20);
+    let info = snapshot.players.iter().find(|p| p.id == robber).unwrap();
+    assert!(info.is_landlord);
+    assert_eq!(info.rob, Some(true));
+}
+
+#[test]
+fn rob_room_rejects_out_of_turn_rob_and_point_bids() {
+    let mut manager = RoomManager::with_seed(19);
+    let room_id = rob_room(&mut manager);
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 308).unwrap();
+
+    let caller = current_turn(&manager, &room_id, ids[0]);
+    let other = ids.iter().copied().find(|id| *id != caller).unwrap();
+    assert_eq!(
+        manager.rob(&room_id, other, true).err(),
+        Some(RoomError::NotYourTurn)
+    );
+    assert_eq!(
+        manager.bid(&room_id, caller, 1).err(),
+        Some(RoomError::WrongBiddingMode)
+    );
+}
+
+#[test]
+fn create_room_message_accepts_optional_config() {
+    let plain: ClientMessage = serde_json::from_str(r#"{"type":"CreateRoom"}"#).unwrap();
+    assert!(matches!(plain, ClientMessage::CreateRoom(None)));
+    let rob: ClientMessage =
+        serde_json::from_str(r#"{"type":"CreateRoom","data":{"bidding":"Rob"}}"#).unwrap();
+    match rob {
+        ClientMessage::CreateRoom(Some(config)) => {
+            assert_eq!(config.rules.bidding, BiddingMode::Rob)
+        }
+        other => panic!("unexpected message: {other:?}"),
+    }
+}
+
+#[test]
+fn laizi_room_reports_wild_rank() {
+    let mut manager = RoomManager::with_seed(21);
+    let classic = manager.create_room();
+    join_three(&mut manager, &classic);
+    manager.start_if_ready(&classic, 309).unwrap();
+    let snapshot = manager.snapshot_for(&classic, 10).unwrap();
+    assert_eq!(snapshot.wild_rank, None);
+
+    let room_id = manager
+        .create_room_with(RuleSet {
+            laizi: true,
+            ..RuleSet::default()
+        })
+        .unwrap();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 310).unwrap();
+    let snapshot = manager.snapshot_for(&room_id, ids[0]).unwrap();
+    let wild = snapshot.wild_rank.unwrap();
+    assert!(wild != "BlackJoker" && wild != "RedJoker");
+}
+
+#[test]
+fn four_player_room_waits_for_fourth_seat() {
+    let mut manager = RoomManager::with_seed(22);
+    let room_id = manager
+        .create_room_with(RuleSet {
+            variant: Variant::FourPlayer,
+            ..RuleSet::default()
+        })
+        .unwrap();
+    join_three(&mut manager, &room_id);
+    assert_eq!(
+        manager.start_if_ready(&room_id, 311).err(),
+        Some(RoomError::NotReady)
+    );
+    let summary = &manager.room_summaries()[0];
+    assert_eq!(summary.capacity, 4);
+    assert!(summary.can_join);
+
+    manager
+        .join_room(&room_id, PlayerConn { id: 13, tx: None })
+        .unwrap();
+    assert_eq!(
+        manager
+            .join_room(&room_id, PlayerConn { id: 14, tx: None })
+            .err(),
+        Some(RoomError::Full)
+    );
+    manager.start_if_ready(&room_id, 311).unwrap();
+    let snapshot = manager.snapshot_for(&room_id, 13).unwrap();
+    assert_eq!(snapshot.variant, "FourPlayer");
+    assert_eq!(snapshot.players.len(), 4);
+    assert_eq!(snapshot.your_hand.len(), 25);
+
+    let landlord = finish_bidding(&mut manager, &room_id, 13);
+    assert_eq!(hand_for(&manager, &room_id, landlord).len(), 33);
+    let result = manager.remove_connection(&room_id, 13).unwrap();
+    assert!(result.game_interrupted);
+}
+
+#[test]
+fn create_room_message_accepts_variant() {
+    let message: ClientMessage =
+        serde_json::from_str(r#"{"type":"CreateRoom","data":{"variant":"FourPlayer"}}"#).unwrap();
+    match message {
+        ClientMessage::CreateRoom(Some(config)) => {
+            assert_eq!(config.rules.variant, Variant::FourPlayer)
+        }
+        other => panic!("unexpected message: {other:?}"),
+    }
+}
+
+#[test]
+fn two_player_room_starts_with_two_seats() {
+    let mut manager = RoomManager::with_seed(23);
+    let room_id = manager
+        .create_room_with(RuleSet {
+            variant: Variant::TwoPlayer,
+            ..RuleSet::default()
+        })
+        .unwrap();
+    for id in [10u64, 11] {
+        manager
+            .join_room(&room_id, PlayerConn { id, tx: None })
+            .unwrap();
+    }
+    assert_eq!(
+        manager
+            .join_room(&room_id, PlayerConn { id: 12, tx: None })
+            .err(),
+        Some(RoomError::Full)
+    );
+    manager.start_if_ready(&room_id, 312).unwrap();
+    let snapshot = manager.snapshot_for(&room_id, 10).unwrap();
+    assert_eq!(snapshot.variant, "TwoPlayer");
+    assert_eq!(snapshot.players.len(), 2);
+    assert_eq!(snapshot.your_hand.len(), 17);
+    assert!(snapshot.bottom.is_empty());
+}
+
+#[test]
+fn preset_room_echoes_rules_in_snapshot() {
+    let mut manager = RoomManager::with_seed(24);
+    let room_id = manager.create_room_with_preset(RulePreset::Happy);
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 313).unwrap();
+    let snapshot = manager.snapshot_for(&room_id, ids[0]).unwrap();
+    assert_eq!(snapshot.preset, Some(RulePreset::Happy));
+    assert_eq!(snapshot.rules, RulePreset::Happy.rules());
+    assert_eq!(snapshot.bidding_mode, "Rob");
+    assert_eq!(manager.room_summaries()[0].preset, Some(RulePreset::Happy));
+}
+
+#[test]
+fn create_room_message_accepts_preset_or_custom_rules() {
+    let preset: ClientMessage =
+        serde_json::from_str(r#"{"type":"CreateRoom","data":{"preset":"Strict"}}"#).unwrap();
+    match preset {
+        ClientMessage::CreateRoom(Some(config)) => {
+            assert_eq!(config.preset, Some(RulePreset::Strict))
+        }
+        other => panic!("unexpected message: {other:?}"),
+    }
+
+    let custom: ClientMessage = serde_json::from_str(
+        r#"{"type":"CreateRoom","data":{"min_straight":6,"enabled_kinds":["Single","Pair","Bomb","Rocket"]}}"#,
+    )
+    .unwrap();
+    match custom {
+        ClientMessage::CreateRoom(Some(config)) => {
+            assert_eq!(config.preset, None);
+            assert_eq!(config.rules.min_straight, 6);
+            assert!(config.rules.allows(PlayKind::Bomb));
+            assert!(!config.rules.allows(PlayKind::Straight));
+        }
+        other => panic!("unexpected message: {other:?}"),
+    }
+}
+
+#[test]
+fn play_message_accepts_optional_kind() {
+    let plain: ClientMessage =
+        serde_json::from_str(r#"{"type":"Play","data":{"cards":["3C"]}}"#).unwrap();
+    assert!(matches!(plain, ClientMessage::Play { kind: None, .. }));
+    let intended: ClientMessage =
+        serde_json::from_str(r#"{"type":"Play","data":{"cards":["3C"],"kind":"AirplaneSingles"}}"#)
+            .unwrap();
+    assert!(matches!(
+        intended,
+        ClientMessage::Play {
+            kind: Some(PlayKind::AirplaneSingles),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn apply_play_as_rejects_mismatched_kind() {
+    let mut manager = RoomManager::with_seed(25);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 314).unwrap();
+    let landlord = finish_bidding(&mut manager, &room_id, ids[0]);
+    let card = hand_for(&manager, &room_id, landlord)[0].clone();
+    let card = game_core::Card::from_code(&card).unwrap();
+    assert_eq!(
+        manager
+            .apply_play_as(&room_id, landlord, vec![card], Some(PlayKind::Pair))
+            .err(),
+        Some(RoomError::InvalidPlay)
+    );
+    manager
+        .apply_play_as(&room_id, landlord, vec![card], Some(PlayKind::Single))
+        .unwrap();
+}
+
+#[test]
+fn doubling_room_reports_per_player_multipliers() {
+    let mut manager = RoomManager::with_seed(26);
+    let room_id = manager
+        .create_room_with(RuleSet {
+            doubling: true,
+            ..RuleSet::default()
+        })
+        .unwrap();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 315).unwrap();
+    let landlord = finish_bidding(&mut manager, &room_id, ids[0]);
+    let snapshot = manager.snapshot_for(&room_id, landlord).unwrap();
+    assert_eq!(snapshot.phase, "Doubling");
+    let first = snapshot.turn;
+    assert_ne!(first, landlord);
+    assert_eq!(
+        manager.double(&room_id, first, 3).err(),
+        Some(RoomError::InvalidDouble)
+    );
+    manager.double(&room_id, first, 2).unwrap();
+    let second = current_turn(&manager, &room_id, landlord);
+    manager.double(&room_id, second, 1).unwrap();
+    assert_eq!(current_turn(&manager, &room_id, landlord), landlord);
+    manager.double(&room_id, landlord, 2).unwrap();
+
+    let snapshot = manager.snapshot_for(&room_id, landlord).unwrap();
+    assert_eq!(snapshot.phase, "Playing");
+    let info = |id: u64| snapshot.players.iter().find(|p| p.id == id).unwrap();
+    assert_eq!(info(first).double, Some(2));
+    assert_eq!(info(first).multiplier, 4);
+    assert_eq!(info(second).double, Some(1));
+    assert_eq!(info(second).multiplier, 2);
+    assert_eq!(info(landlord).multiplier, 6);
+
+    simulate_until_game_over(&mut manager, &room_id, landlord);
+    let settlement = manager.settlement_for(&room_id).unwrap();
+    let total: i64 = settlement.scores.iter().map(|score| score.delta).sum();
+    assert_eq!(total, 0);
+    let first_score = settlement
+        .scores
+        .iter()
+        .find(|score| score.player_id == first)
+        .unwrap();
+    assert_eq!(first_score.double, 2);
+}
+
+#[test]
+fn double_message_round_trips() {
+    let message: ClientMessage =
+        serde_json::from_str(r#"{"type":"Double","data":{"factor":4}}"#).unwrap();
+    assert!(matches!(message, ClientMessage::Double { factor: 4 }));
+}
+
+#[test]
+fn shown_hand_is_visible_to_everyone() {
+    let mut manager = RoomManager::with_seed(27);
+    let room_id = manager.create_room_with_preset(RulePreset::Happy);
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 316).unwrap();
+    assert_eq!(manager.show_hand(&room_id, ids[1]), Ok(4));
+    assert_eq!(
+        manager.show_hand(&room_id, ids[1]),
+        Err(RoomError::CannotShowHand)
+    );
+
+    let own = manager.snapshot_for(&room_id, ids[1]).unwrap();
+    let other = manager.snapshot_for(&room_id, ids[0]).unwrap();
+    let shown = other
+        .players
+        .iter()
+        .find(|p| p.id == ids[1])
+        .and_then(|p| p.shown_hand.clone());
+    assert_eq!(shown, Some(own.your_hand));
+    assert!(other
+        .players
+        .iter()
+        .filter(|p| p.id != ids[1])
+        .all(|p| p.shown_hand.is_none()));
+    assert_eq!(other.multiplier, 4);
+}
+
+#[test]
+fn show_hand_message_has_no_data() {
+    let message: ClientMessage = serde_json::from_str(r#"{"type":"ShowHand"}"#).unwrap();
+    assert!(matches!(message, ClientMessage::ShowHand));
+}
+
+#[test]
+fn game_result_reports_winning_side_and_remaining_hands() {
+    let mut manager = RoomManager::with_seed(28);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 2030).unwrap();
+    let landlord = finish_bidding(&mut manager, &room_id, ids[0]);
+    assert!(manager.result_for(&room_id).is_none());
+
+    let winner_id = simulate_until_game_over(&mut manager, &room_id, ids[0]);
+    let result = manager.result_for(&room_id).unwrap();
+    assert_eq!(result.first_out_id, winner_id);
+    let expected_side = if winner_id == landlord {
+        Side::Landlord
+    } else {
+        Side::Farmers
+    };
+    assert_eq!(result.winning_side, expected_side);
+    for player in &result.players {
+        assert_eq!(player.won, player.side == expected_side);
+        assert_eq!(player.side == Side::Landlord, player.player_id == landlord);
+        let snapshot = manager.snapshot_for(&room_id, player.player_id).unwrap();
+        assert_eq!(player.remaining, snapshot.your_hand);
+    }
+    let plays: u32 = result.players.iter().map(|p| p.plays).sum();
+    assert_eq!(plays, result.landlord_plays + result.farmer_plays);
+    assert!(result.landlord_plays > 0);
+}
+
+#[test]
+fn hints_are_playable_and_follow_room_rules() {
+    let mut manager = RoomManager::with_seed(29);
+    let room_id = manager.create_room_with_preset(RulePreset::Strict);
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 2031).unwrap();
+    assert_eq!(
+        manager.hints_for(&room_id, ids[0]).err(),
+        Some(RoomError::WrongPhase)
+    );
+    let landlord = finish_bidding(&mut manager, &room_id, ids[0]);
+
+    let hints = manager.hints_for(&room_id, landlord).unwrap();
+    assert!(!hints.is_empty());
+    assert!(
+        hints
+            .iter()
+            .all(|hint| !["Triple", "FourTwoSingle", "FourTwoPair"]
+                .contains(&hint.play.kind.as_str()))
+    );
+    let hint = &hints[0];
+    let cards = hint
+        .cards
+        .iter()
+        .map(|code| game_core::Card::from_code(code).unwrap())
+        .collect();
+    manager.apply_play(&room_id, landlord, cards).unwrap();
+
+    let next = current_turn(&manager, &room_id, landlord);
+    for hint in manager.hints_for(&room_id, next).unwrap() {
+        let cards = hint
+            .cards
+            .iter()
+            .map(|code| game_core::Card::from_code(code).unwrap())
+            .collect();
+        let mut probe = manager.clone();
+        probe.apply_play(&room_id, next, cards).unwrap();
+    }
+}
+
+#[test]
+fn request_hint_cursor_defaults_to_zero() {
+    let message: ClientMessage =
+        serde_json::from_str(r#"{"type":"RequestHint","data":{}}"#).unwrap();
+    assert!(matches!(message, ClientMessage::RequestHint { cursor: 0 }));
+    let message: ClientMessage =
+        serde_json::from_str(r#"{"type":"RequestHint","data":{"cursor":3}}"#).unwrap();
+    assert!(matches!(message, ClientMessage::RequestHint { cursor: 3 }));
+}
+
+#[test]
+fn practice_room_undoes_once_everyone_agrees() {
+    let mut manager = RoomManager::with_seed(31);
+    let room_id = manager.create_room();
+    manager.make_practice(&room_id).unwrap();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 64).unwrap();
+    finish_bidding(&mut manager, &room_id, ids[0]);
+    let before = manager.snapshot_for(&room_id, ids[0]).unwrap();
+    assert!(before.practice);
+
+    let turn = current_turn(&manager, &room_id, ids[0]);
+    let hand = hand_for(&manager, &room_id, turn);
+    let card = game_core::Card::from_code(&hand[0]).unwrap();
+    manager.apply_play(&room_id, turn, vec![card]).unwrap();
+
+    assert_eq!(
+        manager.request_undo(&room_id, ids[0]),
+        Ok(UndoVote::Pending {
+            agreed: vec![ids[0]],
+            needed: 3
+        })
+    );
+    // Asking twice does not count twice.
+    assert!(matches!(
+        manager.request_undo(&room_id, ids[0]),
+        Ok(UndoVote::Pending { .. })
+    ));
+    manager.request_undo(&room_id, ids[1]).unwrap();
+    assert_eq!(manager.request_undo(&room_id, ids[2]), Ok(UndoVote::Undone));
+
+    let after = manager.snapshot_for(&room_id, ids[0]).unwrap();
+    assert_eq!(after.turn, turn);
+    assert!(after.last_play.is_none());
+    assert_eq!(hand_for(&manager, &room_id, turn), hand);
+    assert_eq!(after.your_hand, before.your_hand);
+}
+
+#[test]
+fn undo_needs_practice_room_and_fresh_votes() {
+    let mut manager = RoomManager::with_seed(32);
+    let room_id = manager.create_room();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 65).unwrap();
+    finish_bidding(&mut manager, &room_id, ids[0]);
+    assert_eq!(
+        manager.request_undo(&room_id, ids[0]),
+        Err(RoomError::UndoNotAllowed)
+    );
+
+    manager.make_practice(&room_id).unwrap();
+    manager.request_undo(&room_id, ids[0]).unwrap();
+    manager.request_undo(&room_id, ids[1]).unwrap();
+    // A new action starts the vote over.
+    let turn = current_turn(&manager, &room_id, ids[0]);
+    let hand = hand_for(&manager, &room_id, turn);
+    let card = game_core::Card::from_code(&hand[0]).unwrap();
+    manager.apply_play(&room_id, turn, vec![card]).unwrap();
+    assert_eq!(
+        manager.request_undo(&room_id, ids[2]),
+        Ok(UndoVote::Pending {
+            agreed: vec![ids[2]],
+            needed: 3
+        })
+    );
+    let undo: ClientMessage = serde_json::from_str(r#"{"type":"Undo"}"#).unwrap();
+    assert!(matches!(undo, ClientMessage::Undo));
+    let practice: ClientMessage =
+        serde_json::from_str(r#"{"type":"CreateRoom","data":{"practice":true}}"#).unwrap();
+    assert!(matches!(practice, ClientMessage::CreateRoom(Some(config)) if config.practice));
+}
+
+#[test]
+fn undo_during_bidding_restores_the_bid() {
+    let mut manager = RoomManager::with_seed(33);
+    let room_id = manager.create_room();
+    manager.make_practice(&room_id).unwrap();
+    let ids = join_three(&mut manager, &room_id);
+    manager.start_if_ready(&room_id, 66).unwrap();
+    for id in ids {
+        assert_eq!(
+            manager.request_undo(&room_id, id),
+            Err(RoomError::NothingToUndo)
+        );
+    }
+    let bidder = finish_bidding(&mut manager, &room_id, ids[0]);
+    for id in ids {
+        manager.request_undo(&room_id, id).unwrap();
+    }
+    let snapshot = manager.snapshot_for(&room_id, bidder).unwrap();
+    assert_eq!(snapshot.phase, "Bidding");
+    assert_eq!(snapshot.turn, bidder);
+    assert_eq!(snapshot.your_hand.len(), 17);
+}
+
+/// A room with one human (id 10) and two bots playing `strategy`.
+fn room_with_bots(manager: &mut RoomManager, strategy: &str) -> (String, [u64; 2]) {
+    let room_id = manager.create_room();
+    manager
+        .join_room(&room_id, PlayerConn { id: 10, tx: None })
+        .unwrap();
+    let bots = [
+        manager.add_bot(&room_id, strategy).unwrap(),
+        manager.add_bot(&room_id, strategy).unwrap(),
+    ];
+    (room_id, bots)
+}
+
+fn run_bots(manager: &mut RoomManager, room_id: &str) -> Vec<BotTurn> {
+    let mut turns = Vec::new();
+    while let Some(turn) = manager.play_bot_turn(room_id).unwrap() {
+        turns.push(turn);
+        assert!(turns.len() < 500, "bots kept playing");
+    }
+    turns
+}
+
+#[test]
+fn bots_join_by_strategy_name() {
+    let mut manager = RoomManager::with_seed(34);
+    let (room_id, bots) = room_with_bots(&mut manager, "easy");
+    assert_eq!(manager.add_bot(&room_id, "random"), Err(RoomError::Full));
+    let other = manager.create_room();
+    assert_eq!(
+        manager.add_bot(&other, "grandmaster"),
+        Err(RoomError::UnknownBot)
+    );
+    assert_eq!(manager.room_player_count(&other), Some(0));
+
+    manager.start_if_ready(&room_id, 67).unwrap();
+    let snapshot = manager.snapshot_for(&room_id, 10).unwrap();
+    for player in &snapshot.players {
+        let expected = bots.contains(&player.id).then(|| "easy".to_string());
+        assert_eq!(player.bot, expected);
+    }
+    let message: ClientMessage =
+        serde_json::from_str(r#"{"type":"AddBot","data":{"strategy":"hard"}}"#).unwrap();
+    assert!(matches!(message, ClientMessage::AddBot { strategy } if strategy == "hard"));
+}
+
+#[test]
+fn bot_moves_decided_after_the_game_moved_on_are_dropped() {
+    let mut manager = RoomManager::with_seed(36);
+    let (room_id, _) = room_with_bots(&mut manager, "random");
+    manager.start_if_ready(&room_id, 69).unwrap();
+    if manager.bot_move(&room_id).unwrap().is_none() {
+        manager.bid(&room_id, 10, 0).unwrap();
+    }
+    let bot_move = manager.bot_move(&room_id).unwrap().unwrap();
+    let action = bot_move.decide().unwrap();
+    let turn = manager
+        .apply_bot_move(&room_id, &bot_move, action.clone())
+        .unwrap();
+    assert_eq!(turn.map(|turn| turn.bot_id), Some(bot_move.bot_id));
+    assert_eq!(
+        manager.apply_bot_move(&room_id, &bot_move, action),
+        Ok(None)
+    );
+}
+
+#[test]
+fn refused_bot_moves_leave_the_seat_free_for_a_fallback() {
+    let mut manager = RoomManager::with_seed(37);
+    let (room_id, _) = room_with_bots(&mut manager, "easy");
+    manager.start_if_ready(&room_id, 70).unwrap();
+    if manager.bot_move(&room_id).unwrap().is_none() {
+        manager.bid(&room_id, 10, 0).unwrap();
+    }
+    let bot_move = manager.bot_move(&room_id).unwrap().unwrap();
+    assert!(manager
+        .apply_bot_move(&room_id, &bot_move, Action::Double(7))
+        .is_err());
+    let fallback = bot_move.fallback().unwrap();
+    let turn = manager
+        .apply_bot_move(&room_id, &bot_move, fallback)
+        .unwrap();
+    assert_eq!(turn.map(|turn| turn.bot_id), Some(bot_move.bot_id));
+}
+
+#[test]
+fn bots_play_until_a_human_is_to_move() {
+    let mut manager = RoomManager::with_seed(35);
+    let (room_id, _) = room_with_bots(&mut manager, "easy");
+    manager.start_if_ready(&room_id, 68).unwrap();
+    let mut winner = None;
+    for _ in 0..200 {
+        for turn in run_bots(&mut manager, &room_id) {
+            winner = winner.or(turn.winner_id);
+        }
+        let snapshot = manager.snapshot_for(&room_id, 10).unwrap();
+        if snapshot.phase == "Finished" {
+            break;
+        }
+        assert_eq!(snapshot.turn, 10);
+        if snapshot.phase == "Bidding" {
+            manager.bid(&room_id, 10, 0).unwrap();
+            continue;
+        }
+        let hints = manager.hints_for(&room_id, 10).unwrap();
+        match hints.first() {
+            Some(hint) => {
+                let cards = hint
+                    .cards
+                    .iter()
+                    .map(|code| game_core::Card::from_code(code).unwrap())
+                    .collect();
+                winner = winner.or(manager.apply_play(&room_id, 10, cards).unwrap());
+            }
+            None => manager.pass_turn(&room_id, 10).unwrap(),
+        }
+    }
+    let result = manager.result_for(&room_id).expect("the game finished");
+    assert_eq!(winner, Some(result.first_out_id));
+}
+
+#[test]
+fn undo_with_bots_takes_back_their_replies_too() {
+    let mut manager = RoomManager::with_seed(36);
+    let (room_id, _) = room_with_bots(&mut manager, "easy");
+    manager.make_practice(&room_id).unwrap();
+    manager.start_if_ready(&room_id, 69).unwrap();
+    run_bots(&mut manager, &room_id);
+    if manager.snapshot_for(&room_id, 10).unwrap().phase == "Bidding" {
+        manager.bid(&room_id, 10, 3).unwrap();
+    }
+    run_bots(&mut manager, &room_id);
+    let before = manager.snapshot_for(&room_id, 10).unwrap();
+    assert_eq!(before.turn, 10);
+
+    let hint = manager.hints_for(&room_id, 10).unwrap().remove(0);
+    let cards = hint
+        .cards
+        .iter()
+        .map(|code| game_core::Card::from_code(code).unwrap())
+        .collect();
+    manager.apply_play(&room_id, 10, cards).unwrap();
+    assert!(!run_bots(&mut manager, &room_id).is_empty());
+
+    // The human's vote is the only one needed.
+    assert_eq!(manager.request_undo(&room_id, 10), Ok(UndoVote::Undone));
+    let after = manager.snapshot_for(&room_id, 10).unwrap();
+    assert_eq!(after.turn, 10);
+    assert_eq!(after.your_hand, before.your_hand);
+}
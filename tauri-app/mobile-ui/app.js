@@ -3,6 +3,9 @@ const CARD_ASSET_PATH = "assets/cards";
 const ROOM_LIST_POLL_MS = 500;
 const MAX_LOG_LINES = 24;
 const MAX_RECOMMEND = 5;
+const MAX_BID = 3;
+const DOUBLE_FACTORS = [1, 2, 4];
+const DOUBLE_LABEL = { 2: "加倍", 4: "超级加倍" };
 
 const KIND_LABEL = {
   Single: "单张",
@@ -13,7 +16,11 @@ const KIND_LABEL = {
   Straight: "顺子",
   DoubleStraight: "连对",
   Airplane: "飞机",
+  AirplaneSingles: "飞机带单",
+  AirplanePairs: "飞机带对",
   Bomb: "炸弹",
+  SoftBomb: "软炸",
+  LaiziBomb: "癞子炸",
   Rocket: "王炸",
   FourTwoSingle: "四带二",
   FourTwoPair: "四带两对",
@@ -93,6 +100,9 @@ export const state = {
   roomId: null,
   selectedRoomId: null,
   roomStarted: false,
+  phase: null,
+  currentBid: 0,
+  showHandAllowed: false,
   currentRoomPlayerCount: 0,
   players: [],
   hand: [],
@@ -102,6 +112,8 @@ export const state = {
   roomList: [],
   playerNames: new Map(),
   recommendations: [],
+  hintCursor: null,
+  hintApply: false,
   gameOverWinnerId: null,
   cardDiagEnabled: true,
   serverUrl: DEFAULT_WS_URL,
@@ -275,6 +287,32 @@ function setGameOver(winnerId, source = "server") {
   }
 }
 
+const SIDE_LABEL = { Landlord: "地主", Farmers: "农民" };
+
+export function renderGameResult(container, result) {
+  if (!container) return;
+  container.innerHTML = "";
+  if (!result) return;
+  setText(
+    "gameOverWinner",
+    `${SIDE_LABEL[result.winning_side] || result.winning_side}胜 · ${nameById(result.first_out_id)} 先出完`,
+  );
+  const plays = document.createElement("div");
+  plays.className = "muted";
+  plays.textContent = `出牌手数：地主 ${result.landlord_plays} · 农民 ${result.farmer_plays}`;
+  container.appendChild(plays);
+  (result.players || []).forEach((player) => {
+    const row = document.createElement("div");
+    row.className = `result-row${player.won ? " won" : ""}`;
+    const delta = `${player.delta > 0 ? "+" : ""}${player.delta}`;
+    const remaining = sortHand(player.remaining || [])
+      .map((code) => parseCard(code)?.label || code)
+      .join(" ");
+    row.innerHTML = `<span>${nameById(player.player_id)}（${SIDE_LABEL[player.side] || player.side}）</span><strong>${delta}</strong><span class="result-hand">${remaining || "出完"}</span>`;
+    container.appendChild(row);
+  });
+}
+
 function logCardDiagnostics(reason) {
   if (!state.cardDiagEnabled || typeof document === "undefined" || typeof window === "undefined") return;
   const cards = Array.from(document.querySelectorAll("#hand .card"));
@@ -404,6 +442,9 @@ function resetRoom() {
   state.roomId = null;
   state.selectedRoomId = null;
   state.roomStarted = false;
+  state.phase = null;
+  state.currentBid = 0;
+  state.showHandAllowed = false;
   state.currentRoomPlayerCount = 0;
   state.players = [];
   state.hand = [];
@@ -419,6 +460,7 @@ function resetRoom() {
   setText("lastPlayer", "本轮还没有人出牌");
   syncRoomInput();
   setGameOver(null, "reset");
+  renderGameResult(el("gameOverResults"), null);
 }
 
 function requestRoomList() {
@@ -494,17 +536,24 @@ export function sendMessage(payload) {
 
 function buildSeats(players) {
   if (!players || players.length === 0) {
-    return { left: null, right: null, self: null };
+    return { left: null, right: null, across: null, self: null };
   }
   const selfIndex = players.findIndex((player) => player.id === state.userId);
   const base = selfIndex >= 0 ? selfIndex : 0;
+  const count = players.length;
   return {
     self: players[base] || null,
-    right: players.length > 1 ? players[(base + 1) % players.length] : null,
-    left: players.length > 2 ? players[(base + 2) % players.length] : null,
+    right: count > 1 ? players[(base + 1) % count] : null,
+    across: count > 3 ? players[(base + 2) % count] : null,
+    left: count > 2 ? players[(base + count - 1) % count] : null,
   };
 }
 
+function currentRoomCapacity() {
+  const room = state.roomList.find((item) => item.room_id === state.roomId);
+  return room?.capacity || state.players.length || 3;
+}
+
 function playerLabel(player) {
   return player ? player.name || nameById(player.id) : "玩家";
 }
@@ -541,6 +590,13 @@ function renderOpponentSeat(container, player, turnId, sideLabel) {
     tags.appendChild(landlord);
   }
 
+  if (player.shown_hand) {
+    const shown = document.createElement("span");
+    shown.className = "tag shown";
+    shown.textContent = "明牌";
+    tags.appendChild(shown);
+  }
+
   if (player.id === turnId) {
     const turn = document.createElement("span");
     turn.className = "tag turn";
@@ -550,7 +606,18 @@ function renderOpponentSeat(container, player, turnId, sideLabel) {
 
   const stack = document.createElement("div");
   stack.className = "card-stack";
-  const count = Math.max(2, Math.min(7, Math.ceil(player.hand_count / 3)));
+  if (player.shown_hand) {
+    stack.classList.add("shown-hand");
+    sortHand(player.shown_hand).forEach((code) => {
+      const face = document.createElement("span");
+      const parsed = parseCard(code);
+      face.className = "shown-card";
+      face.dataset.tone = parsed ? parsed.tone : "unknown";
+      face.textContent = parsed ? parsed.label : code;
+      stack.appendChild(face);
+    });
+  }
+  const count = player.shown_hand ? 0 : Math.max(2, Math.min(7, Math.ceil(player.hand_count / 3)));
   for (let i = 0; i < count; i += 1) {
     const back = document.createElement("img");
     back.className = "card-back";
@@ -587,7 +654,7 @@ function updateSelectedCount() {
 
 function classifyRoomStatus(room) {
   if (room.started) return "进行中";
-  if (room.player_count >= 3) return "已满";
+  if (room.player_count >= (room.capacity || 3)) return "已满";
   return "可加入";
 }
 
@@ -611,7 +678,7 @@ export function renderRoomList(container, rooms) {
     if (room.room_id === state.selectedRoomId) item.classList.add("selected");
     if (!room.can_join && room.room_id !== state.roomId) item.classList.add("full");
 
-    item.innerHTML = `<span class="room-id">${room.room_id}</span><span class="room-meta">${room.player_count}/3 · ${classifyRoomStatus(room)}</span>`;
+    item.innerHTML = `<span class="room-id">${room.room_id}</span><span class="room-meta">${room.player_count}/${room.capacity || 3} · ${classifyRoomStatus(room)}</span>`;
 
     item.addEventListener("click", () => {
       state.selectedRoomId = room.room_id;
@@ -628,131 +695,9 @@ export function renderRoomList(container, rooms) {
   });
 }
 
-function groupByRank(handCodes) {
-  const groups = new Map();
-  handCodes
-    .map(parseCard)
-    .filter(Boolean)
-    .forEach((card) => {
-      if (!groups.has(card.rank)) groups.set(card.rank, []);
-      groups.get(card.rank).push(card.code);
-    });
-  return groups;
-}
-
-function windows(values, size) {
-  const result = [];
-  for (let i = 0; i <= values.length - size; i += 1) {
-    const slice = values.slice(i, i + size);
-    let consecutive = true;
-    for (let j = 1; j < slice.length; j += 1) {
-      if (slice[j] !== slice[j - 1] + 1) consecutive = false;
-    }
-    if (consecutive) result.push(slice);
-  }
-  return result;
-}
-
-function beat(prev, next) {
-  if (!prev || !next) return false;
-  if (prev.kind === "Rocket") return false;
-  if (next.kind === "Rocket") return true;
-  if (next.kind === "Bomb" && prev.kind !== "Bomb") return true;
-  if (prev.kind === "Bomb" && next.kind !== "Bomb") return false;
-  if (prev.kind !== next.kind) return false;
-
-  if (["Straight", "DoubleStraight", "Airplane"].includes(prev.kind)) {
-    return prev.size === next.size && next.mainRank > prev.mainRank;
-  }
-  return next.mainRank > prev.mainRank;
-}
-
-function recKey(rec) {
-  return [...rec.codes].sort().join(",");
-}
-
-function buildRecommendations() {
-  if (isGameOver() || !state.hand.length) return [];
-
-  const groups = groupByRank(state.hand);
-  const ranks = [...groups.keys()].sort((a, b) => a - b);
-  const prev = state.lastPlay && state.lastPlayer !== state.userId
-    ? {
-      kind: state.lastPlay.kind,
-      mainRank: rankValue(state.lastPlay.main_rank),
-      size: Number(state.lastPlay.size) || 0,
-    }
-    : null;
-
-  const recs = [];
-  const add = (rec) => {
-    if (!rec || !rec.codes || rec.codes.some((code) => !code)) return;
-    if (recs.find((item) => recKey(item) === recKey(rec))) return;
-    recs.push(rec);
-  };
-
-  const singles = () => ranks.forEach((rank) => add({ kind: "Single", mainRank: rank, size: 1, codes: [groups.get(rank)[0]] }));
-  const pairs = () => ranks.forEach((rank) => {
-    if (groups.get(rank).length >= 2) add({ kind: "Pair", mainRank: rank, size: 2, codes: groups.get(rank).slice(0, 2) });
-  });
-  const triples = () => ranks.forEach((rank) => {
-    if (groups.get(rank).length >= 3) add({ kind: "Triple", mainRank: rank, size: 3, codes: groups.get(rank).slice(0, 3) });
-  });
-  const bombs = () => ranks.forEach((rank) => {
-    if (groups.get(rank).length === 4) add({ kind: "Bomb", mainRank: rank, size: 4, codes: groups.get(rank).slice(0, 4) });
-  });
-  const rocket = () => {
-    if (groups.has(17) && groups.has(18)) {
-      add({ kind: "Rocket", mainRank: 18, size: 2, codes: [groups.get(17)[0], groups.get(18)[0]] });
-    }
-  };
-
-  if (!prev) {
-    singles();
-    pairs();
-    triples();
-    return recs.slice(0, MAX_RECOMMEND);
-  }
-
-  if (prev.kind === "Single") singles();
-  if (prev.kind === "Pair") pairs();
-  if (prev.kind === "Triple") triples();
-  if (prev.kind === "Bomb") bombs();
-  if (prev.kind !== "Rocket") {
-    bombs();
-    rocket();
-  }
-
-  if (prev.kind === "Straight" || prev.kind === "DoubleStraight" || prev.kind === "Airplane") {
-    const need = prev.kind === "Straight" ? 1 : prev.kind === "DoubleStraight" ? 2 : 3;
-    const chain = ranks.filter((rank) => rank >= 3 && rank <= 14 && groups.get(rank).length >= need);
-    windows(chain, prev.size).forEach((windowRanks) => {
-      const codes = windowRanks.flatMap((rank) => groups.get(rank).slice(0, need));
-      add({ kind: prev.kind, mainRank: windowRanks[windowRanks.length - 1], size: prev.size, codes });
-    });
-    if (prev.kind !== "Rocket") {
-      bombs();
-      rocket();
-    }
-  }
-
-  const filtered = recs.filter((rec) => beat(prev, rec));
-  filtered.sort((a, b) => {
-    const score = (kind) => {
-      if (kind === prev.kind) return 0;
-      if (kind === "Bomb") return 1;
-      if (kind === "Rocket") return 2;
-      return 3;
-    };
-    return score(a.kind) - score(b.kind) || a.mainRank - b.mainRank;
-  });
-
-  return filtered.slice(0, MAX_RECOMMEND);
-}
-
 function recLabel(rec) {
   const kind = KIND_LABEL[rec.kind] || rec.kind;
-  const rank = rankLabel(rec.mainRank);
+  const rank = rankToLabel(rec.mainRank);
   const brief = rec.codes.slice(0, 4).map((code) => parseCard(code)?.label || code).join(" ");
   return `${kind}(${rank}) ${brief}`;
 }
@@ -781,7 +726,8 @@ function renderRecommendations() {
   }
 
   const selected = selectedCardsSet();
-  recs.forEach((rec) => {
+  const first = state.hintCursor !== null && state.hintCursor >= MAX_RECOMMEND ? state.hintCursor : 0;
+  recs.slice(first, first + MAX_RECOMMEND).forEach((rec, offset) => {
     const btn = document.createElement("button");
     btn.type = "button";
     btn.className = "recommend-chip";
@@ -790,6 +736,7 @@ function renderRecommendations() {
       btn.classList.add("active");
     }
     btn.addEventListener("click", () => {
+      state.hintCursor = first + offset;
       applyRec(rec.codes);
       renderRecommendations();
     });
@@ -797,8 +744,45 @@ function renderRecommendations() {
   });
 }
 
+function isBidding() {
+  return state.phase === "Bidding";
+}
+
+function isDoubling() {
+  return state.phase === "Doubling";
+}
+
+// Hints come from the server so they follow the room's rules; a fresh list is
+// fetched whenever the table changes and shown without touching the selection.
 function refreshRecommendations() {
-  state.recommendations = buildRecommendations();
+  state.recommendations = [];
+  state.hintCursor = null;
+  state.hintApply = false;
+  const playing = state.phase === "Playing" && state.roomStarted && !isGameOver();
+  if (playing && state.hand.length) {
+    sendMessage({ type: "RequestHint", data: { cursor: 0 } });
+  }
+  renderRecommendations();
+}
+
+// "提示": each press asks for the next hint and selects its cards.
+function requestNextHint() {
+  const cursor = state.hintCursor === null ? 0 : state.hintCursor + 1;
+  state.hintApply = true;
+  sendMessage({ type: "RequestHint", data: { cursor } });
+}
+
+export function applyHints(cursor, hints) {
+  state.recommendations = (hints || []).map((hint) => ({
+    kind: hint.play.kind,
+    mainRank: hint.play.main_rank,
+    codes: hint.cards,
+  }));
+  state.hintCursor = state.recommendations.length ? cursor : null;
+  if (state.hintApply && state.recommendations.length) {
+    applyRec(state.recommendations[cursor].codes);
+  }
+  state.hintApply = false;
   renderRecommendations();
 }
 
@@ -819,8 +803,20 @@ function updateTurnBanner() {
     text = `对局结束，胜者：${nameById(state.gameOverWinnerId)}`;
     tone = "ready";
   } else if (!state.roomStarted) {
-    text = `房间 ${state.roomId} 等待玩家加入（${state.currentRoomPlayerCount}/3）`;
+    text = `房间 ${state.roomId} 等待玩家加入（${state.currentRoomPlayerCount}/${currentRoomCapacity()}）`;
     tone = "ready";
+  } else if (isBidding() && state.turn === state.userId) {
+    text = state.currentBid > 0 ? `轮到你叫分（当前 ${state.currentBid} 分）` : "轮到你叫分";
+    tone = "active";
+  } else if (isBidding() && state.turn) {
+    text = `等待 ${nameById(state.turn)} 叫分`;
+    tone = "wait";
+  } else if (isDoubling() && state.turn === state.userId) {
+    text = "轮到你选择是否加倍";
+    tone = "active";
+  } else if (isDoubling() && state.turn) {
+    text = `等待 ${nameById(state.turn)} 选择加倍`;
+    tone = "wait";
   } else if (state.turn === state.userId) {
     text = "轮到你出牌";
     tone = "active";
@@ -847,8 +843,27 @@ function updateActionState() {
   if (el("createRoomBtn")) el("createRoomBtn").disabled = !state.connected;
   if (el("joinRoomBtn")) el("joinRoomBtn").disabled = !canJoin;
   if (el("refreshRoomsBtn")) el("refreshRoomsBtn").disabled = !state.connected;
-  if (el("playBtn")) el("playBtn").disabled = !myTurn;
-  if (el("passBtn")) el("passBtn").disabled = !myTurn;
+  if (el("playBtn")) el("playBtn").disabled = !myTurn || isBidding() || isDoubling();
+  if (el("passBtn")) el("passBtn").disabled = !myTurn || isBidding() || isDoubling();
+  if (el("hintBtn")) {
+    el("hintBtn").disabled = !state.connected || state.phase !== "Playing" || isGameOver();
+  }
+  if (el("bidActions")) el("bidActions").hidden = !isBidding();
+  for (let value = 0; value <= MAX_BID; value += 1) {
+    const btn = el(`bid${value}Btn`);
+    if (btn) btn.disabled = !myTurn || !isBidding() || (value > 0 && value <= state.currentBid);
+  }
+  if (el("showHandBtn")) {
+    const me = state.players.find((player) => player.id === state.userId);
+    const canShow = isBidding() || (me?.is_landlord && !state.lastPlayer && !isGameOver());
+    el("showHandBtn").hidden = !state.showHandAllowed;
+    el("showHandBtn").disabled = !state.connected || !me || Boolean(me.shown_hand) || !canShow;
+  }
+  if (el("doubleActions")) el("doubleActions").hidden = !isDoubling();
+  DOUBLE_FACTORS.forEach((factor) => {
+    const btn = el(`double${factor}Btn`);
+    if (btn) btn.disabled = !myTurn || !isDoubling();
+  });
   if (el("clearBtn")) el("clearBtn").disabled = state.hand.length === 0 || isGameOver();
   if (el("restartGameBtn")) {
     el("restartGameBtn").disabled = !isGameOver() || !state.connected || !state.roomId;
@@ -869,7 +884,10 @@ export function renderPlayers(container, players, turnId) {
   players.forEach((player) => {
     const row = document.createElement("div");
     row.className = `player${player.id === turnId ? " active" : ""}`;
-    row.innerHTML = `<span>${playerLabel(player)}（${player.is_landlord ? "地主" : "农民"}）</span><span>${player.hand_count}张</span>`;
+    const role = isBidding() ? "叫分中" : player.is_landlord ? "地主" : "农民";
+    const doubled = player.double > 1 ? ` · ${DOUBLE_LABEL[player.double]}` : "";
+    const shown = player.shown_hand ? " · 明牌" : "";
+    row.innerHTML = `<span>${playerLabel(player)}（${role}${doubled}${shown}）</span><span>${player.hand_count}张</span>`;
     container.appendChild(row);
   });
 }
@@ -949,6 +967,9 @@ export function applyRoomState(snapshot) {
   state.players = snapshot.players || [];
   state.hand = sortHand(snapshot.your_hand || []);
   state.turn = snapshot.turn;
+  state.phase = snapshot.phase || "Playing";
+  state.currentBid = Number(snapshot.current_bid) || 0;
+  state.showHandAllowed = Boolean(snapshot.rules?.show_hand);
   state.lastPlay = snapshot.last_play;
   state.lastPlayer = snapshot.last_player;
   state.currentRoomPlayerCount = state.players.length;
@@ -969,9 +990,18 @@ export function applyRoomState(snapshot) {
 
   const seats = buildSeats(state.players);
   renderOpponentSeat(el("leftSeat"), seats.left, state.turn, "左侧");
+  if (el("leftSeat")) el("leftSeat").hidden = state.players.length === 2;
   renderOpponentSeat(el("rightSeat"), seats.right, state.turn, "右侧");
+  const acrossSeat = el("acrossSeat");
+  if (acrossSeat) {
+    acrossSeat.hidden = !seats.across;
+    if (seats.across) {
+      renderOpponentSeat(acrossSeat, seats.across, state.turn, "对面");
+      acrossSeat.classList.add("across-seat");
+    }
+  }
 
-  if (seats.self) {
+  if (seats.self && !isBidding()) {
     setText("myRole", seats.self.is_landlord ? "地主" : "农民");
   }
 
@@ -1036,13 +1066,25 @@ export function handleServerMessage(msg) {
       logMessage(`出牌失败：${msg.data.reason}`);
       break;
 
+    case "Hints":
+      applyHints(msg.data.cursor, msg.data.hints);
+      break;
+
     case "GameOver":
       if (!msg?.data?.room_id || !state.roomId || msg.data.room_id !== state.roomId || !state.roomStarted) {
         logMessage(`忽略无效结束事件：${msg?.data?.room_id || "unknown-room"}`);
         break;
       }
       setGameOver(msg.data.winner_id, "server-message");
+      renderGameResult(el("gameOverResults"), msg.data.result);
       logMessage(`对局结束，赢家：${nameById(msg.data.winner_id)}`);
+      if (msg.data.settlement) {
+        const { multiplier, scores = [] } = msg.data.settlement;
+        const summary = scores
+          .map((score) => `${nameById(score.player_id)} ${score.delta > 0 ? "+" : ""}${score.delta}`)
+          .join("，");
+        logMessage(`结算（${multiplier} 倍）：${summary}`);
+      }
       updateTurnBanner();
       updateActionState();
       refreshRecommendations();
@@ -1082,6 +1124,12 @@ export function handleServerMessage(msg) {
       updateActionState();
       break;
 
+    case "UndoRequested":
+      logMessage(
+        `${nameById(msg.data.requester_id)} 请求悔牌（${msg.data.agreed.length}/${msg.data.needed} 人同意）`
+      );
+      break;
+
     case "Error":
       logMessage(`服务器错误：${msg.data.message}`);
       break;
@@ -1113,7 +1161,8 @@ export function init() {
 
   bindClick("reconnectBtn", () => connect((el("serverUrl")?.value || DEFAULT_WS_URL).trim()));
   bindClick("createRoomBtn", () => {
-    if (sendMessage({ type: "CreateRoom" })) logMessage("正在创建房间...");
+    const preset = el("rulePreset")?.value || "Classic";
+    if (sendMessage({ type: "CreateRoom", data: { preset } })) logMessage("正在创建房间...");
   });
   bindClick("joinRoomBtn", () => {
     if (state.selectedRoomId) joinRoom(state.selectedRoomId);
@@ -1129,6 +1178,14 @@ export function init() {
     if (sendMessage({ type: "Play", data: { cards } })) clearSelection();
   });
   bindClick("passBtn", () => sendMessage({ type: "Pass" }));
+  for (let value = 0; value <= MAX_BID; value += 1) {
+    bindClick(`bid${value}Btn`, () => sendMessage({ type: "Bid", data: { value } }));
+  }
+  DOUBLE_FACTORS.forEach((factor) => {
+    bindClick(`double${factor}Btn`, () => sendMessage({ type: "Double", data: { factor } }));
+  });
+  bindClick("showHandBtn", () => sendMessage({ type: "ShowHand" }));
+  bindClick("hintBtn", requestNextHint);
   bindClick("clearBtn", clearSelection);
   bindClick("restartGameBtn", () => {
     if (!state.connected || !state.roomId) {
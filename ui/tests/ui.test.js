@@ -59,6 +59,8 @@ beforeEach(() => {
   state.serverUrl = "ws://127.0.0.1:33030/ws";
   state.roomList = [];
   state.recommendations = [];
+  state.hintCursor = null;
+  state.hintApply = false;
   state.gameOverWinnerId = null;
   state.cardDiagEnabled = false;
   state.playerNames = new Map([[1, "Brave_Panda"]]);
@@ -113,6 +115,26 @@ describe("ui rendering", () => {
     expect(document.getElementById("recommendations").children.length).toBeGreaterThan(0);
   });
 
+  it("selects the hinted cards when the hint button was pressed", () => {
+    renderHand(document.getElementById("hand"), ["S3", "H3", "C9"]);
+    state.hintApply = true;
+    handleServerMessage({
+      type: "Hints",
+      data: {
+        cursor: 1,
+        hints: [
+          { cards: ["C9"], play: { kind: "Single", main_rank: "Nine", size: 1, substitutions: [] } },
+          { cards: ["S3", "H3"], play: { kind: "Pair", main_rank: "Three", size: 2, substitutions: [] } },
+        ],
+      },
+    });
+
+    expect(selectedCards()).toEqual(["S3", "H3"]);
+    expect(state.hintCursor).toBe(1);
+    expect(document.querySelectorAll(".recommend-chip").length).toBe(2);
+    expect(document.querySelector(".recommend-chip.active").textContent).toContain("对子");
+  });
+
   it("shows waiting status after joined not started", () => {
     state.connected = true;
     handleServerMessage({